@@ -0,0 +1,72 @@
+mod datasets_paths;
+mod utils;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId};
+use milli::update::Settings;
+use milli::Filter;
+use utils::Conf;
+
+#[global_allocator]
+static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+fn base_conf(builder: &mut Settings) {
+    let faceted_fields = ["released-timestamp", "duration-float", "genre", "country", "artist"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    builder.set_filterable_fields(faceted_fields);
+}
+
+const CONF: Conf = Conf {
+    database_name: "filter_prefetch.mmdb",
+    dataset: datasets_paths::SMOL_SONGS,
+    configure: base_conf,
+    primary_key: Some("id"),
+    ..Conf::BASE
+};
+
+fn bench_filter_prefetch(c: &mut criterion::Criterion) {
+    let index = utils::base_setup(&CONF);
+
+    let artists = ["David Bowie", "Michael Jackson", "Thelonious Monk", "Charles Mingus"]
+        .iter()
+        .map(|artist| format!("artist = {artist:?}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    // one range per year from 1970 to 2010
+    let years = (0..40)
+        .map(|year| {
+            let start = 31536000 + year * 31556952;
+            format!("released-timestamp {start} TO {}", start + 31556952 / 2)
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let mixed = format!(
+        "({artists}) OR (genre = rock AND NOT country = \"United States\") OR duration-float 1.1 TO 1.5"
+    );
+
+    let mut group = c.benchmark_group("filter prefetch");
+    group.sample_size(10);
+    for (name, expression) in [("artists", &artists), ("years", &years), ("mixed", &mixed)] {
+        let filter = Filter::from_str(expression).unwrap().unwrap();
+
+        group.bench_with_input(BenchmarkId::new("sequential", name), &filter, |b, filter| {
+            b.iter(|| {
+                let rtxn = index.read_txn().unwrap();
+                filter.evaluate(&rtxn, &index).unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("prefetched", name), &filter, |b, filter| {
+            b.iter(|| {
+                let rtxn = index.read_txn().unwrap();
+                filter.evaluate_prefetched(&rtxn, &index).unwrap()
+            })
+        });
+    }
+    group.finish();
+
+    index.prepare_for_closing().wait();
+}
+
+criterion_group!(benches, bench_filter_prefetch);
+criterion_main!(benches);
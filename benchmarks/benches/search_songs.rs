@@ -144,11 +144,26 @@ fn bench_songs(c: &mut criterion::Criterion) {
             filter: Some("released-timestamp <= 946728000"), // year 2000
             ..BASE_CONF
         },
+        utils::Conf {
+            group_name: "basic filter: =",
+            filter: Some("released-timestamp = 946728000"), // year 2000
+            ..BASE_CONF
+        },
+        utils::Conf {
+            group_name: "basic filter: single value TO",
+            filter: Some("released-timestamp 946728000 TO 946728000"), // same as `=` through the levels
+            ..BASE_CONF
+        },
         utils::Conf {
             group_name: "basic filter: TO",
             filter: Some("released-timestamp 946728000 TO 1262347200"), // year 2000 to 2010
             ..BASE_CONF
         },
+        utils::Conf {
+            group_name: "filter: two ranges",
+            filter: Some("duration-float 1.1 TO 1.5 AND released-timestamp 946728000 TO 1262347200"), // the second range only visits the groups containing durations
+            ..BASE_CONF
+        },
         utils::Conf {
             group_name: "big filter",
             filter: Some("released-timestamp != 1262347200 AND (NOT (released-timestamp = 946728000)) AND (duration-float = 1 OR (duration-float 1.1 TO 1.5 AND released-timestamp > 315576000))"),
@@ -0,0 +1,136 @@
+//! Derives [`filter_parser::ToFilterCondition`] for the structs of optional fields, e.g.
+//!
+//! ```ignore
+//! #[derive(ToFilterCondition)]
+//! struct ProductFilter {
+//!     genre: Option<String>,
+//!     #[filter(rename = "price", op = ">=")]
+//!     min_price: Option<f64>,
+//!     #[filter(skip)]
+//!     page: usize,
+//! }
+//! ```
+//!
+//! The fields that are set are compared to the attribute of the same name with `=` and combined
+//! with `AND`, the fields that are not an `Option` are always set. The `filter` attribute
+//! changes the compared attribute with `rename` and the operator with `op`, one of `=`, `!=`,
+//! `>`, `>=`, `<` or `<=`. The values are converted with their `Display` implementation.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Type};
+
+#[proc_macro_derive(ToFilterCondition, attributes(filter))]
+pub fn derive_to_filter_condition(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match to_filter_condition(&input) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn to_filter_condition(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new(input.span(), "expected a struct with named fields")),
+        },
+        _ => return Err(Error::new(input.span(), "expected a struct with named fields")),
+    };
+
+    let mut conditions = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let mut attribute = LitStr::new(&ident.to_string(), ident.span());
+        let mut operator = LitStr::new("=", ident.span());
+        let mut skip = false;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("filter")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    attribute = meta.value()?.parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("op") {
+                    operator = meta.value()?.parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename`, `op` or `skip`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+
+        let condition = match operator.value().as_str() {
+            "=" => quote!(Equal),
+            "!=" => quote!(NotEqual),
+            ">" => quote!(GreaterThan),
+            ">=" => quote!(GreaterThanOrEqual),
+            "<" => quote!(LowerThan),
+            "<=" => quote!(LowerThanOrEqual),
+            _ => {
+                return Err(Error::new(
+                    operator.span(),
+                    "expected one of `=`, `!=`, `>`, `>=`, `<` or `<=`",
+                ))
+            }
+        };
+
+        let push = quote! {
+            let fid = ::filter_parser::Token::from(#attribute);
+            let value = ::filter_parser::Token::new(
+                fid.original_span(),
+                ::std::option::Option::Some(::std::string::ToString::to_string(value)),
+            );
+            conditions.push(::filter_parser::FilterCondition::Condition {
+                fid,
+                op: ::filter_parser::Condition::#condition(value),
+            });
+        };
+        conditions.push(if is_option(&field.ty) {
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    #push
+                }
+            }
+        } else {
+            quote! {
+                let value = &self.#ident;
+                #push
+            }
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::filter_parser::ToFilterCondition for #name #ty_generics #where_clause {
+            fn to_filter_condition(
+                &self,
+            ) -> ::std::option::Option<::filter_parser::FilterCondition<'static>> {
+                let mut conditions = ::std::vec::Vec::new();
+                #(#conditions)*
+                match conditions.len() {
+                    0 => ::std::option::Option::None,
+                    1 => conditions.pop(),
+                    _ => ::std::option::Option::Some(::filter_parser::FilterCondition::And(conditions)),
+                }
+            }
+        }
+    })
+}
+
+/// Returns `true` if the type is written `Option<T>`, whatever its path.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => {
+            path.path.segments.last().map_or(false, |segment| segment.ident == "Option")
+        }
+        _ => false,
+    }
+}
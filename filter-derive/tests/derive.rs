@@ -0,0 +1,56 @@
+use filter_derive::ToFilterCondition;
+use filter_parser::ToFilterCondition as _;
+
+#[derive(Default, ToFilterCondition)]
+struct ProductFilter {
+    genre: Option<String>,
+    #[filter(rename = "price", op = ">=")]
+    min_price: Option<f64>,
+    #[filter(rename = "price", op = "<")]
+    max_price: Option<f64>,
+    #[filter(op = "!=")]
+    brand: Option<&'static str>,
+    in_stock: bool,
+    #[filter(skip)]
+    #[allow(dead_code)]
+    page: usize,
+}
+
+#[derive(Default, ToFilterCondition)]
+struct OptionalFilter {
+    genre: Option<String>,
+    year: std::option::Option<i64>,
+}
+
+#[test]
+fn some_fields_set() {
+    let filter = ProductFilter {
+        genre: Some(String::from("sci-fi")),
+        min_price: Some(10.5),
+        ..Default::default()
+    };
+    insta::assert_display_snapshot!(
+        filter.to_filter_condition().unwrap(),
+        @"AND[{genre} = {sci-fi}, {price} >= {10.5}, {in_stock} = {false}, ]"
+    );
+
+    let filter = ProductFilter {
+        max_price: Some(100.0),
+        brand: Some("ACME corp"),
+        in_stock: true,
+        page: 3,
+        ..Default::default()
+    };
+    insta::assert_display_snapshot!(
+        filter.to_filter_condition().unwrap(),
+        @"AND[{price} < {100}, {brand} != {ACME corp}, {in_stock} = {true}, ]"
+    );
+}
+
+#[test]
+fn no_field_set() {
+    assert!(OptionalFilter::default().to_filter_condition().is_none());
+
+    let filter = OptionalFilter { year: Some(2020), ..Default::default() };
+    insta::assert_display_snapshot!(filter.to_filter_condition().unwrap(), @"{year} = {2020}");
+}
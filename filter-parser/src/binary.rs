@@ -0,0 +1,491 @@
+//! A compact binary format for the filters, to cache many parsed filters with little memory.
+//!
+//! The first byte is the version of the format, followed by the tree of conditions: one tag
+//! byte per node, the lengths as LEB128 varints and the strings as their length and UTF-8 bytes.
+
+use std::fmt;
+use std::ops::Bound;
+
+use crate::{
+    ArithmeticOperator, Condition, Expression, FilterCondition, Span, Token, TypeAnnotation,
+    ValueFunction,
+};
+
+/// The version of the format, to be increased on any change of the encoding.
+const FORMAT_VERSION: u8 = 5;
+
+const HAS_VALUE: u8 = 1 << 0;
+const PLACEHOLDER: u8 = 1 << 1;
+const QUOTED: u8 = 1 << 2;
+const HAS_EXPRESSION: u8 = 1 << 3;
+const ANNOTATION_SHIFT: u8 = 4;
+const ANNOTATION_MASK: u8 = 0b11;
+const HAS_FUNCTION: u8 = 1 << 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The filter was encoded with another version of the format.
+    UnsupportedVersion(u8),
+    /// The bytes are truncated or are not an encoded filter.
+    Malformed,
+}
+
+impl std::error::Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "The filter was encoded with the version {version} of the format, \
+                 but only the version {FORMAT_VERSION} is supported."
+            ),
+            Self::Malformed => write!(f, "The bytes are not a valid encoded filter."),
+        }
+    }
+}
+
+impl<'a> FilterCondition<'a> {
+    /// Encodes the filter in a compact binary format, see [`FilterCondition::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        encode_condition(self, &mut bytes);
+        bytes
+    }
+
+    /// Decodes a filter encoded with [`FilterCondition::to_bytes`], borrowing its strings
+    /// from `bytes`.
+    ///
+    /// The decoded filter is equal to the encoded one, but the positions of its tokens in the
+    /// original input are lost, an error on a token only points to the token itself.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder { bytes };
+        match decoder.byte()? {
+            FORMAT_VERSION => (),
+            version => return Err(DecodeError::UnsupportedVersion(version)),
+        }
+        let condition = decoder.condition()?;
+        if decoder.bytes.is_empty() {
+            Ok(condition)
+        } else {
+            Err(DecodeError::Malformed)
+        }
+    }
+}
+
+fn encode_condition(condition: &FilterCondition, bytes: &mut Vec<u8>) {
+    match condition {
+        FilterCondition::Not(condition) => {
+            bytes.push(0);
+            encode_condition(condition, bytes);
+        }
+        FilterCondition::Condition { fid, op } => {
+            bytes.push(1);
+            encode_token(fid, bytes);
+            encode_operator(op, bytes);
+        }
+        FilterCondition::In { fid, els } => {
+            bytes.push(2);
+            encode_token(fid, bytes);
+            encode_len(els.len(), bytes);
+            els.iter().for_each(|el| encode_token(el, bytes));
+        }
+        FilterCondition::HasAll { fid, els } => {
+            bytes.push(3);
+            encode_token(fid, bytes);
+            encode_len(els.len(), bytes);
+            els.iter().for_each(|el| encode_token(el, bytes));
+        }
+        FilterCondition::Or(conditions) => {
+            bytes.push(4);
+            encode_len(conditions.len(), bytes);
+            conditions.iter().for_each(|condition| encode_condition(condition, bytes));
+        }
+        FilterCondition::And(conditions) => {
+            bytes.push(5);
+            encode_len(conditions.len(), bytes);
+            conditions.iter().for_each(|condition| encode_condition(condition, bytes));
+        }
+        FilterCondition::GeoLowerThan { point, radius } => {
+            bytes.push(6);
+            point.iter().chain([radius]).for_each(|token| encode_token(token, bytes));
+        }
+        FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
+            bytes.push(7);
+            top_right_point
+                .iter()
+                .chain(bottom_left_point)
+                .for_each(|token| encode_token(token, bytes));
+        }
+        FilterCondition::Const(value) => bytes.push(8 + *value as u8),
+    }
+}
+
+fn encode_operator(operator: &Condition, bytes: &mut Vec<u8>) {
+    let (tag, tokens) = match operator {
+        Condition::GreaterThan(token) => (0, vec![token]),
+        Condition::GreaterThanOrEqual(token) => (1, vec![token]),
+        Condition::Equal(token) => (2, vec![token]),
+        Condition::NotEqual(token) => (3, vec![token]),
+        Condition::Null => (4, vec![]),
+        Condition::Empty => (5, vec![]),
+        Condition::Exists => (6, vec![]),
+        Condition::LowerThan(token) => (7, vec![token]),
+        Condition::LowerThanOrEqual(token) => (8, vec![token]),
+        Condition::Between { from, to } => (9, vec![from, to]),
+        Condition::BetweenStep { from, to, step } => (10, vec![from, to, step]),
+        Condition::Interval { from, to } => {
+            bytes.push(11);
+            encode_bound(from, bytes);
+            encode_bound(to, bytes);
+            return;
+        }
+        Condition::Like(token) => (12, vec![token]),
+        Condition::Window { from, limit } => (13, vec![from, limit]),
+        Condition::Integer => (14, vec![]),
+        Condition::Fractional => (15, vec![]),
+    };
+    bytes.push(tag);
+    tokens.into_iter().for_each(|token| encode_token(token, bytes));
+}
+
+fn encode_bound(bound: &Bound<Token>, bytes: &mut Vec<u8>) {
+    match bound {
+        Bound::Unbounded => bytes.push(0),
+        Bound::Included(token) => {
+            bytes.push(1);
+            encode_token(token, bytes);
+        }
+        Bound::Excluded(token) => {
+            bytes.push(2);
+            encode_token(token, bytes);
+        }
+    }
+}
+
+fn encode_token(token: &Token, bytes: &mut Vec<u8>) {
+    let annotation = match token.annotation {
+        None => 0,
+        Some(TypeAnnotation::F64) => 1,
+        Some(TypeAnnotation::I64) => 2,
+        Some(TypeAnnotation::String) => 3,
+    };
+    let flags = (token.value.is_some() as u8 * HAS_VALUE)
+        | (token.placeholder as u8 * PLACEHOLDER)
+        | (token.quoted as u8 * QUOTED)
+        | (token.expression.is_some() as u8 * HAS_EXPRESSION)
+        | (annotation << ANNOTATION_SHIFT)
+        | (token.function.is_some() as u8 * HAS_FUNCTION);
+
+    encode_str(token.span.fragment(), bytes);
+    bytes.push(flags);
+    if let Some(value) = &token.value {
+        encode_str(value, bytes);
+    }
+    if let Some(expression) = &token.expression {
+        encode_expression(expression, bytes);
+    }
+    if let Some(function) = token.function {
+        bytes.push(match function {
+            ValueFunction::Lower => 0,
+            ValueFunction::Upper => 1,
+            ValueFunction::Trim => 2,
+            ValueFunction::Length => 3,
+            ValueFunction::Count => 4,
+        });
+    }
+}
+
+fn encode_expression(expression: &Expression, bytes: &mut Vec<u8>) {
+    match expression {
+        Expression::Value(token) => {
+            bytes.push(0);
+            encode_token(token, bytes);
+        }
+        Expression::Operation { operator, token, left, right } => {
+            bytes.push(1);
+            bytes.push(match operator {
+                ArithmeticOperator::Add => 0,
+                ArithmeticOperator::Sub => 1,
+                ArithmeticOperator::Mul => 2,
+                ArithmeticOperator::Div => 3,
+            });
+            encode_token(token, bytes);
+            encode_expression(left, bytes);
+            encode_expression(right, bytes);
+        }
+    }
+}
+
+fn encode_str(s: &str, bytes: &mut Vec<u8>) {
+    encode_len(s.len(), bytes);
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+/// Encodes the length as a LEB128 varint, most lengths fit in a single byte.
+fn encode_len(mut len: usize, bytes: &mut Vec<u8>) {
+    while len >= 0x80 {
+        bytes.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    bytes.push(len as u8);
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let (byte, rest) = self.bytes.split_first().ok_or(DecodeError::Malformed)?;
+        self.bytes = rest;
+        Ok(*byte)
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let mut len = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            len |= ((byte & 0x7f) as usize).checked_shl(shift).ok_or(DecodeError::Malformed)?;
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+        }
+        Err(DecodeError::Malformed)
+    }
+
+    fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.len()?;
+        if len > self.bytes.len() {
+            return Err(DecodeError::Malformed);
+        }
+        let (s, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        std::str::from_utf8(s).map_err(|_| DecodeError::Malformed)
+    }
+
+    fn condition(&mut self) -> Result<FilterCondition<'a>, DecodeError> {
+        Ok(match self.byte()? {
+            0 => FilterCondition::Not(Box::new(self.condition()?)),
+            1 => FilterCondition::Condition { fid: self.token()?, op: self.operator()? },
+            2 => FilterCondition::In { fid: self.token()?, els: self.tokens()? },
+            3 => FilterCondition::HasAll { fid: self.token()?, els: self.tokens()? },
+            4 => FilterCondition::Or(self.conditions()?),
+            5 => FilterCondition::And(self.conditions()?),
+            6 => FilterCondition::GeoLowerThan {
+                point: [self.token()?, self.token()?],
+                radius: self.token()?,
+            },
+            7 => FilterCondition::GeoBoundingBox {
+                top_right_point: [self.token()?, self.token()?],
+                bottom_left_point: [self.token()?, self.token()?],
+            },
+            8 => FilterCondition::Const(false),
+            9 => FilterCondition::Const(true),
+            _ => return Err(DecodeError::Malformed),
+        })
+    }
+
+    fn conditions(&mut self) -> Result<Vec<FilterCondition<'a>>, DecodeError> {
+        let len = self.len()?;
+        // every condition takes at least one byte, this bounds the allocation
+        let mut conditions = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            conditions.push(self.condition()?);
+        }
+        Ok(conditions)
+    }
+
+    fn operator(&mut self) -> Result<Condition<'a>, DecodeError> {
+        Ok(match self.byte()? {
+            0 => Condition::GreaterThan(self.token()?),
+            1 => Condition::GreaterThanOrEqual(self.token()?),
+            2 => Condition::Equal(self.token()?),
+            3 => Condition::NotEqual(self.token()?),
+            4 => Condition::Null,
+            5 => Condition::Empty,
+            6 => Condition::Exists,
+            7 => Condition::LowerThan(self.token()?),
+            8 => Condition::LowerThanOrEqual(self.token()?),
+            9 => Condition::Between { from: self.token()?, to: self.token()? },
+            10 => Condition::BetweenStep {
+                from: self.token()?,
+                to: self.token()?,
+                step: self.token()?,
+            },
+            11 => Condition::Interval { from: self.bound()?, to: self.bound()? },
+            12 => Condition::Like(self.token()?),
+            13 => Condition::Window { from: self.token()?, limit: self.token()? },
+            14 => Condition::Integer,
+            15 => Condition::Fractional,
+            _ => return Err(DecodeError::Malformed),
+        })
+    }
+
+    fn bound(&mut self) -> Result<Bound<Token<'a>>, DecodeError> {
+        match self.byte()? {
+            0 => Ok(Bound::Unbounded),
+            1 => Ok(Bound::Included(self.token()?)),
+            2 => Ok(Bound::Excluded(self.token()?)),
+            _ => Err(DecodeError::Malformed),
+        }
+    }
+
+    fn token(&mut self) -> Result<Token<'a>, DecodeError> {
+        let fragment = self.str()?;
+        let flags = self.byte()?;
+        if flags >> 7 != 0 {
+            return Err(DecodeError::Malformed);
+        }
+        let annotation = match (flags >> ANNOTATION_SHIFT) & ANNOTATION_MASK {
+            0 => None,
+            1 => Some(TypeAnnotation::F64),
+            2 => Some(TypeAnnotation::I64),
+            3 => Some(TypeAnnotation::String),
+            _ => return Err(DecodeError::Malformed),
+        };
+        let value = match flags & HAS_VALUE {
+            0 => None,
+            _ => Some(self.str()?.to_string()),
+        };
+        let expression = match flags & HAS_EXPRESSION {
+            0 => None,
+            _ => Some(Box::new(self.expression()?)),
+        };
+        let function = match flags & HAS_FUNCTION {
+            0 => None,
+            _ => Some(match self.byte()? {
+                0 => ValueFunction::Lower,
+                1 => ValueFunction::Upper,
+                2 => ValueFunction::Trim,
+                3 => ValueFunction::Length,
+                4 => ValueFunction::Count,
+                _ => return Err(DecodeError::Malformed),
+            }),
+        };
+
+        Ok(Token {
+            span: Span::new_extra(fragment, fragment),
+            value,
+            annotation,
+            placeholder: flags & PLACEHOLDER != 0,
+            quoted: flags & QUOTED != 0,
+            expression,
+            function,
+        })
+    }
+
+    fn tokens(&mut self) -> Result<Vec<Token<'a>>, DecodeError> {
+        let len = self.len()?;
+        let mut tokens = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            tokens.push(self.token()?);
+        }
+        Ok(tokens)
+    }
+
+    fn expression(&mut self) -> Result<Expression<'a>, DecodeError> {
+        match self.byte()? {
+            0 => Ok(Expression::Value(self.token()?)),
+            1 => {
+                let operator = match self.byte()? {
+                    0 => ArithmeticOperator::Add,
+                    1 => ArithmeticOperator::Sub,
+                    2 => ArithmeticOperator::Mul,
+                    3 => ArithmeticOperator::Div,
+                    _ => return Err(DecodeError::Malformed),
+                };
+                let token = self.token()?;
+                let left = Box::new(self.expression()?);
+                let right = Box::new(self.expression()?);
+                Ok(Expression::Operation { operator, token, left, right })
+            }
+            _ => Err(DecodeError::Malformed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, FORMAT_VERSION};
+    use crate::{Condition, FilterCondition, Span, Token};
+
+    fn round_trip(input: &str) {
+        let filter = FilterCondition::parse(input).unwrap().unwrap();
+        let bytes = filter.to_bytes();
+        let decoded = FilterCondition::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, filter);
+        assert_eq!(decoded.to_string(), filter.to_string());
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn round_trip_every_condition() {
+        round_trip("channel = Ponce AND (dog_race IN [bernese, husky] OR age IS NOT NULL)");
+        round_trip("NOT name LIKE 'A%' OR tags HAS_ALL [a, \"b c\"] OR size IS EMPTY OR x EXISTS");
+        round_trip("price [1, *) AND price (*, 5] AND year 2000 TO 2020 STEP 5 AND age 1 TO 2");
+        round_trip("a > 1 OR a >= 2 OR a < 3 OR a <= 4 OR a != 5 OR a = f64:6 OR a = i64:7");
+        round_trip("_geoRadius(12, 13, 14) AND _geoBoundingBox([1, 2], [3, 4]) AND TRUE");
+        round_trip("zip = string:'01234' OR zip = \"01234\" OR zip = `1\\n2` OR FALSE");
+        round_trip("LOWER(name) = foo AND LENGTH('the title') > 5 OR TRIM(a) != b");
+        round_trip("tags BETWEEN_COUNT 2 TO 5 OR id FROM 100 LIMIT 50");
+        round_trip("price IS_INTEGER AND NOT price IS_FRACTIONAL");
+    }
+
+    #[test]
+    fn round_trip_placeholders_and_expressions() {
+        let filter = FilterCondition::parse("size = ? AND price < base * 2 + 1").unwrap().unwrap();
+        let bytes = filter.to_bytes();
+        let mut decoded = FilterCondition::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, filter);
+        assert_eq!(decoded.placeholders_mut().len(), 1);
+
+        let mut expressions = decoded.expressions_mut();
+        assert_eq!(expressions.len(), 1);
+        let expression = expressions[0].expression().unwrap();
+        assert_eq!(expression.evaluate(&|_: &str| Some(10.0)).unwrap(), 21.0);
+    }
+
+    #[test]
+    fn round_trip_deep_tree() {
+        let leaf = |depth: usize| FilterCondition::Condition {
+            fid: Token::from("field"),
+            op: Condition::Equal(Token::new(Span::new_extra("?", "?"), Some(depth.to_string()))),
+        };
+        let mut filter = leaf(0);
+        for depth in 1..500 {
+            filter = match depth % 3 {
+                0 => FilterCondition::Not(Box::new(filter)),
+                1 => FilterCondition::And(vec![filter, leaf(depth)]),
+                _ => FilterCondition::Or(vec![leaf(depth), filter, FilterCondition::Const(true)]),
+            };
+        }
+
+        let bytes = filter.to_bytes();
+        let decoded = FilterCondition::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, filter);
+        assert_eq!(decoded.to_string(), filter.to_string());
+    }
+
+    #[test]
+    fn reject_invalid_bytes() {
+        let filter = FilterCondition::parse("genre = horror OR year > 2000").unwrap().unwrap();
+        let mut bytes = filter.to_bytes();
+
+        // every truncation is detected
+        for len in 0..bytes.len() {
+            assert_eq!(FilterCondition::from_bytes(&bytes[..len]), Err(DecodeError::Malformed));
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(FilterCondition::from_bytes(&trailing), Err(DecodeError::Malformed));
+
+        bytes[0] = FORMAT_VERSION + 1;
+        let error = FilterCondition::from_bytes(&bytes).unwrap_err();
+        assert_eq!(error, DecodeError::UnsupportedVersion(FORMAT_VERSION + 1));
+        assert_eq!(
+            error.to_string(),
+            "The filter was encoded with the version 6 of the format, but only the version 5 is supported."
+        );
+    }
+}
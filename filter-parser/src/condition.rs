@@ -1,18 +1,33 @@
 //! BNF grammar:
 //!
 //! ```text
-//! condition      = value ("==" | ">" ...) value
-//! to             = value value TO value
+//! condition      = (key "<=>" WS* "NULL" WS*) | (key ("=" | "!=" | ">" | ">=" | "<" | "<=" | "<=>") annotated_value)
+//! key            = (function "(" value ")" WS*) | value
+//! to             = value value TO value (STEP value)?
+//! between_count  = value "BETWEEN_COUNT" WS+ value "TO" WS+ value
+//! window         = value "FROM" WS+ value "LIMIT" WS+ value
+//! integer        = value "IS_INTEGER"
+//! fractional     = value "IS_FRACTIONAL"
+//! interval       = value ("[" | "(") bound "," bound ("]" | ")")
+//! like           = value "LIKE" WS* value
+//! not_like       = value "NOT" WS+ "LIKE" WS* value
 //! ```
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::multispace1;
-use nom::combinator::cut;
-use nom::sequence::{terminated, tuple};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{consumed, cut, opt, value};
+use nom::sequence::{delimited, preceded, terminated, tuple};
 use Condition::*;
 
-use crate::{parse_value, FilterCondition, IResult, Span, Token};
+use crate::error::NomErrorExt;
+use crate::value::{parse_wildcard, word_exact};
+use crate::{
+    parse_annotated_value, parse_value, Error, ErrorKind, FilterCondition, IResult, Span, Token,
+    ValueFunction,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Condition<'a> {
@@ -26,14 +41,34 @@ pub enum Condition<'a> {
     LowerThan(Token<'a>),
     LowerThanOrEqual(Token<'a>),
     Between { from: Token<'a>, to: Token<'a> },
+    BetweenStep { from: Token<'a>, to: Token<'a>, step: Token<'a> },
+    Interval { from: Bound<Token<'a>>, to: Bound<Token<'a>> },
+    Like(Token<'a>),
+    Window { from: Token<'a>, limit: Token<'a> },
+    Integer,
+    Fractional,
 }
 
-/// condition      = value ("==" | ">" ...) value
+/// condition      = (key "<=>" WS* "NULL" WS*) | (key ("=" | "!=" | ">" | ">=" | "<" | "<=" | "<=>") annotated_value)
+///
+/// The null-safe equality `<=>` behaves like `=`, except that comparing with the `NULL`
+/// keyword matches the documents without the field, exactly like `NOT EXISTS`.
 pub fn parse_condition(input: Span) -> IResult<FilterCondition> {
-    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
-    let (input, (fid, op, value)) = tuple((parse_value, operator, cut(parse_value)))(input)?;
+    let operator = alt((tag("<=>"), tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, op)) = tuple((parse_key, operator))(input)?;
+
+    if *op.fragment() == "<=>" {
+        let null = delimited(multispace0, word_exact("NULL"), multispace0);
+        if let (input, Some(_)) = opt(null)(input)? {
+            let exists = FilterCondition::Condition { fid, op: Exists };
+            return Ok((input, FilterCondition::Not(Box::new(exists))));
+        }
+    }
+
+    let (input, value) = cut(parse_annotated_value)(input)?;
 
     let condition = match *op.fragment() {
+        "<=>" => FilterCondition::Condition { fid, op: Equal(value) },
         "<=" => FilterCondition::Condition { fid, op: LowerThanOrEqual(value) },
         ">=" => FilterCondition::Condition { fid, op: GreaterThanOrEqual(value) },
         "!=" => FilterCondition::Condition { fid, op: NotEqual(value) },
@@ -46,6 +81,44 @@ pub fn parse_condition(input: Span) -> IResult<FilterCondition> {
     Ok((input, condition))
 }
 
+/// key            = (function "(" value ")" WS*) | value
+/// function       = "LOWER" | "UPPER" | "TRIM" | "LENGTH"
+///
+/// The returned token is the attribute, with the function applied to its values before they are
+/// compared, e.g. `LOWER(name) = foo`. Its span covers the whole call.
+fn parse_key(input: Span) -> IResult<Token> {
+    let (start, _) = multispace0(input)?;
+    let function = alt((
+        value(ValueFunction::Lower, tag("LOWER(")),
+        value(ValueFunction::Upper, tag("UPPER(")),
+        value(ValueFunction::Trim, tag("TRIM(")),
+        value(ValueFunction::Length, tag("LENGTH(")),
+    ));
+
+    // if we were able to parse the name of the function, the call must be complete
+    let (input, (call, (function, attribute))) =
+        match consumed(tuple((function, cut(terminated(parse_value, char(')'))))))(start) {
+            Err(nom::Err::Error(_)) => return parse_value(input),
+            result => result.map_err(|e| {
+                e.map_fail(|failure| {
+                    if matches!(failure.kind(), ErrorKind::Char(_)) {
+                        Error::new_from_kind(start, ErrorKind::MissingClosingDelimiter(')'))
+                    } else {
+                        failure
+                    }
+                })
+            })?,
+        };
+    let (input, _) = multispace0(input)?;
+
+    let token = Token {
+        function: Some(function),
+        quoted: attribute.is_quoted(),
+        ..Token::new(call, Some(attribute.value().to_owned()))
+    };
+    Ok((input, token))
+}
+
 /// null          = value "IS" WS+ "NULL"
 pub fn parse_is_null(input: Span) -> IResult<FilterCondition> {
     let (input, key) = parse_value(input)?;
@@ -62,6 +135,22 @@ pub fn parse_is_not_null(input: Span) -> IResult<FilterCondition> {
     Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: Null }))))
 }
 
+/// integer        = value "IS_INTEGER"
+///
+/// Matches the documents with a number of the attribute without fractional part, e.g. `10.0`.
+pub fn parse_is_integer(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _)) = tuple((parse_value, word_exact("IS_INTEGER")))(input)?;
+    Ok((input, FilterCondition::Condition { fid, op: Integer }))
+}
+
+/// fractional     = value "IS_FRACTIONAL"
+///
+/// Matches the documents with a number of the attribute with a fractional part, e.g. `10.5`.
+pub fn parse_is_fractional(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _)) = tuple((parse_value, word_exact("IS_FRACTIONAL")))(input)?;
+    Ok((input, FilterCondition::Condition { fid, op: Fractional }))
+}
+
 /// empty          = value "IS" WS+ "EMPTY"
 pub fn parse_is_empty(input: Span) -> IResult<FilterCondition> {
     let (input, key) = parse_value(input)?;
@@ -78,24 +167,127 @@ pub fn parse_is_not_empty(input: Span) -> IResult<FilterCondition> {
     Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: Empty }))))
 }
 
-/// exist          = value "EXISTS"
+/// exist          = (wildcard | value) "EXISTS"
 pub fn parse_exists(input: Span) -> IResult<FilterCondition> {
-    let (input, key) = terminated(parse_value, tag("EXISTS"))(input)?;
+    let (input, key) = terminated(alt((parse_wildcard, parse_value)), tag("EXISTS"))(input)?;
 
     Ok((input, FilterCondition::Condition { fid: key, op: Exists }))
 }
-/// exist          = value "NOT" WS+ "EXISTS"
+/// exist          = (wildcard | value) "NOT" WS+ "EXISTS"
 pub fn parse_not_exists(input: Span) -> IResult<FilterCondition> {
-    let (input, key) = parse_value(input)?;
+    let (input, key) = alt((parse_wildcard, parse_value))(input)?;
 
     let (input, _) = tuple((tag("NOT"), multispace1, tag("EXISTS")))(input)?;
     Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: Exists }))))
 }
 
-/// to             = value value "TO" WS+ value
+/// like           = value "LIKE" WS* value
+///
+/// In the pattern `%` matches any sequence of characters, `_` matches exactly one character
+/// and `\` makes the next character literal, e.g. `name LIKE "A%"` or `code LIKE "100\%"`.
+pub fn parse_like(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, pattern)) =
+        tuple((parse_value, terminated(word_exact("LIKE"), multispace0), cut(parse_value)))(input)?;
+    Ok((input, FilterCondition::Condition { fid, op: Like(pattern) }))
+}
+
+/// not_like       = value "NOT" WS+ "LIKE" WS* value
+pub fn parse_not_like(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, _, _, pattern)) = tuple((
+        parse_value,
+        word_exact("NOT"),
+        multispace1,
+        terminated(word_exact("LIKE"), multispace0),
+        cut(parse_value),
+    ))(input)?;
+    Ok((
+        input,
+        FilterCondition::Not(Box::new(FilterCondition::Condition { fid, op: Like(pattern) })),
+    ))
+}
+
+/// to             = value value "TO" WS+ value ("STEP" WS+ value)?
 pub fn parse_to(input: Span) -> IResult<FilterCondition> {
     let (input, (key, from, _, _, to)) =
         tuple((parse_value, parse_value, tag("TO"), multispace1, cut(parse_value)))(input)?;
+    let (input, step) = opt(preceded(tuple((tag("STEP"), multispace1)), cut(parse_value)))(input)?;
+
+    let op = match step {
+        Some(step) => BetweenStep { from, to, step },
+        None => Between { from, to },
+    };
+    Ok((input, FilterCondition::Condition { fid: key, op }))
+}
+
+/// between_count  = value "BETWEEN_COUNT" WS+ value "TO" WS+ value
+///
+/// Matches the documents with a number of distinct values of the attribute within the bounds,
+/// e.g. `tags BETWEEN_COUNT 2 TO 5`. The attribute is returned with the `COUNT` function.
+pub fn parse_between_count(input: Span) -> IResult<FilterCondition> {
+    let (input, (key, _, _, from, _, _, to)) = tuple((
+        parse_value,
+        word_exact("BETWEEN_COUNT"),
+        multispace1,
+        cut(parse_value),
+        cut(tag("TO")),
+        multispace1,
+        cut(parse_value),
+    ))(input)?;
+    let fid = Token { function: Some(ValueFunction::Count), ..key };
+    Ok((input, FilterCondition::Condition { fid, op: Between { from, to } }))
+}
+
+/// window         = value "FROM" WS+ value "LIMIT" WS+ value
+///
+/// A window of `LIMIT` integers starting at `FROM`, e.g. `id FROM 100 LIMIT 50` matches the ids
+/// in `[100, 150)`. The bounds are computed when the filter is evaluated.
+pub fn parse_window(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, _, from, _, _, limit)) = tuple((
+        parse_value,
+        word_exact("FROM"),
+        multispace1,
+        cut(parse_value),
+        cut(word_exact("LIMIT")),
+        multispace1,
+        cut(parse_value),
+    ))(input)?;
+    Ok((input, FilterCondition::Condition { fid, op: Window { from, limit } }))
+}
+
+/// interval       = value ("[" | "(") bound "," bound ("]" | ")")
+///
+/// `[` and `]` include their bound while `(` and `)` exclude it, e.g. `price [10, 20)`
+/// matches `price >= 10 AND price < 20`.
+pub fn parse_interval(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, open, from, _, to, close)) = tuple((
+        parse_value,
+        alt((char('['), char('('))),
+        parse_interval_bound,
+        char(','),
+        parse_interval_bound,
+        terminated(alt((char(']'), char(')'))), multispace0),
+    ))(input)?;
+
+    let from = match from {
+        Some(from) if open == '[' => Included(from),
+        Some(from) => Excluded(from),
+        None => Unbounded,
+    };
+    let to = match to {
+        Some(to) if close == ']' => Included(to),
+        Some(to) => Excluded(to),
+        None => Unbounded,
+    };
+    Ok((input, FilterCondition::Condition { fid, op: Interval { from, to } }))
+}
 
-    Ok((input, FilterCondition::Condition { fid: key, op: Between { from, to } }))
+/// bound          = WS* ("*" WS* | value)?
+///
+/// A `*` or a missing value leaves this side of the interval unbounded.
+fn parse_interval_bound(input: Span) -> IResult<Option<Token>> {
+    let (input, _) = multispace0(input)?;
+    match terminated(tag::<_, _, crate::Error>("*"), multispace0)(input) {
+        Ok((input, _)) => Ok((input, None)),
+        Err(_) => opt(parse_value)(input),
+    }
 }
@@ -3,7 +3,7 @@ use std::fmt::Display;
 use nom::error::{self, ParseError};
 use nom::Parser;
 
-use crate::{IResult, Span};
+use crate::{IResult, Span, TypeAnnotation};
 
 pub trait NomErrorExt<E> {
     fn is_failure(&self) -> bool;
@@ -69,6 +69,7 @@ pub enum ErrorKind<'a> {
     InOpeningBracket,
     InClosingBracket,
     NonFiniteFloat,
+    InvalidAnnotatedValue(TypeAnnotation),
     InExpectedValue(ExpectedValueKind),
     ReservedKeyword(String),
     MissingClosingDelimiter(char),
@@ -181,6 +182,9 @@ impl<'a> Display for Error<'a> {
             ErrorKind::NonFiniteFloat => {
                 writeln!(f, "Non finite floats are not supported")?
             }
+            ErrorKind::InvalidAnnotatedValue(annotation) => {
+                writeln!(f, "Value `{}` is not a valid `{}`.", escaped_input, annotation)?
+            }
             ErrorKind::InExpectedValue(ExpectedValueKind::ReservedKeyword) => {
                 writeln!(f, "Expected only comma-separated field names inside `IN[..]` but instead found `{escaped_input}`, which is a keyword. To use `{escaped_input}` as a field name or a value, surround it by quotes.")?
             }
@@ -0,0 +1,163 @@
+//! BNF grammar:
+//!
+//! ```text
+//! expression     = term (("+" | "-") WS* term)*
+//! term           = value (("*" | "/") WS* value)*
+//! ```
+
+use std::fmt;
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::combinator::cut;
+use nom::sequence::terminated;
+
+use crate::{parse_value, Error, IResult, Span, Token};
+
+/// An arithmetic expression over numbers and named parameters, e.g. `base * 2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression<'a> {
+    /// A number, or the name of a parameter when it isn't a number.
+    Value(Token<'a>),
+    Operation {
+        operator: ArithmeticOperator,
+        /// The operator in the original input.
+        token: Token<'a>,
+        left: Box<Expression<'a>>,
+        right: Box<Expression<'a>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug)]
+pub enum ExpressionError {
+    DivisionByZero,
+    NonFiniteResult,
+    UnknownParameter(String),
+}
+
+impl std::error::Error for ExpressionError {}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DivisionByZero => write!(f, "Division by zero in the expression."),
+            Self::NonFiniteResult => {
+                write!(f, "The expression doesn't compute to a finite number.")
+            }
+            Self::UnknownParameter(name) => {
+                write!(f, "Parameter `{name}` is not a number and has not been provided.")
+            }
+        }
+    }
+}
+
+impl<'a> Expression<'a> {
+    /// Returns `true` if the expression refers to values that are not numbers.
+    pub fn has_parameters(&self) -> bool {
+        match self {
+            Expression::Value(token) => token.parse_finite_float().is_err(),
+            Expression::Operation { left, right, .. } => {
+                left.has_parameters() || right.has_parameters()
+            }
+        }
+    }
+
+    /// Computes the expression, retrieving the value of the named parameters with `parameter`.
+    pub fn evaluate(&self, parameter: &impl Fn(&str) -> Option<f64>) -> Result<f64, Error<'a>> {
+        match self {
+            Expression::Value(token) => match token.parse_finite_float() {
+                Ok(number) => Ok(number),
+                Err(_) => parameter(token.value()).ok_or_else(|| {
+                    token.as_external_error(ExpressionError::UnknownParameter(
+                        token.value().to_string(),
+                    ))
+                }),
+            },
+            Expression::Operation { operator, token, left, right } => {
+                let (left, right) = (left.evaluate(parameter)?, right.evaluate(parameter)?);
+                let result = match operator {
+                    ArithmeticOperator::Add => left + right,
+                    ArithmeticOperator::Sub => left - right,
+                    ArithmeticOperator::Mul => left * right,
+                    ArithmeticOperator::Div if right == 0.0 => {
+                        return Err(token.as_external_error(ExpressionError::DivisionByZero))
+                    }
+                    ArithmeticOperator::Div => left / right,
+                };
+                if result.is_finite() {
+                    Ok(result)
+                } else {
+                    Err(token.as_external_error(ExpressionError::NonFiniteResult))
+                }
+            }
+        }
+    }
+
+    /// The rightmost value of the expression.
+    pub(crate) fn last_value(&self) -> &Token<'a> {
+        match self {
+            Expression::Value(token) => token,
+            Expression::Operation { right, .. } => right.last_value(),
+        }
+    }
+}
+
+/// expression     = term (("+" | "-") WS* term)*
+pub fn parse_expression_value(input: Span) -> IResult<Expression> {
+    let (mut input, mut expression) = parse_term(input)?;
+    loop {
+        match terminated(alt((tag::<_, _, Error>("+"), tag("-"))), multispace0)(input) {
+            Ok((rest, symbol)) => {
+                let (rest, right) = cut(parse_term)(rest)?;
+                let operator = match *symbol.fragment() {
+                    "+" => ArithmeticOperator::Add,
+                    _ => ArithmeticOperator::Sub,
+                };
+                expression = Expression::Operation {
+                    operator,
+                    token: symbol.into(),
+                    left: Box::new(expression),
+                    right: Box::new(right),
+                };
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, expression)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// term           = value (("*" | "/") WS* value)*
+fn parse_term(input: Span) -> IResult<Expression> {
+    let (mut input, value) = parse_value(input)?;
+    let mut expression = Expression::Value(value);
+    loop {
+        match terminated(alt((tag::<_, _, Error>("*"), tag("/"))), multispace0)(input) {
+            Ok((rest, symbol)) => {
+                let (rest, right) = cut(parse_value)(rest)?;
+                let operator = match *symbol.fragment() {
+                    "*" => ArithmeticOperator::Mul,
+                    _ => ArithmeticOperator::Div,
+                };
+                expression = Expression::Operation {
+                    operator,
+                    token: symbol.into(),
+                    left: Box::new(expression),
+                    right: Box::new(Expression::Value(right)),
+                };
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, expression)),
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -5,18 +5,44 @@
 //! expression     = or
 //! or             = and ("OR" WS+ and)*
 //! and            = not ("AND" WS+ not)*
-//! not            = ("NOT" WS+ not) | primary
-//! primary        = (WS* "(" WS* expression WS* ")" WS*) | geoRadius | in | condition | exists | not_exists | to
+//! not            = ("NOT" WS+ not) | (WS* "-" (WS+ | &"(") primary) | primary
+//! primary        = (WS* "(" WS* expression WS* ")" WS*) | geoRadius | in_ranges | in | has_any | has_all | condition | exists | not_exists | between_count | window | integer | fractional | to | interval | like | not_like | const
+//! in_ranges      = value "NOT"? "IN" WS* "[" in_range ("," in_range)* ","? "]"
+//! in_range       = value ("TO" WS+ value)?
 //! in             = value "IN" WS* "[" value_list "]"
-//! condition      = value ("=" | "!=" | ">" | ">=" | "<" | "<=") value
-//! exists         = value "EXISTS"
-//! not_exists     = value "NOT" WS+ "EXISTS"
-//! to             = value value "TO" WS+ value
-//! value          = WS* ( word | singleQuoted | doubleQuoted) WS+
-//! value_list     = (value ("," value)* ","?)?
+//! has_any        = value "HAS_ANY" WS* "[" value_list "]"
+//! has_all        = value "HAS_ALL" WS* "[" value_list "]"
+//! condition      = (key "<=>" WS* "NULL" WS*) | (key ("=" | "!=" | ">" | ">=" | "<" | "<=" | "<=>") annotated_value)
+//! key            = (function "(" value ")" WS*) | value
+//! function       = "LOWER" | "UPPER" | "TRIM" | "LENGTH"
+//! exists         = (wildcard | value) "EXISTS"
+//! not_exists     = (wildcard | value) "NOT" WS+ "EXISTS"
+//! to             = value value "TO" WS+ value ("STEP" WS+ value)?
+//! between_count  = value "BETWEEN_COUNT" WS+ value "TO" WS+ value
+//! window         = value "FROM" WS+ value "LIMIT" WS+ value
+//! integer        = value "IS_INTEGER"
+//! fractional     = value "IS_FRACTIONAL"
+//! interval       = value ("[" | "(") bound "," bound ("]" | ")")
+//! like           = value "LIKE" WS* value
+//! not_like       = value "NOT" WS+ "LIKE" WS* value
+//! bound          = WS* ("*" WS* | value)?
+//! const          = WS* ("TRUE" | "FALSE") WS*
+//! value          = WS* ( word | singleQuoted | doubleQuoted | backQuoted) WS+
+//! unit_value     = number unit               a word made of a number and a unit, e.g. `5kb`
+//! unit           = (a..z | A..Z)+
+//! legacy_condition = value ":" (value | "[" value_list "]")
+//! annotated_value = WS* type_annotation? (computed_value | "?")
+//! type_annotation = ("f64" | "i64" | "string") ":"
+//! computed_value = term (("+" | "-") WS* term)*
+//! term           = value (("*" | "/") WS* value)*
+//! value_list     = (list_value ("," list_value)* ","?)?
+//! list_value     = WS* type_annotation? value
 //! singleQuoted   = "'" .* all but quotes "'"
 //! doubleQuoted   = "\"" .* all but double quotes "\""
+//! backQuoted     = "`" .* all but backticks "`"
+//! escape         = "\\" ("n" | "t" | "r" | "\\" | quote | ...)     unescaped in all the values
 //! word           = (alphanumeric | _ | - | .)+
+//! wildcard       = WS* word ".*" WS*      the sub-fields of an object, e.g. `author.*`
 //! geoRadius      = "_geoRadius(" WS* float WS* "," WS* float WS* "," float WS* ")"
 //! geoBoundingBox = "_geoBoundingBox([" WS * float WS* "," WS* float WS* "], [" WS* float WS* "," WS* float WS* "]")
 //! ```
@@ -40,30 +66,40 @@
 //! ```
 //!
 
+mod binary;
 mod condition;
 mod error;
+mod expression;
+mod syntax_tree;
 mod value;
 
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::ops::{Bound, Range};
 
-pub use condition::{parse_condition, parse_to, Condition};
+pub use binary::DecodeError;
 use condition::{
-    parse_exists, parse_is_empty, parse_is_not_empty, parse_is_not_null, parse_is_null,
-    parse_not_exists,
+    parse_between_count, parse_exists, parse_is_empty, parse_is_fractional, parse_is_integer,
+    parse_is_not_empty, parse_is_not_null, parse_is_null, parse_like, parse_not_exists,
+    parse_not_like, parse_window,
 };
+pub use condition::{parse_condition, parse_interval, parse_to, Condition};
 use error::{cut_with_err, ExpectedValueKind, NomErrorExt};
 pub use error::{Error, ErrorKind};
+pub use expression::{ArithmeticOperator, Expression, ExpressionError};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{char, multispace0};
-use nom::combinator::{cut, eof, map, opt};
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, eof, map, opt, peek};
 use nom::multi::{many0, separated_list1};
 use nom::number::complete::recognize_float;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::Finish;
 use nom_locate::LocatedSpan;
-pub(crate) use value::parse_value;
+pub use syntax_tree::{parse_syntax_tree, SyntaxNode};
 use value::word_exact;
+pub(crate) use value::{parse_annotated_value, parse_list_value, parse_value};
 
 pub type Span<'a> = LocatedSpan<&'a str, &'a str>;
 
@@ -71,6 +107,48 @@ type IResult<'a, Ret> = nom::IResult<Span<'a>, Ret, Error<'a>>;
 
 const MAX_FILTER_DEPTH: usize = 200;
 
+/// The type a value has been explicitly annotated with, e.g. `price = f64:9`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeAnnotation {
+    F64,
+    I64,
+    String,
+}
+
+impl std::fmt::Display for TypeAnnotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeAnnotation::F64 => f.write_str("f64"),
+            TypeAnnotation::I64 => f.write_str("i64"),
+            TypeAnnotation::String => f.write_str("string"),
+        }
+    }
+}
+
+/// A function applied to the values of an attribute before comparing them,
+/// e.g. `LOWER(name) = foo` or `LENGTH(title) > 5`. The `COUNT` function is the number of
+/// distinct values of the attribute, it is only written with `BETWEEN_COUNT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueFunction {
+    Lower,
+    Upper,
+    Trim,
+    Length,
+    Count,
+}
+
+impl std::fmt::Display for ValueFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueFunction::Lower => f.write_str("LOWER"),
+            ValueFunction::Upper => f.write_str("UPPER"),
+            ValueFunction::Trim => f.write_str("TRIM"),
+            ValueFunction::Length => f.write_str("LENGTH"),
+            ValueFunction::Count => f.write_str("COUNT"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq)]
 pub struct Token<'a> {
     /// The token in the original input, it should be used when possible.
@@ -78,6 +156,18 @@ pub struct Token<'a> {
     /// If you need to modify the original input you can use the `value` field
     /// to store your modified input.
     value: Option<String>,
+    /// The type the user explicitly gave to this value, if any.
+    annotation: Option<TypeAnnotation>,
+    /// Whether this value is a `?` placeholder that must be bound before evaluation.
+    placeholder: bool,
+    /// Whether this value was surrounded by quotes, e.g. `"01234"`.
+    quoted: bool,
+    /// The arithmetic expression computing this value once its named parameters are bound.
+    expression: Option<Box<Expression<'a>>>,
+    /// The function applied to the values of this attribute, e.g. `LOWER` for `LOWER(name)`.
+    function: Option<ValueFunction>,
+    /// The unit suffix of a word made of a number and a unit, e.g. `kb` for `5kb`.
+    unit: Option<Span<'a>>,
 }
 
 impl<'a> PartialEq for Token<'a> {
@@ -88,7 +178,16 @@ impl<'a> PartialEq for Token<'a> {
 
 impl<'a> Token<'a> {
     pub fn new(span: Span<'a>, value: Option<String>) -> Self {
-        Self { span, value }
+        Self {
+            span,
+            value,
+            annotation: None,
+            placeholder: false,
+            quoted: false,
+            expression: None,
+            function: None,
+            unit: None,
+        }
     }
 
     /// Returns the string contained in the span of the `Token`.
@@ -104,6 +203,58 @@ impl<'a> Token<'a> {
         self.value.as_ref().map_or(&self.span, |value| value)
     }
 
+    /// Returns the type the value was explicitly annotated with, e.g. `f64` for `f64:9`.
+    pub fn annotation(&self) -> Option<TypeAnnotation> {
+        self.annotation
+    }
+
+    /// Returns `true` if this value is a `?` placeholder that hasn't been bound yet.
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
+    /// Returns `true` if this value was surrounded by quotes, in which case it must
+    /// be considered as a string even if it looks like a number, e.g. `"01234"`.
+    pub fn is_quoted(&self) -> bool {
+        self.quoted
+    }
+
+    /// Returns the arithmetic expression with named parameters computing this value, if it hasn't
+    /// been bound yet.
+    pub fn expression(&self) -> Option<&Expression<'a>> {
+        self.expression.as_deref()
+    }
+
+    /// Returns the function applied to the values of this attribute before comparing them,
+    /// e.g. `LOWER` for `LOWER(name)`, the value of the token being the attribute.
+    pub fn function(&self) -> Option<ValueFunction> {
+        self.function
+    }
+
+    /// Returns the number and the unit of a word made of a number followed by a unit suffix,
+    /// e.g. `5` and `kb` for `5kb`. The value is still the whole word, see [`Token::bind`] to
+    /// replace it by the number in the base unit.
+    pub fn number_with_unit(&self) -> Option<(f64, &str)> {
+        let unit = self.unit?;
+        let number = &self.span.fragment()[..self.span.fragment().len() - unit.fragment().len()];
+        Some((number.parse().ok()?, unit.fragment()))
+    }
+
+    /// Binds a placeholder or an expression to its value, the token keeps pointing
+    /// to the `?` or the expression in the original input.
+    pub fn bind(&mut self, value: String) {
+        self.value = Some(value);
+        self.placeholder = false;
+        self.expression = None;
+        self.unit = None;
+    }
+
+    /// Replaces the attribute of this token, e.g. to resolve an alias, the token keeps pointing
+    /// to the attribute in the original input.
+    pub fn rename(&mut self, attribute: String) {
+        self.value = Some(attribute);
+    }
+
     pub fn as_external_error(&self, error: impl std::error::Error) -> Error<'a> {
         Error::new_from_external(self.span, error)
     }
@@ -125,7 +276,16 @@ impl<'a> Token<'a> {
 
 impl<'a> From<Span<'a>> for Token<'a> {
     fn from(span: Span<'a>) -> Self {
-        Self { span, value: None }
+        Self {
+            span,
+            value: None,
+            annotation: None,
+            placeholder: false,
+            quoted: false,
+            expression: None,
+            function: None,
+            unit: None,
+        }
     }
 }
 
@@ -136,18 +296,64 @@ impl<'a> From<&'a str> for Token<'a> {
     }
 }
 
+/// Converts a value, usually a struct of optional fields, into a filter.
+///
+/// It can be derived with the `filter-derive` crate, the fields that are set
+/// being compared to their attribute and combined with `AND`.
+pub trait ToFilterCondition {
+    /// Returns the filter, or `None` if it doesn't filter anything, e.g. no field is set.
+    fn to_filter_condition(&self) -> Option<FilterCondition<'static>>;
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FilterCondition<'a> {
     Not(Box<Self>),
     Condition { fid: Token<'a>, op: Condition<'a> },
     In { fid: Token<'a>, els: Vec<Token<'a>> },
+    HasAll { fid: Token<'a>, els: Vec<Token<'a>> },
     Or(Vec<Self>),
     And(Vec<Self>),
     GeoLowerThan { point: [Token<'a>; 2], radius: Token<'a> },
     GeoBoundingBox { top_right_point: [Token<'a>; 2], bottom_left_point: [Token<'a>; 2] },
+    Const(bool),
 }
 
 impl<'a> FilterCondition<'a> {
+    /// Returns the condition matching the documents that contain the attribute of this
+    /// comparison but don't match it, e.g. `- color = red`. Unlike a `NOT`, the documents
+    /// without the attribute are not matched.
+    ///
+    /// The conditions that are not on a single attribute are simply negated.
+    pub fn negate(self) -> Self {
+        let exists = |fid: &Token<'a>| FilterCondition::Condition {
+            fid: fid.clone(),
+            op: Condition::Exists,
+        };
+        match self {
+            FilterCondition::Not(condition) => match condition.leaf_attribute() {
+                Some(fid) => FilterCondition::And(vec![exists(fid), *condition]),
+                None => *condition,
+            },
+            condition => match condition.leaf_attribute() {
+                Some(fid) => FilterCondition::And(vec![
+                    exists(fid),
+                    FilterCondition::Not(Box::new(condition)),
+                ]),
+                None => FilterCondition::Not(Box::new(condition)),
+            },
+        }
+    }
+
+    /// Returns the attribute compared by this condition if it is a comparison.
+    fn leaf_attribute(&self) -> Option<&Token<'a>> {
+        match self {
+            FilterCondition::Condition { fid, .. }
+            | FilterCondition::In { fid, .. }
+            | FilterCondition::HasAll { fid, .. } => Some(fid),
+            _ => None,
+        }
+    }
+
     /// Returns the first token found at the specified depth, `None` if no token at this depth.
     pub fn token_at_depth(&self, depth: usize) -> Option<&Token> {
         match self {
@@ -176,11 +382,390 @@ impl<'a> FilterCondition<'a> {
     }
 
     pub fn parse(input: &'a str) -> Result<Option<Self>, Error> {
+        Self::parse_with_legacy_syntax(input, false)
+    }
+
+    /// Parses the filter, also accepting the legacy `field:value` syntax when `legacy_syntax` is set.
+    pub fn parse_with_legacy_syntax(
+        input: &'a str,
+        legacy_syntax: bool,
+    ) -> Result<Option<Self>, Error> {
         if input.trim().is_empty() {
             return Ok(None);
         }
         let span = Span::new_extra(input, input);
-        parse_filter(span).finish().map(|(_rem, output)| Some(output))
+        terminated(|input| parse_or(input, 0, legacy_syntax), eof)(span)
+            .finish()
+            .map(|(_rem, output)| Some(output))
+    }
+
+    /// Returns the `?` placeholders that are still to be bound, in the order they appear in the filter.
+    pub fn placeholders_mut(&mut self) -> Vec<&mut Token<'a>> {
+        let mut placeholders = self.operator_values_mut();
+        placeholders.retain(|value| value.is_placeholder());
+        placeholders
+    }
+
+    /// Returns the arithmetic expressions with named parameters that are still to be bound,
+    /// in the order they appear in the filter.
+    pub fn expressions_mut(&mut self) -> Vec<&mut Token<'a>> {
+        let mut expressions = self.operator_values_mut();
+        expressions.retain(|value| value.expression().is_some());
+        expressions
+    }
+
+    /// Returns the attributes compared by the filter, in the order they appear in the filter.
+    pub fn attributes_mut(&mut self) -> Vec<&mut Token<'a>> {
+        let mut attributes = Vec::new();
+        let mut stack = vec![self];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                FilterCondition::Not(condition) => stack.push(condition),
+                // the stack is LIFO, we push the conditions in reverse to keep them in order
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions.iter_mut().rev())
+                }
+                FilterCondition::Condition { fid, .. }
+                | FilterCondition::In { fid, .. }
+                | FilterCondition::HasAll { fid, .. } => attributes.push(fid),
+                FilterCondition::GeoLowerThan { .. }
+                | FilterCondition::GeoBoundingBox { .. }
+                | FilterCondition::Const(_) => (),
+            }
+        }
+        attributes
+    }
+
+    /// Returns the values written with a unit, e.g. `5kb`, that are compared by the `=`, `!=`,
+    /// `>`, `>=`, `<`, `<=` and `TO` operators, with the attribute they are compared with.
+    pub fn unit_values_mut(&mut self) -> Vec<(&Token<'a>, &mut Token<'a>)> {
+        let mut values = Vec::new();
+        let mut stack = vec![self];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                FilterCondition::Not(condition) => stack.push(condition),
+                // the stack is LIFO, we push the conditions in reverse to keep them in order
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions.iter_mut().rev())
+                }
+                FilterCondition::Condition { fid, op } => {
+                    let operands = match op {
+                        Condition::GreaterThan(value)
+                        | Condition::GreaterThanOrEqual(value)
+                        | Condition::Equal(value)
+                        | Condition::NotEqual(value)
+                        | Condition::LowerThan(value)
+                        | Condition::LowerThanOrEqual(value) => vec![value],
+                        Condition::Between { from, to } => vec![from, to],
+                        _ => continue,
+                    };
+                    let fid = &*fid;
+                    for value in operands {
+                        if value.number_with_unit().is_some() {
+                            values.push((fid, value));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        values
+    }
+
+    /// Returns the values compared by the `=`, `!=`, `>`, `>=`, `<` and `<=` operators.
+    fn operator_values_mut(&mut self) -> Vec<&mut Token<'a>> {
+        let mut values = Vec::new();
+        let mut stack = vec![self];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                FilterCondition::Not(condition) => stack.push(condition),
+                // the stack is LIFO, we push the conditions in reverse to keep them in order
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions.iter_mut().rev())
+                }
+                FilterCondition::Condition {
+                    op:
+                        Condition::GreaterThan(value)
+                        | Condition::GreaterThanOrEqual(value)
+                        | Condition::Equal(value)
+                        | Condition::NotEqual(value)
+                        | Condition::LowerThan(value)
+                        | Condition::LowerThanOrEqual(value),
+                    ..
+                } => values.push(value),
+                _ => (),
+            }
+        }
+        values
+    }
+
+    /// Folds the `TRUE` and `FALSE` constants of the filter, e.g. `a AND FALSE` becomes `FALSE`
+    /// and `a OR FALSE` becomes `a`.
+    pub fn simplify(self) -> Self {
+        match self {
+            FilterCondition::Not(condition) => match condition.simplify() {
+                FilterCondition::Const(value) => FilterCondition::Const(!value),
+                FilterCondition::Not(condition) => *condition,
+                condition => FilterCondition::Not(Box::new(condition)),
+            },
+            FilterCondition::And(conditions) => {
+                let mut simplified = Vec::new();
+                for condition in conditions {
+                    match condition.simplify() {
+                        FilterCondition::Const(true) => (),
+                        FilterCondition::Const(false) => return FilterCondition::Const(false),
+                        condition => simplified.push(condition),
+                    }
+                }
+                match simplified.len() {
+                    0 => FilterCondition::Const(true),
+                    1 => simplified.pop().unwrap(),
+                    _ => FilterCondition::And(simplified),
+                }
+            }
+            FilterCondition::Or(conditions) => {
+                let mut simplified = Vec::new();
+                for condition in conditions {
+                    match condition.simplify() {
+                        FilterCondition::Const(false) => (),
+                        FilterCondition::Const(true) => return FilterCondition::Const(true),
+                        condition => simplified.push(condition),
+                    }
+                }
+                match simplified.len() {
+                    0 => FilterCondition::Const(false),
+                    1 => simplified.pop().unwrap(),
+                    _ => FilterCondition::Or(simplified),
+                }
+            }
+            condition => condition,
+        }
+    }
+
+    /// Builds the half-open range `field [min, max)`, i.e. `field >= min AND field < max`, the
+    /// default of many range selectors. When `min` is above `max` the range can't contain any
+    /// value and `FALSE` is returned instead. The infinite bounds leave the range unbounded.
+    pub fn half_open_range(field: &'a str, min: f64, max: f64) -> Self {
+        if min > max || min.is_nan() || max.is_nan() {
+            return FilterCondition::Const(false);
+        }
+        let fid = Token::from(field);
+        let value = |number: f64| Token::new(fid.original_span(), Some(number.to_string()));
+        let from = if min.is_finite() { Bound::Included(value(min)) } else { Bound::Unbounded };
+        let to = if max.is_finite() { Bound::Excluded(value(max)) } else { Bound::Unbounded };
+        let op = Condition::Interval { from, to };
+        FilterCondition::Condition { fid, op }
+    }
+
+    /// Merges the numeric ranges on the same field of every `OR` into the minimal disjoint
+    /// intervals, e.g. `year > 2000 OR year > 2010` becomes `year (2000, *)` and
+    /// `price 1 TO 5 OR price [5, 10)` becomes `price [1, 10)`, which avoids scanning the
+    /// overlapping part of the ranges several times.
+    ///
+    /// Only the `>`, `>=`, `<`, `<=`, `TO` and interval conditions bounded by plain numbers are
+    /// merged, when they overlap or touch. The other conditions are kept as they are written.
+    pub fn merge_ranges(self) -> Self {
+        match self {
+            FilterCondition::Not(condition) => {
+                FilterCondition::Not(Box::new(condition.merge_ranges()))
+            }
+            FilterCondition::And(conditions) => {
+                FilterCondition::And(conditions.into_iter().map(Self::merge_ranges).collect())
+            }
+            FilterCondition::Or(conditions) => {
+                let conditions = conditions.into_iter().map(Self::merge_ranges).collect();
+                let mut merged = merge_or_ranges(conditions);
+                match merged.len() {
+                    1 => merged.pop().unwrap(),
+                    _ => FilterCondition::Or(merged),
+                }
+            }
+            condition => condition,
+        }
+    }
+
+    /// Returns `true` if the filter provably can't match any document, e.g. `year > 2010 AND
+    /// year < 2000`, by intersecting the numeric ranges on the same field of every `AND`.
+    ///
+    /// The analysis is conservative and returns `false` when it can't conclude: only the
+    /// ranges bounded by plain numbers are considered, and the `NOT` are not analyzed. It
+    /// assumes a document has a single value per field, a document with `"year": [1990, 2020]`
+    /// still matches `year > 2010 AND year < 2000`.
+    pub fn is_contradiction(&self) -> bool {
+        match self {
+            FilterCondition::Const(value) => !value,
+            FilterCondition::Condition { fid, op } => {
+                numeric_range(fid, op).map_or(false, |(from, to)| is_empty_range(&from, &to))
+            }
+            FilterCondition::Or(conditions) => conditions.iter().all(Self::is_contradiction),
+            FilterCondition::And(conditions) => {
+                if conditions.iter().any(Self::is_contradiction) {
+                    return true;
+                }
+                // the intersection of the ranges of each field
+                let mut ranges: BTreeMap<&str, (NumericBound, NumericBound)> = BTreeMap::new();
+                for condition in conditions {
+                    let (fid, (from, to)) = match condition {
+                        FilterCondition::Condition { fid, op } => match numeric_range(fid, op) {
+                            Some(range) => (fid.value(), range),
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    match ranges.get_mut(fid) {
+                        Some((lower, upper)) => {
+                            if cmp_lower_bounds(&from, lower) == Ordering::Greater {
+                                *lower = from;
+                            }
+                            if cmp_upper_bounds(&to, upper) == Ordering::Less {
+                                *upper = to;
+                            }
+                        }
+                        None => {
+                            ranges.insert(fid, (from, to));
+                        }
+                    }
+                }
+                ranges.values().any(|(from, to)| is_empty_range(from, to))
+            }
+            FilterCondition::Not(_)
+            | FilterCondition::In { .. }
+            | FilterCondition::HasAll { .. }
+            | FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoBoundingBox { .. } => false,
+        }
+    }
+
+    /// Returns the indices of the leaf conditions that can't change the result of the filter,
+    /// in the order of the leaves returned by [`FilterCondition::parse_with_spans`], so a UI
+    /// can warn about them.
+    ///
+    /// A leaf is unreachable when it is part of a contradiction, as reported by
+    /// [`FilterCondition::is_contradiction`], or when an `OR` contains a broader numeric range on
+    /// the same field, e.g. `year > 2010` in `year > 2000 OR year > 2010`. Of two identical
+    /// ranges, the second one is reported. The `NOT` are not analyzed.
+    pub fn unreachable_leaves(&self) -> Vec<usize> {
+        let mut unreachable = Vec::new();
+        self.collect_unreachable_leaves(&mut 0, &mut unreachable);
+        unreachable
+    }
+
+    fn collect_unreachable_leaves(&self, next_leaf: &mut usize, unreachable: &mut Vec<usize>) {
+        if self.is_contradiction() {
+            let count = self.leaves_count();
+            unreachable.extend(*next_leaf..*next_leaf + count);
+            *next_leaf += count;
+            return;
+        }
+        match self {
+            FilterCondition::Not(condition) => *next_leaf += condition.leaves_count(),
+            FilterCondition::Const(_) => (),
+            FilterCondition::And(conditions) => conditions
+                .iter()
+                .for_each(|condition| condition.collect_unreachable_leaves(next_leaf, unreachable)),
+            FilterCondition::Or(conditions) => {
+                for (position, condition) in conditions.iter().enumerate() {
+                    if is_subsumed_range(&conditions[..], position) {
+                        unreachable.push(*next_leaf);
+                        *next_leaf += 1;
+                    } else {
+                        condition.collect_unreachable_leaves(next_leaf, unreachable);
+                    }
+                }
+            }
+            FilterCondition::Condition { .. }
+            | FilterCondition::In { .. }
+            | FilterCondition::HasAll { .. }
+            | FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoBoundingBox { .. } => *next_leaf += 1,
+        }
+    }
+
+    /// The number of leaves, counted like in [`FilterCondition::parse_with_spans`].
+    fn leaves_count(&self) -> usize {
+        match self {
+            FilterCondition::Not(condition) => condition.leaves_count(),
+            FilterCondition::Const(_) => 0,
+            FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                conditions.iter().map(Self::leaves_count).sum()
+            }
+            FilterCondition::Condition { .. }
+            | FilterCondition::In { .. }
+            | FilterCondition::HasAll { .. }
+            | FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoBoundingBox { .. } => 1,
+        }
+    }
+
+    /// Parses the filter like [`FilterCondition::parse`] but also returns the byte range,
+    /// in the original input, of each leaf condition in the order they appear.
+    /// The `TRUE` and `FALSE` constants are not part of the returned ranges.
+    pub fn parse_with_spans(input: &'a str) -> Result<Option<(Self, Vec<Range<usize>>)>, Error> {
+        let condition = match Self::parse(input)? {
+            Some(condition) => condition,
+            None => return Ok(None),
+        };
+        let mut spans = Vec::new();
+        condition.leaves_spans(input, &mut spans);
+        Ok(Some((condition, spans)))
+    }
+
+    fn leaves_spans(&self, input: &'a str, spans: &mut Vec<Range<usize>>) {
+        match self {
+            FilterCondition::Not(condition) => condition.leaves_spans(input, spans),
+            FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                conditions.iter().for_each(|c| c.leaves_spans(input, spans))
+            }
+            leaf => spans.extend(leaf.leaf_span(input)),
+        }
+    }
+
+    /// Returns the byte range of this condition in the original input if it is a leaf,
+    /// i.e. neither a `NOT`, an `AND`, an `OR` nor a constant.
+    pub(crate) fn leaf_span(&self, input: &'a str) -> Option<Range<usize>> {
+        let start = match self {
+            FilterCondition::Not(_)
+            | FilterCondition::Const(_)
+            | FilterCondition::Or(_)
+            | FilterCondition::And(_) => return None,
+            FilterCondition::Condition { fid, .. }
+            | FilterCondition::In { fid, .. }
+            | FilterCondition::HasAll { fid, .. } => {
+                let offset = fid.original_span().location_offset();
+                // a quoted attribute starts at its opening quote
+                let offset = match input[..offset].chars().last() {
+                    Some('\'' | '"' | '`') => offset - 1,
+                    _ => offset,
+                };
+                // and an attribute in a function call at the name of the function, `COUNT` is
+                // only written as the `BETWEEN_COUNT` operator after the attribute
+                match fid.function() {
+                    Some(function) if function != ValueFunction::Count => {
+                        let call = format!("{function}(");
+                        match input[..offset].strip_suffix(call.as_str()) {
+                            Some(before) => before.len(),
+                            None => offset,
+                        }
+                    }
+                    _ => offset,
+                }
+            }
+            FilterCondition::GeoLowerThan { point, .. } => {
+                let offset = point[0].original_span().location_offset();
+                input[..offset].rfind("_geoRadius").unwrap_or(offset)
+            }
+            FilterCondition::GeoBoundingBox { top_right_point, .. } => {
+                let offset = top_right_point[0].original_span().location_offset();
+                input[..offset].rfind("_geoBoundingBox").unwrap_or(offset)
+            }
+        };
+
+        // the leaf is parsed again from its first character to find where it ends
+        let end = match parse_primary(Span::new_extra(&input[start..], input), 0, false) {
+            Ok((rest, _)) => input.len() - rest.fragment().len(),
+            Err(_) => input.len(),
+        };
+        Some(start..start + input[start..end].trim_end().len())
     }
 }
 
@@ -191,11 +776,11 @@ fn ws<'a, O>(
     delimited(multispace0, inner, multispace0)
 }
 
-/// value_list = (value ("," value)* ","?)?
+/// value_list = (list_value ("," list_value)* ","?)?
 fn parse_value_list(input: Span) -> IResult<Vec<Token>> {
-    let (input, first_value) = opt(parse_value)(input)?;
+    let (input, first_value) = opt(parse_list_value)(input)?;
     if let Some(first_value) = first_value {
-        let value_list_el_parser = preceded(ws(tag(",")), parse_value);
+        let value_list_el_parser = preceded(ws(tag(",")), parse_list_value);
 
         let (input, mut values) = many0(value_list_el_parser)(input)?;
         let (input, _) = opt(ws(tag(",")))(input)?;
@@ -209,7 +794,12 @@ fn parse_value_list(input: Span) -> IResult<Vec<Token>> {
 
 /// "IN" WS* "[" value_list "]"
 fn parse_in_body(input: Span) -> IResult<Vec<Token>> {
-    let (input, _) = ws(word_exact("IN"))(input)?;
+    parse_list_body("IN", input)
+}
+
+/// keyword WS* "[" value_list "]"
+fn parse_list_body<'a>(keyword: &'static str, input: Span<'a>) -> IResult<'a, Vec<Token<'a>>> {
+    let (input, _) = ws(word_exact(keyword))(input)?;
 
     // everything after `IN` can be a failure
     let (input, _) =
@@ -235,9 +825,34 @@ fn parse_in_body(input: Span) -> IResult<Vec<Token>> {
         }
     })(input)?;
 
+    // the whole list is rejected before being evaluated if one of its values is invalid
+    check_annotated_values(&content).map_err(nom::Err::Failure)?;
+
     Ok((input, content))
 }
 
+/// Returns an error on the first annotated value that is not a literal of its type, e.g. `i64:9.5`.
+fn check_annotated_values<'a: 'b, 'b>(
+    values: impl IntoIterator<Item = &'b Token<'a>>,
+) -> Result<(), Error<'a>> {
+    for value in values {
+        let annotation = match value.annotation() {
+            Some(annotation) => annotation,
+            None => continue,
+        };
+        let is_valid = match annotation {
+            TypeAnnotation::F64 => value.parse_finite_float().is_ok(),
+            TypeAnnotation::I64 => value.value().parse::<i64>().is_ok(),
+            TypeAnnotation::String => true,
+        };
+        if !is_valid {
+            let kind = ErrorKind::InvalidAnnotatedValue(annotation);
+            return Err(Error::new_from_kind(value.original_span(), kind));
+        }
+    }
+    Ok(())
+}
+
 /// in = value "IN" "[" value_list "]"
 fn parse_in(input: Span) -> IResult<FilterCondition> {
     let (input, value) = parse_value(input)?;
@@ -257,15 +872,84 @@ fn parse_not_in(input: Span) -> IResult<FilterCondition> {
     Ok((input, filter))
 }
 
+/// in_ranges = value "NOT"? "IN" "[" in_range ("," in_range)* "]"
+///
+/// A list containing at least one range, e.g. `year IN [1990 TO 1995, 2000 TO 2005]`, matches
+/// the numbers within any of its ranges or equal to any of its values, so it becomes the `OR`
+/// of these conditions, where the overlapping ranges are merged like by
+/// [`FilterCondition::merge_ranges`]. The lists without a range are left to `in` and `not_in`.
+fn parse_in_ranges(input: Span) -> IResult<FilterCondition> {
+    let (input, fid) = parse_value(input)?;
+    let (input, not) = opt(word_exact("NOT"))(input)?;
+    let (input, _) = tuple((ws(word_exact("IN")), tag("[")))(input)?;
+
+    let (input, ranges) = separated_list1(ws(tag(",")), parse_in_range)(input)?;
+    let (input, _) = tuple((opt(ws(tag(","))), ws(tag("]"))))(input)?;
+    if ranges.iter().all(|(_, to)| to.is_none()) {
+        return Err(nom::Err::Error(Error::new_from_kind(input, ErrorKind::InvalidPrimary)));
+    }
+    let values = ranges.iter().flat_map(|(from, to)| std::iter::once(from).chain(to));
+    check_annotated_values(values).map_err(nom::Err::Failure)?;
+
+    let conditions = ranges
+        .into_iter()
+        .map(|(from, to)| {
+            let op = match to {
+                Some(to) => Condition::Between { from, to },
+                None => Condition::Equal(from),
+            };
+            FilterCondition::Condition { fid: fid.clone(), op }
+        })
+        .collect();
+    let mut conditions = merge_or_ranges(conditions);
+    let filter = match conditions.len() {
+        1 => conditions.pop().unwrap(),
+        _ => FilterCondition::Or(conditions),
+    };
+    match not {
+        Some(_) => Ok((input, FilterCondition::Not(Box::new(filter)))),
+        None => Ok((input, filter)),
+    }
+}
+
+/// in_range = value ("TO" value)?
+fn parse_in_range(input: Span) -> IResult<(Token, Option<Token>)> {
+    let (input, from) = parse_list_value(input)?;
+    let to = preceded(tuple((tag("TO"), multispace1)), cut(parse_value));
+    let (input, to) = opt(to)(input)?;
+    Ok((input, (from, to)))
+}
+
+/// has_any = value "HAS_ANY" "[" value_list "]"
+/// Matching any of the values is exactly what `IN` does, so we reuse it.
+fn parse_has_any(input: Span) -> IResult<FilterCondition> {
+    let (input, value) = parse_value(input)?;
+    let (input, content) = parse_list_body("HAS_ANY", input)?;
+
+    let filter = FilterCondition::In { fid: value, els: content };
+    Ok((input, filter))
+}
+
+/// has_all = value "HAS_ALL" "[" value_list "]"
+fn parse_has_all(input: Span) -> IResult<FilterCondition> {
+    let (input, value) = parse_value(input)?;
+    let (input, content) = parse_list_body("HAS_ALL", input)?;
+
+    let filter = FilterCondition::HasAll { fid: value, els: content };
+    Ok((input, filter))
+}
+
 /// or             = and ("OR" and)
-fn parse_or(input: Span, depth: usize) -> IResult<FilterCondition> {
+fn parse_or(input: Span, depth: usize, legacy: bool) -> IResult<FilterCondition> {
     if depth > MAX_FILTER_DEPTH {
         return Err(nom::Err::Error(Error::new_from_kind(input, ErrorKind::DepthLimitReached)));
     }
-    let (input, first_filter) = parse_and(input, depth + 1)?;
+    let (input, first_filter) = parse_and(input, depth + 1, legacy)?;
     // if we found a `OR` then we MUST find something next
-    let (input, mut ors) =
-        many0(preceded(ws(word_exact("OR")), cut(|input| parse_and(input, depth + 1))))(input)?;
+    let (input, mut ors) = many0(preceded(
+        ws(word_exact("OR")),
+        cut(|input| parse_and(input, depth + 1, legacy)),
+    ))(input)?;
 
     let filter = if ors.is_empty() {
         first_filter
@@ -278,14 +962,16 @@ fn parse_or(input: Span, depth: usize) -> IResult<FilterCondition> {
 }
 
 /// and            = not ("AND" not)*
-fn parse_and(input: Span, depth: usize) -> IResult<FilterCondition> {
+fn parse_and(input: Span, depth: usize, legacy: bool) -> IResult<FilterCondition> {
     if depth > MAX_FILTER_DEPTH {
         return Err(nom::Err::Error(Error::new_from_kind(input, ErrorKind::DepthLimitReached)));
     }
-    let (input, first_filter) = parse_not(input, depth + 1)?;
+    let (input, first_filter) = parse_not(input, depth + 1, legacy)?;
     // if we found a `AND` then we MUST find something next
-    let (input, mut ands) =
-        many0(preceded(ws(word_exact("AND")), cut(|input| parse_not(input, depth + 1))))(input)?;
+    let (input, mut ands) = many0(preceded(
+        ws(word_exact("AND")),
+        cut(|input| parse_not(input, depth + 1, legacy)),
+    ))(input)?;
 
     let filter = if ands.is_empty() {
         first_filter
@@ -297,22 +983,31 @@ fn parse_and(input: Span, depth: usize) -> IResult<FilterCondition> {
     Ok((input, filter))
 }
 
-/// not            = ("NOT" WS+ not) | primary
+/// not            = ("NOT" WS+ not) | (WS* "-" (WS+ | &"(") primary) | primary
 /// We can have multiple consecutive not, eg: `NOT NOT channel = mv`.
 /// If we parse a `NOT` we MUST parse something behind.
-fn parse_not(input: Span, depth: usize) -> IResult<FilterCondition> {
+/// A `-` only negates the comparison it prefixes, see [`FilterCondition::negate`]. It must be
+/// followed by a whitespace or a `(`, otherwise it is part of the attribute, e.g. `-score > 3`.
+fn parse_not(input: Span, depth: usize, legacy: bool) -> IResult<FilterCondition> {
     if depth > MAX_FILTER_DEPTH {
         return Err(nom::Err::Error(Error::new_from_kind(input, ErrorKind::DepthLimitReached)));
     }
     alt((
         map(
-            preceded(ws(word_exact("NOT")), cut(|input| parse_not(input, depth + 1))),
+            preceded(ws(word_exact("NOT")), cut(|input| parse_not(input, depth + 1, legacy))),
             |e| match e {
                 FilterCondition::Not(e) => *e,
                 _ => FilterCondition::Not(Box::new(e)),
             },
         ),
-        |input| parse_primary(input, depth + 1),
+        map(
+            preceded(
+                tuple((multispace0, char('-'), peek(alt((multispace1, tag("(")))))),
+                cut(|input| parse_primary(input, depth + 1, legacy)),
+            ),
+            FilterCondition::negate,
+        ),
+        |input| parse_primary(input, depth + 1, legacy),
     ))(input)
 }
 
@@ -426,32 +1121,250 @@ fn parse_error_reserved_keyword(input: Span) -> IResult<FilterCondition> {
     }
 }
 
-/// primary        = (WS* "(" WS* expression WS* ")" WS*) | geoRadius | condition | exists | not_exists | to
-fn parse_primary(input: Span, depth: usize) -> IResult<FilterCondition> {
+/// legacy_condition = value ":" (value | "[" value_list "]")
+/// Only accepted when parsing with the legacy syntax, `field:value` is translated to `field = value`.
+fn parse_legacy_condition(input: Span) -> IResult<FilterCondition> {
+    let (input, fid) = terminated(parse_value, char(':'))(input)?;
+
+    alt((
+        map(delimited(ws(char('[')), cut(parse_value_list), cut(ws(char(']')))), |els| {
+            FilterCondition::In { fid: fid.clone(), els }
+        }),
+        map(cut(parse_value), |value| FilterCondition::Condition {
+            fid: fid.clone(),
+            op: Condition::Equal(value),
+        }),
+    ))(input)
+}
+
+/// const          = WS* ("TRUE" | "FALSE") WS*
+fn parse_const(input: Span) -> IResult<FilterCondition> {
+    alt((
+        map(ws(word_exact("TRUE")), |_| FilterCondition::Const(true)),
+        map(ws(word_exact("FALSE")), |_| FilterCondition::Const(false)),
+    ))(input)
+}
+
+/// A bound of a numeric range, with the token it was written with.
+type NumericBound<'a> = Bound<(f64, Token<'a>)>;
+
+/// Returns the numeric range matched by the condition, if it is a range bounded by plain numbers.
+///
+/// The conditions on a function of the attribute, e.g. `LOWER(name) > 5`, are not compared
+/// to the values of the attribute itself and have no range.
+fn numeric_range<'a>(
+    fid: &Token<'a>,
+    op: &Condition<'a>,
+) -> Option<(NumericBound<'a>, NumericBound<'a>)> {
+    if fid.function().is_some() {
+        return None;
+    }
+    let number = |token: &Token<'a>| {
+        if token.is_placeholder() || token.expression().is_some() || token.annotation().is_some() {
+            return None;
+        }
+        token.parse_finite_float().ok().map(|number| (number, token.clone()))
+    };
+    let bound = |bound: &Bound<Token<'a>>| match bound {
+        Bound::Included(token) => number(token).map(Bound::Included),
+        Bound::Excluded(token) => number(token).map(Bound::Excluded),
+        Bound::Unbounded => Some(Bound::Unbounded),
+    };
+
+    Some(match op {
+        Condition::GreaterThan(token) => (Bound::Excluded(number(token)?), Bound::Unbounded),
+        Condition::GreaterThanOrEqual(token) => (Bound::Included(number(token)?), Bound::Unbounded),
+        Condition::LowerThan(token) => (Bound::Unbounded, Bound::Excluded(number(token)?)),
+        Condition::LowerThanOrEqual(token) => (Bound::Unbounded, Bound::Included(number(token)?)),
+        Condition::Between { from, to } => {
+            (Bound::Included(number(from)?), Bound::Included(number(to)?))
+        }
+        Condition::Interval { from, to } => (bound(from)?, bound(to)?),
+        _ => return None,
+    })
+}
+
+/// Returns `true` if no number is between `from` and `to`.
+fn is_empty_range(from: &NumericBound, to: &NumericBound) -> bool {
+    match (from, to) {
+        (Bound::Included((from, _)), Bound::Included((to, _))) => from > to,
+        (Bound::Included((from, _)) | Bound::Excluded((from, _)), Bound::Excluded((to, _)))
+        | (Bound::Excluded((from, _)), Bound::Included((to, _))) => from >= to,
+        _ => false,
+    }
+}
+
+/// Orders the lower bounds of ranges, `[1` starts before `(1`.
+fn cmp_lower_bounds(left: &NumericBound, right: &NumericBound) -> Ordering {
+    let key = |bound: &NumericBound| match bound {
+        Bound::Unbounded => (f64::NEG_INFINITY, 0),
+        Bound::Included((number, _)) => (*number, 1),
+        Bound::Excluded((number, _)) => (*number, 2),
+    };
+    let ((left, left_rank), (right, right_rank)) = (key(left), key(right));
+    left.total_cmp(&right).then(left_rank.cmp(&right_rank))
+}
+
+/// Orders the upper bounds of ranges, `1)` ends before `1]`.
+fn cmp_upper_bounds(left: &NumericBound, right: &NumericBound) -> Ordering {
+    let key = |bound: &NumericBound| match bound {
+        Bound::Excluded((number, _)) => (*number, 0),
+        Bound::Included((number, _)) => (*number, 1),
+        Bound::Unbounded => (f64::INFINITY, 2),
+    };
+    let ((left, left_rank), (right, right_rank)) = (key(left), key(right));
+    left.total_cmp(&right).then(left_rank.cmp(&right_rank))
+}
+
+/// Returns `true` if a range ending with `to` overlaps or touches a range starting with `from`,
+/// when the second range doesn't start before the first one.
+fn ranges_touch(to: &NumericBound, from: &NumericBound) -> bool {
+    match (to, from) {
+        (Bound::Unbounded, _) | (_, Bound::Unbounded) => true,
+        (Bound::Excluded((to, _)), Bound::Excluded((from, _))) => from < to,
+        (
+            Bound::Included((to, _)) | Bound::Excluded((to, _)),
+            Bound::Included((from, _)) | Bound::Excluded((from, _)),
+        ) => from <= to,
+    }
+}
+
+/// Returns `true` if the operand of the `OR` at `position` is a numeric range contained in
+/// the range of another operand on the same field, or equal to the range of a previous one.
+fn is_subsumed_range<'a>(conditions: &[FilterCondition<'a>], position: usize) -> bool {
+    let range = |condition: &FilterCondition<'a>| match condition {
+        FilterCondition::Condition { fid, op } => {
+            numeric_range(fid, op).map(|(from, to)| (fid.value().to_owned(), from, to))
+        }
+        _ => None,
+    };
+    let (fid, from, to) = match range(&conditions[position]) {
+        Some(range) => range,
+        None => return false,
+    };
+
+    conditions.iter().enumerate().any(|(other_position, other)| {
+        let (other_fid, other_from, other_to) = match range(other) {
+            Some(range) if other_position != position => range,
+            _ => return false,
+        };
+        if other_fid != fid || is_empty_range(&other_from, &other_to) {
+            return false;
+        }
+        let lower = cmp_lower_bounds(&other_from, &from);
+        let upper = cmp_upper_bounds(&other_to, &to);
+        if lower == Ordering::Equal && upper == Ordering::Equal {
+            other_position < position
+        } else {
+            lower != Ordering::Greater && upper != Ordering::Less
+        }
+    })
+}
+
+/// Merges the numeric ranges on the same field of the operands of an `OR`. A merged interval
+/// takes the place of the first range it contains, the ranges that are not merged are kept.
+fn merge_or_ranges(conditions: Vec<FilterCondition>) -> Vec<FilterCondition> {
+    // the ranges of each field, with the position of their condition in the `OR`
+    let mut ranges: BTreeMap<&str, Vec<(usize, NumericBound, NumericBound)>> = BTreeMap::new();
+    for (position, condition) in conditions.iter().enumerate() {
+        if let FilterCondition::Condition { fid, op } = condition {
+            // an empty range would wrongly extend the ranges it is merged with
+            if let Some((from, to)) =
+                numeric_range(fid, op).filter(|(from, to)| !is_empty_range(from, to))
+            {
+                ranges.entry(fid.value()).or_default().push((position, from, to));
+            }
+        }
+    }
+
+    // the conditions replacing the ranges at each position, `None` to remove a merged range
+    let mut replacements = BTreeMap::new();
+    for mut ranges in ranges.into_values() {
+        ranges.sort_by(|(_, left, _), (_, right, _)| cmp_lower_bounds(left, right));
+
+        let mut ranges = ranges.into_iter();
+        let mut current = ranges.next().map(|(position, from, to)| (vec![position], from, to));
+        while let Some((positions, from, to)) = current.take() {
+            let mut merged = (positions, from, to);
+            for (position, next_from, next_to) in ranges.by_ref() {
+                if ranges_touch(&merged.2, &next_from) {
+                    merged.0.push(position);
+                    if cmp_upper_bounds(&next_to, &merged.2) == Ordering::Greater {
+                        merged.2 = next_to;
+                    }
+                } else {
+                    current = Some((vec![position], next_from, next_to));
+                    break;
+                }
+            }
+
+            let (positions, from, to) = merged;
+            if positions.len() > 1 {
+                let first = *positions.iter().min().unwrap();
+                let fid = match &conditions[first] {
+                    FilterCondition::Condition { fid, .. } => fid.clone(),
+                    _ => unreachable!(),
+                };
+                let token = |bound: NumericBound<'_>| match bound {
+                    Bound::Included((_, token)) => Bound::Included(token),
+                    Bound::Excluded((_, token)) => Bound::Excluded(token),
+                    Bound::Unbounded => Bound::Unbounded,
+                };
+                let op = Condition::Interval { from: token(from), to: token(to) };
+                for position in positions {
+                    replacements.insert(position, None);
+                }
+                replacements.insert(first, Some(FilterCondition::Condition { fid, op }));
+            }
+        }
+    }
+
+    conditions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, condition)| match replacements.remove(&position) {
+            Some(replacement) => replacement,
+            None => Some(condition),
+        })
+        .collect()
+}
+
+/// primary        = (WS* "(" WS* expression WS* ")" WS*) | geoRadius | condition | exists | not_exists | to | like | not_like | const
+fn parse_primary(input: Span, depth: usize, legacy: bool) -> IResult<FilterCondition> {
     if depth > MAX_FILTER_DEPTH {
         return Err(nom::Err::Error(Error::new_from_kind(input, ErrorKind::DepthLimitReached)));
     }
+    if legacy {
+        match parse_legacy_condition(input) {
+            Err(nom::Err::Error(_)) => (),
+            result => return result,
+        }
+    }
     alt((
         // if we find a first parenthesis, then we must parse an expression and find the closing parenthesis
         delimited(
             ws(char('(')),
-            cut(|input| parse_expression(input, depth + 1)),
+            cut(|input| parse_or(input, depth + 1, legacy)),
             cut_with_err(ws(char(')')), |c| {
                 Error::new_from_kind(input, ErrorKind::MissingClosingDelimiter(c.char()))
             }),
         ),
         parse_geo_radius,
         parse_geo_bounding_box,
-        parse_in,
-        parse_not_in,
+        alt((parse_in_ranges, parse_in, parse_not_in)),
+        parse_has_any,
+        parse_has_all,
         parse_condition,
         parse_is_null,
         parse_is_not_null,
         parse_is_empty,
         parse_is_not_empty,
-        parse_exists,
-        parse_not_exists,
-        parse_to,
+        alt((parse_exists, parse_not_exists)),
+        // nested to stay within the number of parsers `alt` accepts
+        alt((parse_between_count, parse_window, parse_is_integer, parse_is_fractional, parse_to)),
+        parse_interval,
+        alt((parse_like, parse_not_like)),
+        parse_const,
         // the next lines are only for error handling and are written at the end to have the less possible performance impact
         parse_geo,
         parse_geo_distance,
@@ -464,7 +1377,7 @@ fn parse_primary(input: Span, depth: usize) -> IResult<FilterCondition> {
 
 /// expression     = or
 pub fn parse_expression(input: Span, depth: usize) -> IResult<FilterCondition> {
-    parse_or(input, depth)
+    parse_or(input, depth, false)
 }
 
 /// filter     = expression EOF
@@ -488,6 +1401,16 @@ impl<'a> std::fmt::Display for FilterCondition<'a> {
                 }
                 write!(f, "]")
             }
+            FilterCondition::HasAll { fid, els } => {
+                write!(f, "{fid} HAS_ALL[")?;
+                for el in els {
+                    write!(f, "{el}, ")?;
+                }
+                write!(f, "]")
+            }
+            FilterCondition::Const(value) => {
+                write!(f, "{}", if *value { "TRUE" } else { "FALSE" })
+            }
             FilterCondition::Or(els) => {
                 write!(f, "OR[")?;
                 for el in els {
@@ -534,12 +1457,33 @@ impl<'a> std::fmt::Display for Condition<'a> {
             Condition::LowerThan(token) => write!(f, "< {token}"),
             Condition::LowerThanOrEqual(token) => write!(f, "<= {token}"),
             Condition::Between { from, to } => write!(f, "{from} TO {to}"),
+            Condition::BetweenStep { from, to, step } => write!(f, "{from} TO {to} STEP {step}"),
+            Condition::Like(token) => write!(f, "LIKE {token}"),
+            Condition::Window { from, limit } => write!(f, "FROM {from} LIMIT {limit}"),
+            Condition::Integer => write!(f, "IS_INTEGER"),
+            Condition::Fractional => write!(f, "IS_FRACTIONAL"),
+            Condition::Interval { from, to } => {
+                match from {
+                    Bound::Included(from) => write!(f, "[{from}, ")?,
+                    Bound::Excluded(from) => write!(f, "({from}, ")?,
+                    Bound::Unbounded => write!(f, "(*, ")?,
+                }
+                match to {
+                    Bound::Included(to) => write!(f, "{to}]"),
+                    Bound::Excluded(to) => write!(f, "{to})"),
+                    Bound::Unbounded => write!(f, "*)"),
+                }
+            }
         }
     }
 }
 impl<'a> std::fmt::Display for Token<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{{{}}}", self.value())
+        match (self.function, self.annotation) {
+            (Some(function), _) => write!(f, "{}({{{}}})", function, self.value()),
+            (None, Some(annotation)) => write!(f, "{{{}:{}}}", annotation, self.value()),
+            (None, None) => write!(f, "{{{}}}", self.value()),
+        }
     }
 }
 
@@ -584,6 +1528,11 @@ pub mod tests {
         insta::assert_display_snapshot!(p("\"dog race\" = \"Bernese Mountain\""), @"{dog race} = {Bernese Mountain}");
         insta::assert_display_snapshot!(p("'dog race' = 'Bernese Mountain'"), @"{dog race} = {Bernese Mountain}");
         insta::assert_display_snapshot!(p("\"dog race\" = 'Bernese Mountain'"), @"{dog race} = {Bernese Mountain}");
+        insta::assert_display_snapshot!(p("`weird.name` = 12"), @"{weird.name} = {12}");
+        insta::assert_display_snapshot!(p("`dog race` = \"Bernese Mountain\""), @"{dog race} = {Bernese Mountain}");
+        insta::assert_display_snapshot!(p("`dog \\`race\\`` = 'Borzoi'"), @"{dog `race`} = {Borzoi}");
+        insta::assert_display_snapshot!(p("`it's \"quoted\"` EXISTS"), @"{it's \"quoted\"} EXISTS");
+        insta::assert_display_snapshot!(p("`AND` IN [a, `b c`]"), @"{AND} IN[{a}, {b c}, ]");
 
         // Test IN
         insta::assert_display_snapshot!(p("colour IN[]"), @"{colour} IN[]");
@@ -592,6 +1541,12 @@ pub mod tests {
         insta::assert_display_snapshot!(p("colour NOT IN[green,blue]"), @"NOT ({colour} IN[{green}, {blue}, ])");
         insta::assert_display_snapshot!(p(" colour IN [  green , blue , ]"), @"{colour} IN[{green}, {blue}, ]");
 
+        // Test HAS_ANY + HAS_ALL
+        insta::assert_display_snapshot!(p("keywords HAS_ANY [rust, search]"), @"{keywords} IN[{rust}, {search}, ]");
+        insta::assert_display_snapshot!(p("keywords HAS_ALL [rust, search]"), @"{keywords} HAS_ALL[{rust}, {search}, ]");
+        insta::assert_display_snapshot!(p("NOT keywords HAS_ALL[rust]"), @"NOT ({keywords} HAS_ALL[{rust}, ])");
+        insta::assert_display_snapshot!(p("keywords HAS_ALL[]"), @"{keywords} HAS_ALL[]");
+
         // Test IN + OR/AND/()
         insta::assert_display_snapshot!(p(" colour IN [green, blue]  AND color = green "), @"AND[{colour} IN[{green}, {blue}, ], {color} = {green}, ]");
         insta::assert_display_snapshot!(p("NOT (colour IN [green, blue])  AND color = green "), @"AND[NOT ({colour} IN[{green}, {blue}, ]), {color} = {green}, ]");
@@ -613,11 +1568,68 @@ pub mod tests {
         insta::assert_display_snapshot!(p("subscribers >= 1000"), @"{subscribers} >= {1000}");
         insta::assert_display_snapshot!(p("subscribers <= 1000"), @"{subscribers} <= {1000}");
         insta::assert_display_snapshot!(p("subscribers 100 TO 1000"), @"{subscribers} {100} TO {1000}");
+        insta::assert_display_snapshot!(p("year 2000 TO 2020 STEP 5"), @"{year} {2000} TO {2020} STEP {5}");
+        insta::assert_display_snapshot!(p("year 2000 TO 2020 STEP 5 AND a = b"), @"AND[{year} {2000} TO {2020} STEP {5}, {a} = {b}, ]");
+
+        // Test intervals
+        insta::assert_display_snapshot!(p("price [10, 20]"), @"{price} [{10}, {20}]");
+        insta::assert_display_snapshot!(p("price [10, 20)"), @"{price} [{10}, {20})");
+        insta::assert_display_snapshot!(p("price (10, 20]"), @"{price} ({10}, {20}]");
+        insta::assert_display_snapshot!(p("price (10,20)"), @"{price} ({10}, {20})");
+        insta::assert_display_snapshot!(p("price[ 10 , 20 ]"), @"{price} [{10}, {20}]");
+        insta::assert_display_snapshot!(p("price [*, 20)"), @"{price} (*, {20})");
+        insta::assert_display_snapshot!(p("price (10, *]"), @"{price} ({10}, *)");
+        insta::assert_display_snapshot!(p("price [, 20]"), @"{price} (*, {20}]");
+        insta::assert_display_snapshot!(p("price [10,]"), @"{price} [{10}, *)");
+        insta::assert_display_snapshot!(p("price [*, *] AND NOT price (1, 2)"), @"AND[{price} (*, *), NOT ({price} ({1}, {2})), ]");
+
+        // Test LIKE + NOT LIKE
+        insta::assert_display_snapshot!(p("name LIKE \"A%\""), @"{name} LIKE {A%}");
+        insta::assert_display_snapshot!(p("code LIKE A_C"), @"{code} LIKE {A_C}");
+        insta::assert_display_snapshot!(p(r"code LIKE '100\%'"), @r"{code} LIKE {100\%}");
+        insta::assert_display_snapshot!(p("code NOT LIKE A_C"), @"NOT ({code} LIKE {A_C})");
+        insta::assert_display_snapshot!(p("NOT code LIKE 'A%' AND name LIKE '%b'"), @"AND[NOT ({code} LIKE {A%}), {name} LIKE {%b}, ]");
+
+        // Test type annotations
+        insta::assert_display_snapshot!(p("price = f64:9"), @"{price} = {f64:9}");
+        insta::assert_display_snapshot!(p("id != i64:5"), @"{id} != {i64:5}");
+        insta::assert_display_snapshot!(p("zip = string:'01234'"), @"{zip} = {string:01234}");
+        insta::assert_display_snapshot!(p("price > f64"), @"{price} > {f64}");
+        insta::assert_display_snapshot!(p("price IN [i64:1, f64:2.5, string:'3']"), @"{price} IN[{i64:1}, {f64:2.5}, {string:3}, ]");
+        insta::assert_display_snapshot!(p("tag IN [string, i64]"), @"{tag} IN[{string}, {i64}, ]");
+
+        // Test placeholders
+        insta::assert_display_snapshot!(p("genre = ? AND year > ?"), @"AND[{genre} = {?}, {year} > {?}, ]");
+        insta::assert_display_snapshot!(p("year > f64:?"), @"{year} > {f64:?}");
+
+        // Test arithmetic expressions
+        insta::assert_display_snapshot!(p("price < 10 * 2"), @"{price} < {20}");
+        insta::assert_display_snapshot!(p("price < 1 + 2 * 3 - 4 / 2"), @"{price} < {5}");
+        insta::assert_display_snapshot!(p("price < base * 2"), @"{price} < {base * 2}");
+        insta::assert_display_snapshot!(p("price < base * 2 AND price > base - 1"), @"AND[{price} < {base * 2}, {price} > {base - 1}, ]");
+        insta::assert_display_snapshot!(p("price < f64:base / 2"), @"{price} < {f64:base / 2}");
+
+        // Test null-safe equality
+        insta::assert_display_snapshot!(p("subscribers <=> NULL"), @"NOT ({subscribers} EXISTS)");
+        insta::assert_display_snapshot!(p("subscribers <=>   NULL  "), @"NOT ({subscribers} EXISTS)");
+        insta::assert_display_snapshot!(p("subscribers <=> 1000"), @"{subscribers} = {1000}");
+        insta::assert_display_snapshot!(p("subscribers <=> 'NULL'"), @"{subscribers} = {NULL}");
+        insta::assert_display_snapshot!(p("subscribers <=> NULLABLE"), @"{subscribers} = {NULLABLE}");
+        insta::assert_display_snapshot!(p("NOT subscribers <=> NULL"), @"{subscribers} EXISTS");
 
         // Test NOT
         insta::assert_display_snapshot!(p("NOT subscribers < 1000"), @"NOT ({subscribers} < {1000})");
         insta::assert_display_snapshot!(p("NOT subscribers 100 TO 1000"), @"NOT ({subscribers} {100} TO {1000})");
 
+        // Test the negation of a single comparison
+        insta::assert_display_snapshot!(p("- subscribers < 1000"), @"AND[{subscribers} EXISTS, NOT ({subscribers} < {1000}), ]");
+        insta::assert_display_snapshot!(p("- colour NOT IN [green]"), @"AND[{colour} EXISTS, {colour} IN[{green}, ], ]");
+        insta::assert_display_snapshot!(p("x = 1 AND - colour = red"), @"AND[{x} = {1}, AND[{colour} EXISTS, NOT ({colour} = {red}), ], ]");
+        insta::assert_display_snapshot!(p("-(x = 1 OR y = 2)"), @"NOT (OR[{x} = {1}, {y} = {2}, ])");
+        insta::assert_display_snapshot!(p("'-colour' = red"), @"{-colour} = {red}");
+        insta::assert_display_snapshot!(p("-score > 3"), @"{-score} > {3}");
+        insta::assert_display_snapshot!(p("x = 1 AND -score > 3"), @"AND[{x} = {1}, {-score} > {3}, ]");
+
         // Test NULL + NOT NULL
         insta::assert_display_snapshot!(p("subscribers IS NULL"), @"{subscribers} IS NULL");
         insta::assert_display_snapshot!(p("NOT subscribers IS NULL"), @"NOT ({subscribers} IS NULL)");
@@ -638,6 +1650,8 @@ pub mod tests {
         insta::assert_display_snapshot!(p("subscribers NOT EXISTS"), @"NOT ({subscribers} EXISTS)");
         insta::assert_display_snapshot!(p("NOT subscribers NOT EXISTS"), @"{subscribers} EXISTS");
         insta::assert_display_snapshot!(p("subscribers NOT   EXISTS"), @"NOT ({subscribers} EXISTS)");
+        insta::assert_display_snapshot!(p("author.* EXISTS"), @"{author.*} EXISTS");
+        insta::assert_display_snapshot!(p("  author.name.* NOT EXISTS"), @"NOT ({author.name.*} EXISTS)");
 
         // Test nested NOT
         insta::assert_display_snapshot!(p("NOT NOT NOT NOT x = 5"), @"{x} = {5}");
@@ -709,6 +1723,11 @@ pub mod tests {
         11:12 channel = 🐻 AND followers < 100
         "###);
 
+        insta::assert_display_snapshot!(p("price IN [i64:1, i64:2.5, i64:x]"), @r###"
+        Value `2.5` is not a valid `i64`.
+        22:25 price IN [i64:1, i64:2.5, i64:x]
+        "###);
+
         insta::assert_display_snapshot!(p("'OR'"), @r###"
         Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `_geoRadius`, or `_geoBoundingBox` at `\'OR\'`.
         1:5 'OR'
@@ -799,6 +1818,26 @@ pub mod tests {
         11:17 channel = "ponce
         "###);
 
+        insta::assert_display_snapshot!(p("`channel = ponce"), @r###"
+        Expression ``channel = ponce` is missing the following closing delimiter: ```.
+        1:17 `channel = ponce
+        "###);
+
+        insta::assert_display_snapshot!(p("price < 10 / 0"), @r###"
+        Division by zero in the expression.
+        12:13 price < 10 / 0
+        "###);
+
+        insta::assert_display_snapshot!(p("name LIKE"), @r###"
+        Was expecting a value but instead got nothing.
+        10:10 name LIKE
+        "###);
+
+        insta::assert_display_snapshot!(p("price < 10 *"), @r###"
+        Was expecting a value but instead got nothing.
+        13:13 price < 10 *
+        "###);
+
         insta::assert_display_snapshot!(p("channel = mv OR (followers >= 1000"), @r###"
         Expression `(followers >= 1000` is missing the following closing delimiter: `)`.
         17:35 channel = mv OR (followers >= 1000
@@ -926,6 +1965,345 @@ pub mod tests {
         assert!(filter.token_at_depth(3).is_none());
     }
 
+    #[test]
+    fn legacy_syntax() {
+        fn l(s: &str) -> impl std::fmt::Display + '_ {
+            match FilterCondition::parse_with_legacy_syntax(s, true) {
+                Ok(Some(filter)) => filter.to_string(),
+                Ok(None) => String::from("empty"),
+                Err(e) => e.to_string(),
+            }
+        }
+
+        insta::assert_display_snapshot!(l("genre:horror"), @"{genre} = {horror}");
+        insta::assert_display_snapshot!(l("year:2020"), @"{year} = {2020}");
+        insta::assert_display_snapshot!(l("'release year' : \"2020\""), @"{release year} = {2020}");
+        insta::assert_display_snapshot!(l("genre:[horror, 'sci fi']"), @"{genre} IN[{horror}, {sci fi}, ]");
+        insta::assert_display_snapshot!(l("genre:horror AND (year:2020 OR year > 2022)"), @"AND[{genre} = {horror}, OR[{year} = {2020}, {year} > {2022}, ], ]");
+
+        // the legacy syntax is opt-in
+        assert!(FilterCondition::parse("genre:horror").is_err());
+    }
+
+    #[test]
+    fn constants() {
+        insta::assert_display_snapshot!(p("TRUE"), @"TRUE");
+        insta::assert_display_snapshot!(p("NOT FALSE"), @"NOT (FALSE)");
+        insta::assert_display_snapshot!(p("channel = Ponce AND FALSE"), @"AND[{channel} = {Ponce}, FALSE, ]");
+        // constants are regular values on both sides of an operator
+        insta::assert_display_snapshot!(p("TRUE = FALSE"), @"{TRUE} = {FALSE}");
+
+        let s = |s| FilterCondition::parse(s).unwrap().unwrap().simplify();
+        insta::assert_display_snapshot!(s("channel = Ponce AND FALSE"), @"FALSE");
+        insta::assert_display_snapshot!(s("channel = Ponce AND TRUE"), @"{channel} = {Ponce}");
+        insta::assert_display_snapshot!(s("channel = Ponce OR TRUE"), @"TRUE");
+        insta::assert_display_snapshot!(s("channel = Ponce OR (dog = kefir AND NOT TRUE)"), @"{channel} = {Ponce}");
+        insta::assert_display_snapshot!(s("NOT (FALSE OR FALSE)"), @"TRUE");
+        insta::assert_display_snapshot!(s("a = 1 AND b = 2 AND TRUE"), @"AND[{a} = {1}, {b} = {2}, ]");
+    }
+
+    #[test]
+    fn merge_ranges() {
+        let m = |s| FilterCondition::parse(s).unwrap().unwrap().merge_ranges();
+
+        // overlapping and contained ranges
+        insta::assert_display_snapshot!(m("year > 2000 OR year > 2010"), @"{year} ({2000}, *)");
+        insta::assert_display_snapshot!(m("year 1 TO 10 OR year 3 TO 5"), @"{year} [{1}, {10}]");
+        insta::assert_display_snapshot!(m("year < 5 OR year 3 TO 8 OR year >= 7"), @"{year} (*, *)");
+        insta::assert_display_snapshot!(m("price [1, 5] OR price (4, 10) OR genre = horror"), @"OR[{price} [{1}, {10}), {genre} = {horror}, ]");
+
+        // touching ranges
+        insta::assert_display_snapshot!(m("price 1 TO 5 OR price [5, 10)"), @"{price} [{1}, {10})");
+        insta::assert_display_snapshot!(m("price < 5 OR price >= 5"), @"{price} (*, *)");
+        insta::assert_display_snapshot!(m("price < 5 OR price > 5"), @"OR[{price} < {5}, {price} > {5}, ]");
+
+        // disjoint ranges and ranges on other fields are kept
+        insta::assert_display_snapshot!(m("year < 2000 OR age > 10 OR year > 2010 OR year 2005 TO 2008"), @"OR[{year} < {2000}, {age} > {10}, {year} > {2010}, {year} {2005} TO {2008}, ]");
+        insta::assert_display_snapshot!(m("year > 2000 OR age > 10 OR year > 2010"), @"OR[{year} ({2000}, *), {age} > {10}, ]");
+
+        // only the ranges bounded by plain numbers are merged
+        insta::assert_display_snapshot!(m("year > 2000 OR year > ? OR year > f64:2010"), @"OR[{year} > {2000}, {year} > {?}, {year} > {f64:2010}, ]");
+        insta::assert_display_snapshot!(m("name a TO c OR name b TO d"), @"OR[{name} {a} TO {c}, {name} {b} TO {d}, ]");
+        insta::assert_display_snapshot!(m("year 10 TO 5 OR year 1 TO 7"), @"OR[{year} {10} TO {5}, {year} {1} TO {7}, ]");
+
+        // the nested `OR` are merged too
+        insta::assert_display_snapshot!(m("NOT (a > 1 OR a > 2) AND (b < 1 OR b <= 3)"), @"AND[NOT ({a} ({1}, *)), {b} (*, {3}], ]");
+    }
+
+    #[test]
+    fn in_ranges() {
+        let p = |s| FilterCondition::parse(s).unwrap().unwrap();
+
+        // disjoint ranges
+        insta::assert_display_snapshot!(p("year IN [1990 TO 1995, 2000 TO 2005]"), @"OR[{year} {1990} TO {1995}, {year} {2000} TO {2005}, ]");
+        insta::assert_display_snapshot!(p("year IN [2000 TO 2005]"), @"{year} {2000} TO {2005}");
+
+        // overlapping and touching ranges are merged
+        insta::assert_display_snapshot!(p("year IN [1993 TO 2000, 1990 TO 1995, 2000 TO 2001]"), @"{year} [{1990}, {2001}]");
+        insta::assert_display_snapshot!(p("year IN [1990 TO 1995, 2010 TO 2020, 1994 TO 1998]"), @"OR[{year} [{1990}, {1998}], {year} {2010} TO {2020}, ]");
+
+        // the values are kept next to the ranges
+        insta::assert_display_snapshot!(p("year IN [1990 TO 1995, 2003, 1992]"), @"OR[{year} {1990} TO {1995}, {year} = {2003}, {year} = {1992}, ]");
+        insta::assert_display_snapshot!(p("year NOT IN [1990 TO 1995, 2000 TO 2005,]"), @"NOT (OR[{year} {1990} TO {1995}, {year} {2000} TO {2005}, ])");
+
+        // the lists without a range are still parsed by `IN`
+        insta::assert_display_snapshot!(p("year IN [1990, 2000]"), @"{year} IN[{1990}, {2000}, ]");
+        assert!(FilterCondition::parse("year IN [1990 TO ]").is_err());
+        assert!(FilterCondition::parse("year IN [1990 TO 1995").is_err());
+
+        // the annotated values are checked like in the lists without a range
+        insta::assert_display_snapshot!(FilterCondition::parse("year IN [1990 TO 1995, i64:2.5]").unwrap_err(), @r###"
+        Value `2.5` is not a valid `i64`.
+        28:31 year IN [1990 TO 1995, i64:2.5]
+        "###);
+    }
+
+    #[test]
+    fn half_open_range() {
+        let range = FilterCondition::half_open_range("price", 10.0, 20.5);
+        insta::assert_display_snapshot!(range, @"{price} [{10}, {20.5})");
+        assert!(!range.is_contradiction());
+
+        // an empty range is still a range
+        let range = FilterCondition::half_open_range("price", 10.0, 10.0);
+        insta::assert_display_snapshot!(range, @"{price} [{10}, {10})");
+        assert!(range.is_contradiction());
+
+        let range = FilterCondition::half_open_range("price", f64::NEG_INFINITY, 10.0);
+        insta::assert_display_snapshot!(range, @"{price} (*, {10})");
+
+        insta::assert_display_snapshot!(FilterCondition::half_open_range("price", 20.0, 10.0), @"FALSE");
+        insta::assert_display_snapshot!(FilterCondition::half_open_range("price", f64::NAN, 10.0), @"FALSE");
+    }
+
+    #[test]
+    fn is_contradiction() {
+        let c = |s| FilterCondition::parse(s).unwrap().unwrap().is_contradiction();
+
+        assert!(c("year > 2010 AND year < 2000"));
+        assert!(c("year > 2000 AND year <= 2000"));
+        assert!(c("year > 2010 AND genre = horror AND year [1990, 2000)"));
+        assert!(c("year 2010 TO 2000"));
+        assert!(c("(year > 2010 AND year < 2000) OR (price < 5 AND price >= 5)"));
+        assert!(c("channel = Ponce AND (year 1 TO 2 AND year 3 TO 4)"));
+        assert!(c("channel = Ponce AND FALSE"));
+
+        assert!(!c("year > 2000 AND year < 2010"));
+        assert!(!c("year >= 2000 AND year <= 2000"));
+        assert!(!c("year > 2010 OR year < 2000"));
+        assert!(!c("(year > 2010 AND year < 2000) OR genre = horror"));
+        // the ranges on different fields or that are not bounded by numbers can't be compared
+        assert!(!c("year > 2010 AND price < 2000"));
+        assert!(!c("year > 2010 AND year < ?"));
+        assert!(!c("name c TO d AND name a TO b"));
+        assert!(!c("NOT (year > 2010 AND year < 2000)"));
+    }
+
+    #[test]
+    fn unreachable_leaves() {
+        let u = |s| FilterCondition::parse(s).unwrap().unwrap().unreachable_leaves();
+
+        // the narrower range of an `OR` is subsumed by the broader one
+        assert_eq!(u("year > 2000 OR year > 2010"), vec![1]);
+        assert_eq!(u("year > 2010 OR year > 2000"), vec![0]);
+        assert_eq!(u("price [1, 10) OR genre = horror OR price 2 TO 5"), vec![2]);
+        assert_eq!(u("price 1 TO 5 OR price [1, 5)"), vec![1]);
+        assert_eq!(u("price [1, 5) OR price 1 TO 5"), vec![0]);
+        assert_eq!(u("year > 2000 OR year > 2000"), vec![1]);
+        assert_eq!(u("(genre = horror AND year > 2000) OR year < 1990 OR year < 1980"), vec![3]);
+
+        // the contradictions are unreachable
+        assert_eq!(u("genre = horror OR (year > 2010 AND year < 2000)"), vec![1, 2]);
+        assert_eq!(u("genre = horror AND year > 2010 AND year < 2000"), vec![0, 1, 2]);
+        assert_eq!(u("year 10 TO 5 OR year > 20 OR genre = horror"), vec![0]);
+
+        assert!(u("year > 2000 OR year < 1990").is_empty());
+        assert!(u("year > 2000 OR age > 2010").is_empty());
+        assert!(u("year > 2000 AND year > 2010").is_empty());
+        assert!(u("year > ? OR year > 2000").is_empty());
+        assert!(u("NOT (year > 2000 OR year > 2010) OR year > 1 OR TRUE").is_empty());
+
+        let input = "genre = horror OR year > 2000 OR year 2005 TO 2008 OR FALSE";
+        let (filter, spans) = FilterCondition::parse_with_spans(input).unwrap().unwrap();
+        let unreachable: Vec<&str> =
+            filter.unreachable_leaves().into_iter().map(|i| &input[spans[i].clone()]).collect();
+        assert_eq!(unreachable, vec!["year 2005 TO 2008"]);
+    }
+
+    #[test]
+    fn parse_with_spans() {
+        let input = "'channel' = Ponce AND (dog_race IN [bernese, husky] OR age IS NOT NULL)";
+        let (filter, spans) = FilterCondition::parse_with_spans(input).unwrap().unwrap();
+        insta::assert_display_snapshot!(filter, @"AND[{channel} = {Ponce}, OR[{dog_race} IN[{bernese}, {husky}, ], NOT ({age} IS NULL), ], ]");
+
+        let leaves: Vec<&str> = spans.into_iter().map(|span| &input[span]).collect();
+        assert_eq!(
+            leaves,
+            vec!["'channel' = Ponce", "dog_race IN [bernese, husky]", "age IS NOT NULL"]
+        );
+
+        let input = "`weird.name` = 1 AND b = 2";
+        let (_, spans) = FilterCondition::parse_with_spans(input).unwrap().unwrap();
+        let leaves: Vec<&str> = spans.into_iter().map(|span| &input[span]).collect();
+        assert_eq!(leaves, vec!["`weird.name` = 1", "b = 2"]);
+
+        let input = "LOWER(name) = foo OR TRIM('the name') != bar";
+        let (_, spans) = FilterCondition::parse_with_spans(input).unwrap().unwrap();
+        let leaves: Vec<&str> = spans.into_iter().map(|span| &input[span]).collect();
+        assert_eq!(leaves, vec!["LOWER(name) = foo", "TRIM('the name') != bar"]);
+
+        assert!(FilterCondition::parse_with_spans("  ").unwrap().is_none());
+    }
+
+    #[test]
+    fn bind_placeholders() {
+        let mut filter =
+            FilterCondition::parse("genre = ? AND (NOT year > ? OR year = 2000)").unwrap().unwrap();
+        let mut placeholders = filter.placeholders_mut();
+        assert_eq!(placeholders.len(), 2);
+        placeholders[0].bind(String::from("horror"));
+        placeholders[1].bind(String::from("2020"));
+        insta::assert_display_snapshot!(filter, @"AND[{genre} = {horror}, OR[NOT ({year} > {2020}), {year} = {2000}, ], ]");
+        assert!(filter.placeholders_mut().is_empty());
+    }
+
+    #[test]
+    fn rename_attributes() {
+        let mut filter = FilterCondition::parse(
+            "price > 10 AND (NOT tag IN [a, b] OR LOWER(name) = c OR _geoRadius(1, 2, 3))",
+        )
+        .unwrap()
+        .unwrap();
+        let mut attributes = filter.attributes_mut();
+        let names: Vec<_> = attributes.iter().map(|attribute| attribute.value()).collect();
+        assert_eq!(names, ["price", "tag", "name"]);
+        attributes[0].rename(String::from("product_price"));
+        attributes[2].rename(String::from("full_name"));
+        insta::assert_display_snapshot!(filter, @"AND[{product_price} > {10}, OR[NOT ({tag} IN[{a}, {b}, ]), LOWER({full_name}) = {c}, _geoRadius({1}, {2}, {3}), ], ]");
+    }
+
+    #[test]
+    fn bind_expressions() {
+        let mut filter = FilterCondition::parse("price < base * 2 OR price >= ?").unwrap().unwrap();
+        assert_eq!(filter.placeholders_mut().len(), 1);
+
+        let mut expressions = filter.expressions_mut();
+        assert_eq!(expressions.len(), 1);
+        let parameter = |name: &str| (name == "base").then_some(21.5);
+        let value = expressions[0].expression().unwrap().evaluate(&parameter).unwrap();
+        expressions[0].bind(value.to_string());
+        insta::assert_display_snapshot!(filter, @"OR[{price} < {43}, {price} >= {?}, ]");
+        assert!(filter.expressions_mut().is_empty());
+
+        let mut filter = FilterCondition::parse("price < base / zero").unwrap().unwrap();
+        let expressions = filter.expressions_mut();
+        let expression = expressions[0].expression().unwrap();
+        let error = expression.evaluate(&|_: &str| None).unwrap_err();
+        insta::assert_display_snapshot!(error, @r###"
+        Parameter `base` is not a number and has not been provided.
+        9:13 price < base / zero
+        "###);
+        let error = expression.evaluate(&|_: &str| Some(0.0)).unwrap_err();
+        insta::assert_display_snapshot!(error, @r###"
+        Division by zero in the expression.
+        14:15 price < base / zero
+        "###);
+    }
+
+    #[test]
+    fn quoted_values() {
+        let filter =
+            FilterCondition::parse("zip = \"01234\" OR zip = 01234 OR zip = '1' OR zip = `2`")
+                .unwrap()
+                .unwrap();
+        let quoted = match filter {
+            Fc::Or(conditions) => conditions
+                .iter()
+                .map(|condition| match condition {
+                    Fc::Condition { op: Condition::Equal(value), .. } => {
+                        (value.value().to_string(), value.is_quoted())
+                    }
+                    _ => panic!("unexpected condition {condition}"),
+                })
+                .collect::<Vec<_>>(),
+            _ => panic!("unexpected filter {filter}"),
+        };
+        assert_eq!(
+            quoted,
+            vec![
+                ("01234".to_string(), true),
+                ("01234".to_string(), false),
+                ("1".to_string(), true),
+                ("2".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn value_functions() {
+        insta::assert_display_snapshot!(p("LOWER(name) = foo"), @"LOWER({name}) = {foo}");
+        insta::assert_display_snapshot!(p("LENGTH('the title') > 5"), @"LENGTH({the title}) > {5}");
+        insta::assert_display_snapshot!(p("UPPER( name ) != FOO AND TRIM(code) <=> NULL"), @"AND[UPPER({name}) != {FOO}, NOT (TRIM({code}) EXISTS), ]");
+        // without parenthesis it is an attribute named like the function
+        insta::assert_display_snapshot!(p("LOWER = foo"), @"{LOWER} = {foo}");
+
+        let fid = |filter: &str| match FilterCondition::parse(filter).unwrap().unwrap() {
+            Fc::Condition { fid, .. } => (fid.value().to_string(), fid.function()),
+            filter => panic!("unexpected filter {filter}"),
+        };
+        assert_eq!(fid("LOWER(name) = foo"), ("name".to_string(), Some(ValueFunction::Lower)));
+        assert_eq!(fid("LENGTH(title) > 5"), ("title".to_string(), Some(ValueFunction::Length)));
+        assert_eq!(fid("name = foo"), ("name".to_string(), None));
+
+        // a function of an attribute is not the attribute itself
+        let filter = FilterCondition::parse("LENGTH(size) > 5 AND size < 3").unwrap().unwrap();
+        assert!(!filter.is_contradiction());
+        assert_ne!(
+            FilterCondition::parse("LOWER(name) = foo").unwrap(),
+            FilterCondition::parse("name = foo").unwrap()
+        );
+
+        insta::assert_display_snapshot!(Fc::parse("LOWER(name = foo").unwrap_err(), @r###"
+        Expression `LOWER(name = foo` is missing the following closing delimiter: `)`.
+        1:17 LOWER(name = foo
+        "###);
+    }
+
+    #[test]
+    fn window() {
+        insta::assert_display_snapshot!(p("id FROM 100 LIMIT 50"), @"{id} FROM {100} LIMIT {50}");
+        insta::assert_display_snapshot!(p("id FROM 100 LIMIT 0 OR NOT rank FROM -5 LIMIT 10"), @"OR[{id} FROM {100} LIMIT {0}, NOT ({rank} FROM {-5} LIMIT {10}), ]");
+        // the bounds are only checked when the filter is evaluated
+        insta::assert_display_snapshot!(p("id FROM 1.5 LIMIT -1"), @"{id} FROM {1.5} LIMIT {-1}");
+
+        insta::assert_display_snapshot!(p("FROM 1 TO 5"), @"{FROM} {1} TO {5}");
+        assert!(FilterCondition::parse("id FROM 100").is_err());
+        assert!(FilterCondition::parse("id FROM 100 50").is_err());
+    }
+
+    #[test]
+    fn integer_and_fractional() {
+        insta::assert_display_snapshot!(p("price IS_INTEGER"), @"{price} IS_INTEGER");
+        insta::assert_display_snapshot!(p("NOT price IS_FRACTIONAL OR 'the price' IS_INTEGER"), @"OR[NOT ({price} IS_FRACTIONAL), {the price} IS_INTEGER, ]");
+        // the keyword is a single word
+        assert!(FilterCondition::parse("price IS INTEGER").is_err());
+    }
+
+    #[test]
+    fn between_count() {
+        insta::assert_display_snapshot!(p("tags BETWEEN_COUNT 2 TO 5"), @"COUNT({tags}) {2} TO {5}");
+        insta::assert_display_snapshot!(p("NOT 'the tags' BETWEEN_COUNT 0 TO 1 AND a = b"), @"AND[NOT (COUNT({the tags}) {0} TO {1}), {a} = {b}, ]");
+
+        let filter = FilterCondition::parse("tags BETWEEN_COUNT 2 TO 5").unwrap().unwrap();
+        let Fc::Condition { fid, .. } = filter else { panic!("unexpected filter {filter}") };
+        assert_eq!((fid.value(), fid.function()), ("tags", Some(ValueFunction::Count)));
+
+        // without a count it is the attribute and the bounds of a regular range
+        insta::assert_display_snapshot!(p("BETWEEN_COUNT 2 TO 5"), @"{BETWEEN_COUNT} {2} TO {5}");
+        assert!(FilterCondition::parse("tags BETWEEN_COUNT 2").is_err());
+        assert!(FilterCondition::parse("tags BETWEEN_COUNT 2 5").is_err());
+    }
+
     #[test]
     fn token_from_str() {
         let s = "test string that should not be parsed";
@@ -0,0 +1,184 @@
+//! The syntax tree of a filter with the position of its nodes in the input, e.g. for the
+//! editors highlighting the filters or showing the errors inline, see [`parse_syntax_tree`].
+
+use std::ops::{Bound, Range};
+
+use serde::Serialize;
+
+use crate::{Condition, Error, FilterCondition, Token};
+
+/// A node of the syntax tree of a filter, with its text and its byte range in the input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyntaxNode {
+    /// The kind of node, e.g. `and`, `greater_than`, `attribute` or `value`.
+    pub rule: &'static str,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<SyntaxNode>,
+}
+
+/// Parses the filter into its syntax tree, serializable to JSON. The `or`, `and` and `not`
+/// nodes contain the conditions they combine, and the conditions contain the `attribute` and
+/// the `value`s they compare.
+///
+/// The tree is built from the parsed [`FilterCondition`], so the shorthands are already
+/// expanded, e.g. `a NOT IN [b]` is a `not` node containing an `in` node with the same text.
+/// The `TRUE` and `FALSE` constants only have a node when they are the whole filter.
+pub fn parse_syntax_tree(input: &str) -> Result<Option<SyntaxNode>, Error> {
+    let condition = match FilterCondition::parse(input)? {
+        Some(condition) => condition,
+        None => return Ok(None),
+    };
+    let node = syntax_node(&condition, input).unwrap_or_else(|| {
+        let start = input.len() - input.trim_start().len();
+        node(input, "const", start..input.trim_end().len(), Vec::new())
+    });
+    Ok(Some(node))
+}
+
+fn syntax_node<'a>(condition: &FilterCondition<'a>, input: &'a str) -> Option<SyntaxNode> {
+    let (rule, fid, values): (_, _, Vec<&Token>) = match condition {
+        FilterCondition::Const(_) => return None,
+        FilterCondition::Not(condition) => {
+            let child = syntax_node(condition, input)?;
+            // the `NOT` is part of the condition with the `NOT IN` and `NOT EXISTS` operators
+            let before = input[..child.start].trim_end();
+            let start = if before.ends_with("NOT") { before.len() - 3 } else { child.start };
+            return Some(node(input, "not", start..child.end, vec![child]));
+        }
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            let rule = if matches!(condition, FilterCondition::Or(_)) { "or" } else { "and" };
+            let children: Vec<_> =
+                conditions.iter().filter_map(|condition| syntax_node(condition, input)).collect();
+            let start = children.iter().map(|child| child.start).min()?;
+            let end = children.iter().map(|child| child.end).max()?;
+            return Some(node(input, rule, start..end, children));
+        }
+        FilterCondition::Condition { fid, op } => {
+            let (rule, values) = match op {
+                Condition::GreaterThan(value) => ("greater_than", vec![value]),
+                Condition::GreaterThanOrEqual(value) => ("greater_than_or_equal", vec![value]),
+                Condition::Equal(value) => ("equal", vec![value]),
+                Condition::NotEqual(value) => ("not_equal", vec![value]),
+                Condition::Null => ("null", vec![]),
+                Condition::Empty => ("empty", vec![]),
+                Condition::Exists => ("exists", vec![]),
+                Condition::LowerThan(value) => ("lower_than", vec![value]),
+                Condition::LowerThanOrEqual(value) => ("lower_than_or_equal", vec![value]),
+                Condition::Between { from, to } => ("to", vec![from, to]),
+                Condition::BetweenStep { from, to, step } => ("to", vec![from, to, step]),
+                Condition::Interval { from, to } => {
+                    ("interval", [from, to].into_iter().filter_map(bound_token).collect())
+                }
+                Condition::Like(pattern) => ("like", vec![pattern]),
+                Condition::Window { from, limit } => ("window", vec![from, limit]),
+                Condition::Integer => ("integer", vec![]),
+                Condition::Fractional => ("fractional", vec![]),
+            };
+            (rule, Some(fid), values)
+        }
+        FilterCondition::In { fid, els } => ("in", Some(fid), els.iter().collect()),
+        FilterCondition::HasAll { fid, els } => ("has_all", Some(fid), els.iter().collect()),
+        FilterCondition::GeoLowerThan { point, radius } => {
+            ("geo_radius", None, point.iter().chain([radius]).collect())
+        }
+        FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
+            ("geo_bounding_box", None, top_right_point.iter().chain(bottom_left_point).collect())
+        }
+    };
+
+    let attribute = fid.map(|fid| token_node(input, "attribute", fid));
+    let values = values.into_iter().map(|value| token_node(input, "value", value));
+    let children = attribute.into_iter().chain(values).collect();
+    Some(node(input, rule, condition.leaf_span(input)?, children))
+}
+
+fn bound_token<'t, 'a>(bound: &'t Bound<Token<'a>>) -> Option<&'t Token<'a>> {
+    match bound {
+        Bound::Included(token) | Bound::Excluded(token) => Some(token),
+        Bound::Unbounded => None,
+    }
+}
+
+fn token_node(input: &str, rule: &'static str, token: &Token) -> SyntaxNode {
+    let span = token.original_span();
+    let start = span.location_offset();
+    node(input, rule, start..start + span.fragment().len(), Vec::new())
+}
+
+fn node(
+    input: &str,
+    rule: &'static str,
+    span: Range<usize>,
+    children: Vec<SyntaxNode>,
+) -> SyntaxNode {
+    SyntaxNode {
+        rule,
+        text: input[span.clone()].to_string(),
+        start: span.start,
+        end: span.end,
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn compound_expression() {
+        let input = "(genre = horror OR genre IN [comedy, drama]) AND NOT price > 10";
+        let tree = parse_syntax_tree(input).unwrap().unwrap();
+        let tree = serde_json::to_value(tree).unwrap();
+
+        assert_eq!(tree["rule"], "and");
+        assert_eq!((&tree["start"], &tree["end"]), (&json!(1), &json!(63)));
+        assert_eq!(tree["children"][0]["rule"], "or");
+        assert_eq!(tree["children"][0]["text"], "genre = horror OR genre IN [comedy, drama]");
+
+        let in_children: Vec<_> = tree["children"][0]["children"][1]["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|child| (child["rule"].as_str().unwrap(), child["text"].as_str().unwrap()))
+            .collect();
+        assert_eq!(in_children, [("attribute", "genre"), ("value", "comedy"), ("value", "drama")]);
+
+        assert_eq!(
+            tree["children"][1],
+            json!({
+                "rule": "not",
+                "text": "NOT price > 10",
+                "start": 49,
+                "end": 63,
+                "children": [{
+                    "rule": "greater_than",
+                    "text": "price > 10",
+                    "start": 53,
+                    "end": 63,
+                    "children": [
+                        { "rule": "attribute", "text": "price", "start": 53, "end": 58, "children": [] },
+                        { "rule": "value", "text": "10", "start": 61, "end": 63, "children": [] },
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn shorthands_and_constants() {
+        let tree = parse_syntax_tree("genre NOT IN [drama]").unwrap().unwrap();
+        assert_eq!((tree.rule, tree.text.as_str()), ("not", "genre NOT IN [drama]"));
+        assert_eq!(tree.children[0].rule, "in");
+        assert_eq!(tree.children[0].text, tree.text);
+
+        let tree = parse_syntax_tree("  TRUE ").unwrap().unwrap();
+        assert_eq!((tree.rule, tree.start, tree.end), ("const", 2, 6));
+
+        assert!(parse_syntax_tree("   ").unwrap().is_none());
+        assert!(parse_syntax_tree("price >").is_err());
+    }
+}
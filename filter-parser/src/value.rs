@@ -1,14 +1,15 @@
 use nom::branch::alt;
-use nom::bytes::complete::{take_till, take_while, take_while1};
+use nom::bytes::complete::{tag, take_till, take_while, take_while1};
 use nom::character::complete::{char, multispace0};
-use nom::combinator::cut;
-use nom::sequence::{delimited, terminated};
+use nom::combinator::{cut, opt, recognize, verify};
+use nom::sequence::{delimited, pair, terminated};
 use nom::{InputIter, InputLength, InputTake, Slice};
 
 use crate::error::{ExpectedValueKind, NomErrorExt};
+use crate::expression::parse_expression_value;
 use crate::{
     parse_geo, parse_geo_bounding_box, parse_geo_distance, parse_geo_point, parse_geo_radius,
-    Error, ErrorKind, IResult, Span, Token,
+    Error, ErrorKind, Expression, IResult, Span, Token, TypeAnnotation,
 };
 
 /// This function goes through all characters in the [Span] if it finds any escaped character (`\`).
@@ -80,10 +81,23 @@ pub fn word_exact<'a, 'b: 'a>(tag: &'b str) -> impl Fn(Span<'a>) -> IResult<'a,
     }
 }
 
-/// value          = WS* ( word | singleQuoted | doubleQuoted) WS+
+/// wildcard       = WS* word ".*" WS*
+///
+/// The sub-fields of an object, e.g. `author.*`, the word ending with the dot.
+pub fn parse_wildcard(input: Span) -> IResult<Token> {
+    let prefix = verify(take_while1(is_value_component), |word: &Span| {
+        word.len() > 1 && word.ends_with('.')
+    });
+    let (input, wildcard) =
+        delimited(multispace0, recognize(pair(prefix, char('*'))), multispace0)(input)?;
+    Ok((input, wildcard.into()))
+}
+
+/// value          = WS* ( word | singleQuoted | doubleQuoted | backQuoted) WS+
 pub fn parse_value(input: Span) -> IResult<Token> {
     // to get better diagnostic message we are going to strip the left whitespaces from the input right now
     let (input, _) = take_while(char::is_whitespace)(input)?;
+    let quoted = input.starts_with(['\'', '"', '`']);
 
     // then, we want to check if the user is misusing a geo expression
     // This expression can’t finish without error.
@@ -141,6 +155,7 @@ pub fn parse_value(input: Span) -> IResult<Token> {
         alt((
             delimited(char('\''), cut(|input| quoted_by('\'', input)), cut(char('\''))),
             delimited(char('"'), cut(|input| quoted_by('"', input)), cut(char('"'))),
+            delimited(char('`'), cut(|input| quoted_by('`', input)), cut(char('`'))),
             word_not_keyword,
         )),
         multispace0,
@@ -173,11 +188,17 @@ pub fn parse_value(input: Span) -> IResult<Token> {
 
     match unescaper::unescape(value.value()) {
         Ok(content) => {
-            if content.len() != value.value().len() {
-                Ok((input, Token::new(value.original_span(), Some(content))))
+            let value = if content.len() != value.value().len() {
+                Token::new(value.original_span(), Some(content))
             } else {
-                Ok((input, value))
-            }
+                value
+            };
+            let unit = if quoted || value.value.is_some() {
+                None
+            } else {
+                parse_unit(value.original_span())
+            };
+            Ok((input, Token { quoted, unit, ..value }))
         }
         Err(unescaper::Error::IncompleteStr(_)) => Err(nom::Err::Incomplete(nom::Needed::Unknown)),
         Err(unescaper::Error::ParseIntError { .. }) => Err(nom::Err::Error(Error::new_from_kind(
@@ -191,6 +212,83 @@ pub fn parse_value(input: Span) -> IResult<Token> {
     }
 }
 
+/// unit_value = number unit
+///
+/// Returns the unit of a word made of a number followed by letters, e.g. `kb` for `5kb`.
+fn parse_unit(word: Span) -> Option<Span> {
+    let start = word.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = word.fragment().split_at(start);
+    let is_number = !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && number.parse::<f64>().is_ok();
+    let is_unit = unit.chars().all(|c| c.is_ascii_alphabetic());
+    (is_number && is_unit).then(|| word.slice(start..))
+}
+
+/// computed_value = expression
+///
+/// A single value is returned as is. An arithmetic expression is computed right away when it
+/// only contains numbers, otherwise it is kept in the token until its parameters are bound.
+fn parse_computed_value(input: Span) -> IResult<Token> {
+    let (rest, expression) = parse_expression_value(input)?;
+    let expression = match expression {
+        Expression::Value(token) => return Ok((rest, token)),
+        expression => expression,
+    };
+
+    // the span goes from the first operand to the end of the last one
+    let last = expression.last_value().original_span();
+    let end = last.location_offset() + last.fragment().len() - input.location_offset();
+    let span = input.slice(..end);
+
+    if expression.has_parameters() {
+        Ok((rest, Token { expression: Some(Box::new(expression)), ..Token::from(span) }))
+    } else {
+        let number = expression.evaluate(&|_: &str| None).map_err(nom::Err::Failure)?;
+        Ok((rest, Token::new(span, Some(number.to_string()))))
+    }
+}
+
+/// type_annotation = ("f64" | "i64" | "string") ":"
+fn parse_type_annotation(input: Span) -> IResult<Option<TypeAnnotation>> {
+    let (input, annotation) =
+        opt(terminated(alt((tag("f64"), tag("i64"), tag("string"))), char(':')))(input)?;
+
+    let annotation = annotation.map(|annotation| match *annotation.fragment() {
+        "f64" => TypeAnnotation::F64,
+        "i64" => TypeAnnotation::I64,
+        "string" => TypeAnnotation::String,
+        _ => unreachable!(),
+    });
+
+    Ok((input, annotation))
+}
+
+/// annotated_value = WS* type_annotation? (computed_value | "?")
+pub fn parse_annotated_value(input: Span) -> IResult<Token> {
+    let (input, _) = multispace0(input)?;
+    let (input, annotation) = parse_type_annotation(input)?;
+    let (input, mut value) = match terminated(tag::<_, _, Error>("?"), multispace0)(input) {
+        Ok((input, placeholder)) => {
+            (input, Token { placeholder: true, ..Token::from(placeholder) })
+        }
+        Err(_) => parse_computed_value(input)?,
+    };
+    value.annotation = annotation;
+
+    Ok((input, value))
+}
+
+/// list_value = WS* type_annotation? value
+pub fn parse_list_value(input: Span) -> IResult<Token> {
+    let (input, _) = multispace0(input)?;
+    let (input, annotation) = parse_type_annotation(input)?;
+    let (input, mut value) = parse_value(input)?;
+    value.annotation = annotation;
+
+    Ok((input, value))
+}
+
 fn is_value_component(c: char) -> bool {
     c.is_alphanumeric() || ['_', '-', '.'].contains(&c)
 }
@@ -205,6 +303,8 @@ fn is_keyword(s: &str) -> bool {
         "AND"
             | "OR"
             | "IN"
+            | "HAS_ANY"
+            | "HAS_ALL"
             | "NOT"
             | "TO"
             | "EXISTS"
@@ -246,6 +346,10 @@ pub mod test {
             ("\"cha'nnel\"", rtok("'", "cha'nnel")),
             ("I'm tamo", rtok("'m tamo", "I")),
             ("\"I'm \\\"super\\\" tamo\"", rtok("\"", "I'm \\\"super\\\" tamo")),
+            ("`channel`", rtok("`", "channel")),
+            ("`weird.name`", rtok("`", "weird.name")),
+            ("`with some spaces`", rtok("`", "with some spaces")),
+            ("`cha'nn\"el`", rtok("`", "cha'nn\"el")),
         ];
 
         for (input, expected) in test_case {
@@ -348,6 +452,10 @@ pub mod test {
             (r"'Hello \\\'world\\\''", r"Hello \'world\'", true),
             (r#"'I\'m "super" tamo'"#, r#"I'm "super" tamo"#, true),
             (r"'\'\''", r#"''"#, true),
+            // with control characters
+            (r#""first\nsecond""#, "first\nsecond", true),
+            (r"'a\tb\rc'", "a\tb\rc", true),
+            ("\"first\nsecond\"", "first\nsecond", false),
         ];
 
         for (input, expected, escaped) in test_case {
@@ -378,6 +486,27 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn unit() {
+        let test_case = [
+            ("5kb", Some((5., "kb"))),
+            ("1.5h", Some((1.5, "h"))),
+            ("  12MB  ", Some((12., "MB"))),
+            ("5", None),
+            ("kb", None),
+            ("5kb2", None),
+            ("1e5", None),
+            ("\"5kb\"", None),
+            ("5_kb", None),
+        ];
+
+        for (input, expected) in test_case {
+            let input = Span::new_extra(input, input);
+            let (_, value) = parse_value(input).unwrap();
+            assert_eq!(value.number_with_unit(), expected, "{input}");
+        }
+    }
+
     #[test]
     fn diagnostic() {
         let test_case = [
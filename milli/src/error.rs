@@ -122,6 +122,8 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
     InvalidFilter(String),
     #[error("Invalid type for filter subexpression: expected: {}, found: {1}.", .0.join(", "))]
     InvalidFilterExpression(&'static [&'static str], Value),
+    #[error("The filter read more facet values than allowed by its budget.")]
+    FilterBudgetExceeded,
     #[error("Attribute `{}` is not sortable. {}",
         .field,
         match .valid_fields.is_empty() {
@@ -0,0 +1,33 @@
+use std::borrow::Cow;
+
+use heed::BoxedError;
+
+use crate::heed_codec::SliceTooShortError;
+use crate::{try_split_array_at, FieldId};
+
+/// The key of the [`facet_id_value_id_docids`](crate::Index::facet_id_value_id_docids)
+/// database: a field id followed by the id of an interned string value, both big-endian.
+pub struct FieldIdValueIdCodec;
+
+impl<'a> heed::BytesDecode<'a> for FieldIdValueIdCodec {
+    type DItem = (FieldId, u32);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        let (field_id_bytes, bytes) = try_split_array_at(bytes).ok_or(SliceTooShortError)?;
+        let field_id = u16::from_be_bytes(field_id_bytes);
+        let (value_id_bytes, _nothing) = try_split_array_at(bytes).ok_or(SliceTooShortError)?;
+        let value_id = u32::from_be_bytes(value_id_bytes);
+        Ok((field_id, value_id))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for FieldIdValueIdCodec {
+    type EItem = (FieldId, u32);
+
+    fn bytes_encode((field_id, value_id): &Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
+        let mut bytes = Vec::with_capacity(2 + 4);
+        bytes.extend_from_slice(&field_id.to_be_bytes());
+        bytes.extend_from_slice(&value_id.to_be_bytes());
+        Ok(Cow::Owned(bytes))
+    }
+}
@@ -15,12 +15,13 @@ use crate::error::{InternalError, UserError};
 use crate::fields_ids_map::FieldsIdsMap;
 use crate::heed_codec::facet::{
     FacetGroupKeyCodec, FacetGroupValueCodec, FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
-    FieldIdCodec, OrderedF64Codec,
+    FieldIdCodec, FieldIdValueIdCodec, OrderedF64Codec,
 };
 use crate::heed_codec::{
-    BEU16StrCodec, FstSetCodec, ScriptLanguageCodec, StrBEU16Codec, StrRefCodec,
+    BEU16StrCodec, BytesRefCodec, FstSetCodec, ScriptLanguageCodec, StrBEU16Codec, StrRefCodec,
 };
 use crate::proximity::ProximityPrecision;
+use crate::search::facet::get_highest_level;
 use crate::vector::EmbeddingConfig;
 use crate::{
     default_criteria, CboRoaringBitmapCodec, Criterion, DocumentId, ExternalDocumentsIds,
@@ -38,6 +39,7 @@ pub mod main_key {
     pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
     pub const DOCUMENTS_IDS_KEY: &str = "documents-ids";
     pub const HIDDEN_FACETED_FIELDS_KEY: &str = "hidden-faceted-fields";
+    pub const INTERNED_STRING_FACETS_KEY: &str = "interned-string-facets";
     pub const FILTERABLE_FIELDS_KEY: &str = "filterable-fields";
     pub const SORTABLE_FIELDS_KEY: &str = "sortable-fields";
     pub const FIELD_DISTRIBUTION_KEY: &str = "fields-distribution";
@@ -67,6 +69,7 @@ pub mod main_key {
     pub const PAGINATION_MAX_TOTAL_HITS: &str = "pagination-max-total-hits";
     pub const PROXIMITY_PRECISION: &str = "proximity-precision";
     pub const EMBEDDING_CONFIGS: &str = "embedding_configs";
+    pub const FACET_GENERATION_KEY: &str = "facet-generation";
 }
 
 pub mod db_name {
@@ -90,6 +93,8 @@ pub mod db_name {
     pub const FACET_ID_STRING_DOCIDS: &str = "facet-id-string-docids";
     pub const FACET_ID_NORMALIZED_STRING_STRINGS: &str = "facet-id-normalized-string-strings";
     pub const FACET_ID_STRING_FST: &str = "facet-id-string-fst";
+    pub const FACET_ID_STRING_VALUE_IDS: &str = "facet-id-string-value-ids";
+    pub const FACET_ID_VALUE_ID_DOCIDS: &str = "facet-id-value-id-docids";
     pub const FIELD_ID_DOCID_FACET_F64S: &str = "field-id-docid-facet-f64s";
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
     pub const VECTOR_EMBEDDER_CATEGORY_ID: &str = "vector-embedder-category-id";
@@ -154,6 +159,11 @@ pub struct Index {
     pub facet_id_normalized_string_strings: Database<BEU16StrCodec, SerdeJson<BTreeSet<String>>>,
     /// Maps the facet field id of the string facets with an FST containing all the facets values.
     pub facet_id_string_fst: Database<BEU16, FstSetCodec>,
+    /// Maps the facet field id and the string facets of the interned fields with their value id.
+    pub facet_id_string_value_ids: Database<BEU16StrCodec, BEU32>,
+    /// Maps the facet field id and the value id of the interned string facets with the docids
+    /// that corresponds to them.
+    pub facet_id_value_id_docids: Database<FieldIdValueIdCodec, CboRoaringBitmapCodec>,
 
     /// Maps the document id, the facet field id and the numbers.
     pub field_id_docid_facet_f64s: Database<FieldDocIdFacetF64Codec, Unit>,
@@ -178,7 +188,7 @@ impl Index {
     ) -> Result<Index> {
         use db_name::*;
 
-        options.max_dbs(25);
+        options.max_dbs(27);
 
         let env = options.open(path)?;
         let mut wtxn = env.write_txn()?;
@@ -208,6 +218,10 @@ impl Index {
         let facet_id_normalized_string_strings =
             env.create_database(&mut wtxn, Some(FACET_ID_NORMALIZED_STRING_STRINGS))?;
         let facet_id_string_fst = env.create_database(&mut wtxn, Some(FACET_ID_STRING_FST))?;
+        let facet_id_string_value_ids =
+            env.create_database(&mut wtxn, Some(FACET_ID_STRING_VALUE_IDS))?;
+        let facet_id_value_id_docids =
+            env.create_database(&mut wtxn, Some(FACET_ID_VALUE_ID_DOCIDS))?;
         let facet_id_exists_docids =
             env.create_database(&mut wtxn, Some(FACET_ID_EXISTS_DOCIDS))?;
         let facet_id_is_null_docids =
@@ -247,6 +261,8 @@ impl Index {
             facet_id_string_docids,
             facet_id_normalized_string_strings,
             facet_id_string_fst,
+            facet_id_string_value_ids,
+            facet_id_value_id_docids,
             facet_id_exists_docids,
             facet_id_is_null_docids,
             facet_id_is_empty_docids,
@@ -721,6 +737,48 @@ impl Index {
         Ok(fields_ids)
     }
 
+    /* interned string facets */
+
+    /// Writes the names of the fields whose string facets are interned in the database.
+    pub(crate) fn put_interned_string_facets(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<_>>().put(
+            wtxn,
+            main_key::INTERNED_STRING_FACETS_KEY,
+            fields,
+        )
+    }
+
+    /// Deletes the names of the fields whose string facets are interned in the database.
+    pub(crate) fn delete_interned_string_facets(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::INTERNED_STRING_FACETS_KEY)
+    }
+
+    /// Returns the names of the fields whose string facets are interned.
+    ///
+    /// The string facets of these fields are given a value id when the documents are indexed,
+    /// in the [`facet_id_string_value_ids`](Self::facet_id_string_value_ids) database, and
+    /// their documents are stored under this id in the
+    /// [`facet_id_value_id_docids`](Self::facet_id_value_id_docids) database. The filters
+    /// then evaluate the equalities on these fields with an id lookup and a bitmap fetch.
+    pub fn interned_string_facets(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .remap_types::<Str, SerdeJson<_>>()
+            .get(rtxn, main_key::INTERNED_STRING_FACETS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `interned_string_facets`, but returns ids instead.
+    pub fn interned_string_facets_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.interned_string_facets(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.iter().filter_map(|name| fields_ids_map.id(name)).collect())
+    }
+
     /* sortable fields */
 
     /// Writes the sortable fields names in the database.
@@ -874,6 +932,51 @@ impl Index {
         }
     }
 
+    /// Retrieve all the documents which have a string or number value for this field id
+    pub fn faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        let mut docids = RoaringBitmap::new();
+        for db in [
+            self.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+            self.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+        ] {
+            // every level contains all the documents of the field,
+            // the highest one is the smallest to iterate over.
+            let level = get_highest_level(rtxn, db, field_id)?;
+            let mut prefix = field_id.to_be_bytes().to_vec();
+            prefix.push(level);
+            for result in db.remap_key_type::<Bytes>().prefix_iter(rtxn, &prefix)? {
+                let (_, value) = result?;
+                docids |= value.bitmap;
+            }
+        }
+        Ok(docids)
+    }
+
+    /// Returns a number that changes every time the facet databases are written to.
+    ///
+    /// Caches built on top of the facet databases can store it along with their entries
+    /// and consider them stale as soon as it changes.
+    pub fn facet_generation(&self, rtxn: &RoTxn) -> heed::Result<u64> {
+        Ok(self
+            .main
+            .remap_types::<Str, BEU64>()
+            .get(rtxn, main_key::FACET_GENERATION_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn bump_facet_generation(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        let generation = self.facet_generation(wtxn)?;
+        self.main.remap_types::<Str, BEU64>().put(
+            wtxn,
+            main_key::FACET_GENERATION_KEY,
+            &generation.wrapping_add(1),
+        )
+    }
+
     /* distinct field */
 
     pub(crate) fn put_distinct_field(
@@ -1517,13 +1620,15 @@ pub(crate) mod tests {
     use big_s::S;
     use heed::{EnvOpenOptions, RwTxn};
     use maplit::hashset;
+    use roaring::RoaringBitmap;
     use tempfile::TempDir;
 
     use crate::documents::DocumentsBatchReader;
     use crate::error::{Error, InternalError};
     use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
     use crate::update::{
-        self, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig, Settings,
+        self, ClearDocuments, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod,
+        IndexerConfig, Settings,
     };
     use crate::{db_snap, obkv_to_json, Filter, Index, Search, SearchResult};
 
@@ -2468,6 +2573,82 @@ pub(crate) mod tests {
         db_snap!(index, geo_faceted_documents_ids); // ensure that no more document was inserted
     }
 
+    #[test]
+    fn facet_generation() {
+        let index = TempIndex::new();
+
+        let rtxn = index.read_txn().unwrap();
+        let initial = index.facet_generation(&rtxn).unwrap();
+        assert_eq!(initial, 0);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age") });
+            })
+            .unwrap();
+        index.add_documents(documents!([{ "id": 0, "age": 12 }, { "id": 1, "age": 20 }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let after_addition = index.facet_generation(&rtxn).unwrap();
+        assert_ne!(after_addition, initial);
+        drop(rtxn);
+
+        // simulate a write to the facet databases
+        let mut wtxn = index.write_txn().unwrap();
+        index.bump_facet_generation(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let after_write = index.facet_generation(&rtxn).unwrap();
+        assert_ne!(after_write, after_addition);
+        drop(rtxn);
+
+        index.delete_document("0");
+
+        let rtxn = index.read_txn().unwrap();
+        let after_deletion = index.facet_generation(&rtxn).unwrap();
+        assert_ne!(after_deletion, after_write);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        ClearDocuments::new(&mut wtxn, &index).execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_ne!(index.facet_generation(&rtxn).unwrap(), after_deletion);
+    }
+
+    #[test]
+    fn faceted_documents_ids() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age") });
+            })
+            .unwrap();
+
+        // enough values to build several levels in the facet databases
+        let mut documents = Vec::new();
+        for i in 0..300 {
+            documents.push(match i % 5 {
+                0 => serde_json::json!({ "id": i }),
+                1 => serde_json::json!({ "id": i, "age": null }),
+                2 => serde_json::json!({ "id": i, "age": format!("{i} years") }),
+                _ => serde_json::json!({ "id": i, "age": i }),
+            });
+        }
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let age = index.fields_ids_map(&rtxn).unwrap().id("age").unwrap();
+        let faceted = index.faceted_documents_ids(&rtxn, age).unwrap();
+
+        let expected: RoaringBitmap = (0..300).filter(|i| i % 5 >= 2).collect();
+        assert_eq!(faceted, expected);
+    }
+
     #[test]
     fn unexpected_extra_fields_in_geo_field() {
         let index = TempIndex::new();
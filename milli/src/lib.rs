@@ -57,9 +57,10 @@ pub use self::heed_codec::{
 };
 pub use self::index::Index;
 pub use self::search::{
-    FacetDistribution, FacetValueHit, Filter, FormatOptions, MatchBounds, MatcherBuilder,
-    MatchingWords, OrderBy, Search, SearchForFacetValues, SearchResult, TermsMatchingStrategy,
-    DEFAULT_VALUES_PER_FACET,
+    EmptyReason, FacetColumns, FacetDistribution, FacetMetrics, FacetValueHit, Filter,
+    FormatOptions, HasAllDuplicates, InListOrder, LazyFilter, LeafKind, LeafOperator, MatchBounds,
+    MatcherBuilder, MatchingWords, NumberLocale, NumberType, OrderBy, ReadTxnPool, ReadTxnSource,
+    Search, SearchForFacetValues, SearchResult, TermsMatchingStrategy, DEFAULT_VALUES_PER_FACET,
 };
 
 pub type Result<T> = std::result::Result<T, error::Error>;
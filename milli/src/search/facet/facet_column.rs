@@ -0,0 +1,92 @@
+//! An in-memory copy of the numbers of some fields, to evaluate the ranges of the filters
+//! without reading the facet databases, see [`FacetColumns`].
+
+use std::collections::HashMap;
+use std::ops::Bound;
+
+use roaring::RoaringBitmap;
+
+use crate::heed_codec::facet::FacetGroupKey;
+use crate::{FieldId, Index, Result};
+
+/// The numbers of some fields, each loaded once in memory as a column: its distinct values
+/// sorted in ascending order and, aligned with them, the documents containing each value.
+///
+/// [`Filter::evaluate_with_columns`](crate::Filter::evaluate_with_columns) evaluates the ranges
+/// on the loaded fields with two binary searches in the column and a union of the bitmaps
+/// between them, instead of traversing the levels of the facet database. This is worth it for
+/// the hot fields queried again and again with different ranges, e.g. a price slider: loading
+/// a column reads every number of the field once, and the column takes about as much memory
+/// as the level 0 of the field.
+///
+/// The columns are a snapshot of the index when they were loaded, they must be loaded again
+/// after the documents are updated.
+#[derive(Debug, Default, Clone)]
+pub struct FacetColumns {
+    columns: HashMap<FieldId, FacetColumn>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct FacetColumn {
+    values: Vec<f64>,
+    docids: Vec<RoaringBitmap>,
+}
+
+impl FacetColumns {
+    pub fn new() -> Self {
+        FacetColumns::default()
+    }
+
+    /// Loads the column of the numbers of the `field`, replacing its previous column if any.
+    /// Nothing is loaded when the field doesn't exist in the index.
+    pub fn load(&mut self, rtxn: &heed::RoTxn, index: &Index, field: &str) -> Result<&mut Self> {
+        let field_id = match index.fields_ids_map(rtxn)?.id(field) {
+            Some(field_id) => field_id,
+            None => return Ok(self),
+        };
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+        let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+        let mut column = FacetColumn::default();
+        for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+            let (key, value) = result?;
+            column.values.push(key.left_bound);
+            column.docids.push(value.bitmap);
+        }
+
+        self.columns.insert(field_id, column);
+        Ok(self)
+    }
+
+    /// Returns `true` if the column of the field is loaded.
+    pub(crate) fn contains(&self, field_id: FieldId) -> bool {
+        self.columns.contains_key(&field_id)
+    }
+
+    /// Returns the documents containing a number of the field within the bounds, or `None` if
+    /// the column of the field isn't loaded.
+    pub(crate) fn range_docids(
+        &self,
+        field_id: FieldId,
+        left: Bound<f64>,
+        right: Bound<f64>,
+    ) -> Option<RoaringBitmap> {
+        let column = self.columns.get(&field_id)?;
+        let start = column.values.partition_point(|value| match left {
+            Bound::Included(left) => *value < left,
+            Bound::Excluded(left) => *value <= left,
+            Bound::Unbounded => false,
+        });
+        let end = column.values.partition_point(|value| match right {
+            Bound::Included(right) => *value <= right,
+            Bound::Excluded(right) => *value < right,
+            Bound::Unbounded => true,
+        });
+
+        let mut docids = RoaringBitmap::new();
+        for bitmap in column.docids.get(start..end).unwrap_or_default() {
+            docids |= bitmap;
+        }
+        Some(docids)
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound::Unbounded;
 use std::ops::ControlFlow;
 use std::{fmt, mem};
 
@@ -8,6 +9,8 @@ use indexmap::IndexMap;
 use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
 
+use filter_parser::{Condition, FilterCondition, Token};
+
 use crate::error::UserError;
 use crate::facet::FacetType;
 use crate::heed_codec::facet::{
@@ -15,9 +18,11 @@ use crate::heed_codec::facet::{
 };
 use crate::heed_codec::{BytesRefCodec, StrRefCodec};
 use crate::search::facet::facet_distribution_iter::{
-    count_iterate_over_facet_distribution, lexicographically_iterate_over_facet_distribution,
+    count_iterate_over_facet_distribution, facet_distribution_iter,
+    lexicographically_iterate_over_facet_distribution,
 };
-use crate::{FieldId, Index, Result};
+use crate::search::facet::facet_range_iter;
+use crate::{FieldId, Filter, Index, Result};
 
 /// The default number of values by facets that will
 /// be fetched from the key-value store.
@@ -41,6 +46,8 @@ pub struct FacetDistribution<'a> {
     facets: Option<HashMap<String, OrderBy>>,
     candidates: Option<RoaringBitmap>,
     max_values_per_facet: usize,
+    min_count: u64,
+    prefix: Option<String>,
     default_order_by: OrderBy,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
@@ -52,6 +59,8 @@ impl<'a> FacetDistribution<'a> {
             facets: None,
             candidates: None,
             max_values_per_facet: DEFAULT_VALUES_PER_FACET,
+            min_count: 0,
+            prefix: None,
             default_order_by: OrderBy::default(),
             rtxn,
             index,
@@ -76,6 +85,22 @@ impl<'a> FacetDistribution<'a> {
         self
     }
 
+    /// Omits the values contained in fewer than `min` candidates, e.g. to hide the long tail of
+    /// rare values. The omitted values don't take the place of other values in the
+    /// `max_values_per_facet` returned values.
+    pub fn min_count(&mut self, min: u64) -> &mut Self {
+        self.min_count = min;
+        self
+    }
+
+    /// Only counts the string values starting with `prefix`, e.g. to complete the values of a
+    /// facet as the user types. The values are compared once normalized, the numbers are never
+    /// returned, and only the values starting with the prefix are read from the index.
+    pub fn prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
     pub fn default_order_by(&mut self, order_by: OrderBy) -> &mut Self {
         self.default_order_by = order_by;
         self
@@ -176,6 +201,9 @@ impl<'a> FacetDistribution<'a> {
             field_id,
             candidates,
             |facet_key, nbr_docids, _| {
+                if nbr_docids < self.min_count {
+                    return Ok(ControlFlow::Continue(()));
+                }
                 let facet_key = OrderedF64Codec::bytes_decode(facet_key).unwrap();
                 distribution.insert(facet_key.to_string(), nbr_docids);
                 if distribution.len() == self.max_values_per_facet {
@@ -205,6 +233,9 @@ impl<'a> FacetDistribution<'a> {
             field_id,
             candidates,
             |facet_key, nbr_docids, any_docid| {
+                if nbr_docids < self.min_count {
+                    return Ok(ControlFlow::Continue(()));
+                }
                 let facet_key = StrRefCodec::bytes_decode(facet_key).unwrap();
 
                 let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
@@ -225,6 +256,54 @@ impl<'a> FacetDistribution<'a> {
         )
     }
 
+    /// Counts the string values starting with the normalized `prefix` by reading the values of
+    /// the level 0 that are prefixed by it.
+    fn facet_strings_distribution_with_prefix(
+        &self,
+        field_id: FieldId,
+        prefix: &str,
+        candidates: &RoaringBitmap,
+        order_by: OrderBy,
+        distribution: &mut IndexMap<String, u64>,
+    ) -> heed::Result<()> {
+        let prefix = crate::normalize_facet(prefix);
+        let mut key_prefix = field_id.to_be_bytes().to_vec();
+        key_prefix.push(0);
+        key_prefix.extend_from_slice(prefix.as_bytes());
+
+        let iter = self
+            .index
+            .facet_id_string_docids
+            .remap_key_type::<Bytes>()
+            .prefix_iter(self.rtxn, &key_prefix)?
+            .remap_key_type::<FacetGroupKeyCodec<StrRefCodec>>();
+
+        let mut values = Vec::new();
+        for result in iter {
+            let (facet_key, value) = result?;
+            let docids = value.bitmap & candidates;
+            if let Some(any_docid) = docids.min().filter(|_| docids.len() >= self.min_count) {
+                let key: (FieldId, _, &str) = (field_id, any_docid, facet_key.left_bound);
+                let original_string = self
+                    .index
+                    .field_id_docid_facet_strings
+                    .get(self.rtxn, &key)?
+                    .unwrap_or(facet_key.left_bound);
+                values.push((original_string.to_owned(), docids.len()));
+                if order_by == OrderBy::Lexicographic && values.len() == self.max_values_per_facet {
+                    break;
+                }
+            }
+        }
+
+        if order_by == OrderBy::Count {
+            // the sort is stable, the ties stay in lexicographic order
+            values.sort_by(|(_, left), (_, right)| right.cmp(left));
+        }
+        distribution.extend(values.into_iter().take(self.max_values_per_facet));
+        Ok(())
+    }
+
     fn facet_values(
         &self,
         field_id: FieldId,
@@ -233,8 +312,31 @@ impl<'a> FacetDistribution<'a> {
         use FacetType::{Number, String};
 
         let mut distribution = IndexMap::new();
+        if let Some(prefix) = &self.prefix {
+            let universe;
+            let candidates = match &self.candidates {
+                Some(cnd) => cnd,
+                None => {
+                    universe = self.index.documents_ids(self.rtxn)?;
+                    &universe
+                }
+            };
+            self.facet_strings_distribution_with_prefix(
+                field_id,
+                prefix,
+                candidates,
+                order_by,
+                &mut distribution,
+            )?;
+            return Ok(distribution);
+        }
+
         match (order_by, &self.candidates) {
-            (OrderBy::Lexicographic, Some(cnd)) if cnd.len() <= CANDIDATES_THRESHOLD => {
+            // The counts of the documents are only known once every candidate is read, the facet
+            // levels give the count of each value at once and can skip the rare values.
+            (OrderBy::Lexicographic, Some(cnd))
+                if cnd.len() <= CANDIDATES_THRESHOLD && self.min_count <= 1 =>
+            {
                 // Classic search, candidates were specified, we must return facet values only related
                 // to those candidates. We also enter here for facet strings for performance reasons.
                 self.facet_distribution_from_documents(field_id, Number, cnd, &mut distribution)?;
@@ -268,6 +370,207 @@ impl<'a> FacetDistribution<'a> {
         Ok(distribution)
     }
 
+    /// Lazily iterate over the distribution of the given facet, yielding `(value, count)` pairs.
+    ///
+    /// The numbers are returned first and then the strings, both in lexicographic order.
+    /// Values that are not contained in any of the candidates, or in fewer than the `min_count`
+    /// candidates, are skipped. Unlike [`Self::execute`], the `max_values_per_facet` parameter
+    /// is ignored: take as many values as you need from the iterator instead.
+    pub fn facet_distribution_iter(
+        &self,
+        field: &str,
+    ) -> Result<Box<dyn Iterator<Item = Result<(String, u64)>> + 'a>> {
+        let field_id = match self.faceted_field_id(field)? {
+            Some(field_id) => field_id,
+            None => return Ok(Box::new(std::iter::empty())),
+        };
+        let candidates = match &self.candidates {
+            Some(candidates) => candidates.clone(),
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+
+        let (rtxn, index) = (self.rtxn, self.index);
+        let numbers = facet_distribution_iter(
+            rtxn,
+            index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+            field_id,
+            candidates.clone(),
+        )?
+        .map(|result| -> Result<(String, u64)> {
+            let (facet_key, nbr_docids, _) = result?;
+            let facet_key =
+                OrderedF64Codec::bytes_decode(facet_key).map_err(heed::Error::Decoding)?;
+            Ok((facet_key.to_string(), nbr_docids))
+        });
+        let strings = facet_distribution_iter(
+            rtxn,
+            index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+            field_id,
+            candidates,
+        )?
+        .map(move |result| -> Result<(String, u64)> {
+            let (facet_key, nbr_docids, any_docid) = result?;
+            let facet_key = StrRefCodec::bytes_decode(facet_key).map_err(heed::Error::Decoding)?;
+
+            let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
+            let original_string =
+                index.field_id_docid_facet_strings.get(rtxn, &key)?.unwrap_or(facet_key);
+            Ok((original_string.to_owned(), nbr_docids))
+        });
+
+        let min_count = self.min_count;
+        let values = numbers.chain(strings);
+        Ok(Box::new(
+            values.filter(move |result| !matches!(result, Ok((_, count)) if *count < min_count)),
+        ))
+    }
+
+    /// Returns at most `limit` values of the given facet, the most common first.
+    ///
+    /// The values are counted within the candidates like [`Self::facet_distribution_iter`] does.
+    /// Values with the same count keep their lexicographic order, the numbers before the strings.
+    pub fn facet_values_by_count(&self, field: &str, limit: usize) -> Result<Vec<(String, u64)>> {
+        let mut values = self.facet_distribution_iter(field)?.collect::<Result<Vec<_>>>()?;
+        // the sort is stable, the ties stay in the order of the distribution
+        values.sort_by(|(_, left), (_, right)| right.cmp(left));
+        values.truncate(limit);
+        Ok(values)
+    }
+
+    /// Returns at most `limit` values of the given facet, the most common first like
+    /// [`Self::facet_values_by_count`], and the number of candidates containing any of the other
+    /// values, e.g. to display an "other" bucket.
+    ///
+    /// A candidate containing several of the other values is counted once, and a candidate
+    /// containing both a returned value and another value is counted in the other values too.
+    /// The values skipped because of the `min_count` are not counted in the other values.
+    pub fn facet_values_with_other(
+        &self,
+        field: &str,
+        limit: usize,
+    ) -> Result<(Vec<(String, u64)>, u64)> {
+        let field_id = match self.faceted_field_id(field)? {
+            Some(field_id) => field_id,
+            None => return Ok((Vec::new(), 0)),
+        };
+        let candidates = match &self.candidates {
+            Some(candidates) => candidates.clone(),
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+        let min_count = self.min_count.max(1);
+
+        // the documents of every value are needed to deduplicate the other values,
+        // we read them from the level 0 in the order of the distribution
+        let mut values = Vec::new();
+        let numbers = facet_range_iter(
+            self.rtxn,
+            self.index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+            field_id,
+            Unbounded,
+            Unbounded,
+            true,
+        )?;
+        for result in numbers {
+            let (docids, facet_key) = result?;
+            let docids = docids & &candidates;
+            if docids.len() >= min_count {
+                let value =
+                    OrderedF64Codec::bytes_decode(facet_key).map_err(heed::Error::Decoding)?;
+                values.push((value.to_string(), docids));
+            }
+        }
+        let strings = facet_range_iter(
+            self.rtxn,
+            self.index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
+            field_id,
+            Unbounded,
+            Unbounded,
+            true,
+        )?;
+        for result in strings {
+            let (docids, facet_key) = result?;
+            let docids = docids & &candidates;
+            if let Some(any_docid) = docids.min().filter(|_| docids.len() >= min_count) {
+                let facet_key =
+                    StrRefCodec::bytes_decode(facet_key).map_err(heed::Error::Decoding)?;
+                let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
+                let original_string = self
+                    .index
+                    .field_id_docid_facet_strings
+                    .get(self.rtxn, &key)?
+                    .unwrap_or(facet_key);
+                values.push((original_string.to_owned(), docids));
+            }
+        }
+
+        // the sort is stable, the ties stay in the order of the distribution
+        values.sort_by(|(_, left), (_, right)| right.len().cmp(&left.len()));
+        let mut other = RoaringBitmap::new();
+        for (_, docids) in values.iter().skip(limit) {
+            other |= docids;
+        }
+        let values = values.into_iter().take(limit).map(|(value, docids)| (value, docids.len()));
+        Ok((values.collect(), other.len()))
+    }
+
+    /// Returns the values of the given facet with their count and the fraction of the
+    /// candidates containing them, in the order of [`Self::facet_distribution_iter`].
+    ///
+    /// The fractions don't sum to one when some candidates have several values or none. They
+    /// are all zero when there are no candidates.
+    pub fn facet_value_fractions(&self, field: &str) -> Result<Vec<(String, u64, f64)>> {
+        let candidates_len = match &self.candidates {
+            Some(candidates) => candidates.len(),
+            None => self.index.number_of_documents(self.rtxn)?,
+        };
+        self.facet_distribution_iter(field)?
+            .map(|result| {
+                let (value, count) = result?;
+                let fraction =
+                    if candidates_len == 0 { 0.0 } else { count as f64 / candidates_len as f64 };
+                Ok((value, count, fraction))
+            })
+            .collect()
+    }
+
+    /// Returns the distribution of the values of `field_b` among the documents where the
+    /// `(field, value)` of `field_a_value` is set, e.g. the brands of the products that are
+    /// `("colour", "red")`, the most common first.
+    ///
+    /// Both fields must be filterable.
+    pub fn cross_facet(
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        field_a_value: (&str, &str),
+        field_b: &str,
+    ) -> Result<Vec<(String, u64)>> {
+        let (field_a, value) = field_a_value;
+        let condition = FilterCondition::Condition {
+            fid: Token::from(field_a),
+            op: Condition::Equal(Token::from(value)),
+        };
+        let candidates = Filter::from(condition).check_fields(true).evaluate(rtxn, index)?;
+
+        let mut distribution = FacetDistribution::new(rtxn, index);
+        distribution.candidates(candidates);
+        distribution.facet_values_by_count(field_b, usize::MAX)
+    }
+
+    /// Returns the id of the field, `None` if no document contains it, or an error if it is
+    /// not filterable.
+    fn faceted_field_id(&self, field: &str) -> Result<Option<FieldId>> {
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        if !crate::is_faceted(field, &filterable_fields) {
+            return Err(UserError::InvalidFacetsDistribution {
+                invalid_facets_name: Some(field.to_string()).into_iter().collect(),
+                valid_facets_name: filterable_fields.into_iter().collect(),
+            }
+            .into());
+        }
+        Ok(fields_ids_map.id(field))
+    }
+
     pub fn compute_stats(&self) -> Result<BTreeMap<String, (f64, f64)>> {
         let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
         let filterable_fields = self.index.filterable_fields(self.rtxn)?;
@@ -375,6 +678,8 @@ impl fmt::Debug for FacetDistribution<'_> {
             facets,
             candidates,
             max_values_per_facet,
+            min_count,
+            prefix,
             default_order_by,
             rtxn: _,
             index: _,
@@ -384,6 +689,8 @@ impl fmt::Debug for FacetDistribution<'_> {
             .field("facets", facets)
             .field("candidates", candidates)
             .field("max_values_per_facet", max_values_per_facet)
+            .field("min_count", min_count)
+            .field("prefix", prefix)
             .field("default_order_by", default_order_by)
             .finish()
     }
@@ -395,6 +702,7 @@ mod tests {
 
     use big_s::S;
     use maplit::hashset;
+    use roaring::RoaringBitmap;
 
     use crate::documents::documents_batch_reader_from_objects;
     use crate::index::tests::TempIndex;
@@ -561,6 +869,384 @@ mod tests {
         milli_snap!(format!("{map:?}"), @r###"{"colour": {"Red": 3000}}"###);
     }
 
+    #[test]
+    fn facet_distribution_iter_head() {
+        let mut index = TempIndex::new_with_map_size(4096 * 10_000);
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("colour") }))
+            .unwrap();
+
+        let facet_values = (0..1000).map(|x| format!("{x:x}")).collect::<Vec<_>>();
+
+        let mut documents = vec![];
+        for i in 0..10_000 {
+            let document = serde_json::json!({
+                "colour": facet_values[i % 1000],
+            })
+            .as_object()
+            .unwrap()
+            .clone();
+            documents.push(document);
+        }
+
+        let documents = documents_batch_reader_from_objects(documents);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        distribution.facets(iter::once(("colour", OrderBy::default()))).max_values_per_facet(10);
+        let map = distribution.execute().unwrap();
+        let expected: Vec<_> = map["colour"].iter().map(|(k, v)| (k.clone(), *v)).collect();
+
+        let head: Vec<_> = distribution
+            .facet_distribution_iter("colour")
+            .unwrap()
+            .take(10)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(head, expected);
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        distribution
+            .facets(iter::once(("colour", OrderBy::default())))
+            .candidates((5_000..10_000).collect())
+            .max_values_per_facet(10);
+        let map = distribution.execute().unwrap();
+        let expected: Vec<_> = map["colour"].iter().map(|(k, v)| (k.clone(), *v)).collect();
+
+        let head: Vec<_> = distribution
+            .facet_distribution_iter("colour")
+            .unwrap()
+            .take(10)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(head, expected);
+    }
+
+    #[test]
+    fn facet_values_by_count() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("colour") }))
+            .unwrap();
+
+        let documents = documents!([
+            { "colour": "red" },
+            { "colour": "blue" },
+            { "colour": "green" },
+            { "colour": "blue" },
+            { "colour": "yellow" },
+            { "colour": "green" },
+            { "colour": "blue" },
+            { "colour": 3 },
+            { "colour": 3 },
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        let values = distribution.facet_values_by_count("colour", 10).unwrap();
+        // the ties are broken by value, the numbers first
+        assert_eq!(
+            values,
+            vec![(S("blue"), 3), (S("3"), 2), (S("green"), 2), (S("red"), 1), (S("yellow"), 1),]
+        );
+
+        let values = distribution.facet_values_by_count("colour", 2).unwrap();
+        assert_eq!(values, vec![(S("blue"), 3), (S("3"), 2)]);
+
+        // only the candidates are counted
+        distribution.candidates([0, 1, 2, 5, 8].into_iter().collect());
+        let values = distribution.facet_values_by_count("colour", 10).unwrap();
+        assert_eq!(values, vec![(S("green"), 2), (S("3"), 1), (S("blue"), 1), (S("red"), 1)]);
+
+        let error = distribution.facet_values_by_count("size", 10).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid facet distribution"));
+    }
+
+    #[test]
+    fn min_count() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("colour") }))
+            .unwrap();
+
+        let documents = documents!([
+            { "colour": "red" },
+            { "colour": "blue" },
+            { "colour": "green" },
+            { "colour": "blue" },
+            { "colour": "yellow" },
+            { "colour": "green" },
+            { "colour": "blue" },
+            { "colour": 3 },
+            { "colour": 3 },
+            { "colour": 4 },
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        distribution.facets(iter::once(("colour", OrderBy::default()))).min_count(2);
+        let values = &distribution.execute().unwrap()["colour"];
+        assert_eq!(
+            values.iter().map(|(value, count)| (value.as_str(), *count)).collect::<Vec<_>>(),
+            vec![("3", 2), ("blue", 3), ("green", 2)]
+        );
+
+        // the omitted values don't take the place of the other ones
+        distribution.max_values_per_facet(2);
+        let values = &distribution.execute().unwrap()["colour"];
+        assert_eq!(values.keys().collect::<Vec<_>>(), vec!["3", "blue"]);
+
+        // only the candidates are counted, even when there are few of them
+        distribution.max_values_per_facet(10).candidates([1, 3, 5, 7, 9].into_iter().collect());
+        let values = &distribution.execute().unwrap()["colour"];
+        assert_eq!(
+            values.iter().map(|(value, count)| (value.as_str(), *count)).collect::<Vec<_>>(),
+            vec![("blue", 2)]
+        );
+        distribution.facets(iter::once(("colour", OrderBy::Count)));
+        let values = &distribution.execute().unwrap()["colour"];
+        assert_eq!(values.keys().collect::<Vec<_>>(), vec!["blue"]);
+
+        let values = distribution.facet_values_by_count("colour", 10).unwrap();
+        assert_eq!(values, vec![(S("blue"), 2)]);
+    }
+
+    #[test]
+    fn prefix() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("colour") }))
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "colour": "Red" },
+                { "colour": "rose" },
+                { "colour": ["blue", "Royal Blue"] },
+                { "colour": "red" },
+                { "colour": 3 },
+                { "colour": "Rouge" },
+            ]))
+            .unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let map = FacetDistribution::new(&txn, &index)
+            .facets(iter::once(("colour", OrderBy::Lexicographic)))
+            .prefix("R")
+            .execute()
+            .unwrap();
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {"Red": 2, "rose": 1, "Rouge": 1, "Royal Blue": 1}}"###);
+
+        let map = FacetDistribution::new(&txn, &index)
+            .facets(iter::once(("colour", OrderBy::Count)))
+            .prefix("ro")
+            .candidates([1, 2, 3, 4].into_iter().collect())
+            .execute()
+            .unwrap();
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {"rose": 1, "Royal Blue": 1}}"###);
+
+        let map = FacetDistribution::new(&txn, &index)
+            .facets(iter::once(("colour", OrderBy::Lexicographic)))
+            .prefix("r")
+            .max_values_per_facet(2)
+            .execute()
+            .unwrap();
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {"Red": 2, "rose": 1}}"###);
+
+        let map = FacetDistribution::new(&txn, &index)
+            .facets(iter::once(("colour", OrderBy::Lexicographic)))
+            .prefix("green")
+            .execute()
+            .unwrap();
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {}}"###);
+    }
+
+    #[test]
+    fn facet_values_with_other() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("tags") }))
+            .unwrap();
+
+        let tags = [
+            vec!["a", "b"],
+            vec!["a", "c"],
+            vec!["b", "c"],
+            vec!["a"],
+            vec!["c", "d"],
+            vec!["1", "c"],
+            vec![],
+        ];
+        let documents = tags
+            .iter()
+            .map(|tags| {
+                let tags: Vec<_> = tags
+                    .iter()
+                    .map(|tag| {
+                        tag.parse::<u32>().map_or(serde_json::json!(tag), |n| serde_json::json!(n))
+                    })
+                    .collect();
+                serde_json::json!({ "tags": tags }).as_object().unwrap().clone()
+            })
+            .collect::<Vec<_>>();
+        index.add_documents(documents_batch_reader_from_objects(documents)).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        // the candidates containing a tag that isn't one of the top values
+        let remainder = |top: &[(String, u64)], candidates: &[u32]| {
+            candidates
+                .iter()
+                .filter(|&&docid| {
+                    tags[docid as usize].iter().any(|tag| top.iter().all(|(value, _)| value != tag))
+                })
+                .count() as u64
+        };
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        let (values, other) = distribution.facet_values_with_other("tags", 1).unwrap();
+        assert_eq!(values, vec![(S("c"), 4)]);
+        assert_eq!(other, remainder(&values, &[0, 1, 2, 3, 4, 5, 6]));
+        // the documents with several other values are counted once
+        assert_eq!(other, 6);
+
+        let (values, other) = distribution.facet_values_with_other("tags", 2).unwrap();
+        assert_eq!(values, vec![(S("c"), 4), (S("a"), 3)]);
+        assert_eq!(other, remainder(&values, &[0, 1, 2, 3, 4, 5, 6]));
+        assert_eq!(other, 4);
+
+        let (values, other) = distribution.facet_values_with_other("tags", 10).unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(other, 0);
+
+        distribution.candidates([0, 3, 4, 6].into_iter().collect());
+        let (values, other) = distribution.facet_values_with_other("tags", 1).unwrap();
+        assert_eq!(values, vec![(S("a"), 2)]);
+        assert_eq!(other, remainder(&values, &[0, 3, 4, 6]));
+        assert_eq!(other, 2);
+
+        // the values below the minimum count are neither returned nor counted in the other ones
+        distribution.min_count(2);
+        let (values, other) = distribution.facet_values_with_other("tags", 0).unwrap();
+        assert!(values.is_empty());
+        assert_eq!(other, 2);
+        let (values, other) = distribution.facet_values_with_other("tags", 1).unwrap();
+        assert_eq!(values, vec![(S("a"), 2)]);
+        assert_eq!(other, 0);
+    }
+
+    #[test]
+    fn facet_value_fractions() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("colour") }))
+            .unwrap();
+
+        let documents = documents!([
+            { "colour": "red" },
+            { "colour": "blue" },
+            { "colour": "blue" },
+            { "colour": "green" },
+            { "colour": "blue" },
+            { "colour": "red" },
+            { "colour": "blue" },
+            { "colour": 3 },
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let mut distribution = FacetDistribution::new(&txn, &index);
+        let values = distribution.facet_value_fractions("colour").unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (S("3"), 1, 0.125),
+                (S("blue"), 4, 0.5),
+                (S("green"), 1, 0.125),
+                (S("red"), 2, 0.25),
+            ]
+        );
+        assert_eq!(values.iter().map(|(_, _, fraction)| fraction).sum::<f64>(), 1.0);
+
+        distribution.candidates([0, 1, 3, 5].into_iter().collect());
+        let values = distribution.facet_value_fractions("colour").unwrap();
+        assert_eq!(values, vec![(S("blue"), 1, 0.25), (S("green"), 1, 0.25), (S("red"), 2, 0.5)]);
+
+        // no value is contained in an empty set of candidates, and no fraction is NaN
+        distribution.candidates(RoaringBitmap::new());
+        let values = distribution.facet_value_fractions("colour").unwrap();
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn cross_facet() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("colour"), S("brand") })
+            })
+            .unwrap();
+
+        let documents = documents!([
+            { "colour": "red", "brand": "acme" },
+            { "colour": "red", "brand": "acme" },
+            { "colour": "red", "brand": "globex" },
+            { "colour": "red" },
+            { "colour": "blue", "brand": "globex" },
+            { "colour": "blue", "brand": "initech" },
+            { "colour": ["red", "blue"], "brand": "initech" },
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let values =
+            FacetDistribution::cross_facet(&txn, &index, ("colour", "red"), "brand").unwrap();
+        assert_eq!(values, vec![(S("acme"), 2), (S("globex"), 1), (S("initech"), 1)]);
+
+        let values =
+            FacetDistribution::cross_facet(&txn, &index, ("colour", "blue"), "brand").unwrap();
+        assert_eq!(values, vec![(S("initech"), 2), (S("globex"), 1)]);
+
+        let values =
+            FacetDistribution::cross_facet(&txn, &index, ("brand", "acme"), "colour").unwrap();
+        assert_eq!(values, vec![(S("red"), 2)]);
+
+        let values =
+            FacetDistribution::cross_facet(&txn, &index, ("colour", "green"), "brand").unwrap();
+        assert!(values.is_empty());
+
+        FacetDistribution::cross_facet(&txn, &index, ("size", "xl"), "brand").unwrap_err();
+        FacetDistribution::cross_facet(&txn, &index, ("colour", "red"), "size").unwrap_err();
+    }
+
     #[test]
     fn many_candidates_many_facet_values() {
         let mut index = TempIndex::new_with_map_size(4096 * 10_000);
@@ -140,6 +140,92 @@ where
     Ok(())
 }
 
+/// Lazily iterate over the facet distribution of the candidate documents, by lexicographic order.
+///
+/// Contrary to [`lexicographically_iterate_over_facet_distribution`], the facet values are only
+/// visited when the iterator is advanced, making it possible to take the first values of a field
+/// with millions of distinct values without going through all of them.
+///
+/// Each item is made of:
+/// - the facet value, as a byte slice
+/// - the number of documents among the candidates that contain this facet value
+/// - the id of a document which contains the facet value, see
+///   [`lexicographically_iterate_over_facet_distribution`] for more details.
+pub fn facet_distribution_iter<'t>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    candidates: RoaringBitmap,
+) -> Result<FacetDistributionIter<'t>> {
+    let mut stack = Vec::new();
+    let highest_level = get_highest_level(rtxn, db, field_id)?;
+
+    if let Some(first_bound) = get_first_facet_value::<BytesRefCodec>(rtxn, db, field_id)? {
+        let starting_key =
+            FacetGroupKey { field_id, level: highest_level, left_bound: first_bound };
+        let iter = db.range(rtxn, &(&starting_key..))?.take(usize::MAX);
+        stack.push((iter, candidates));
+    }
+
+    Ok(FacetDistributionIter { rtxn, db, field_id, stack })
+}
+
+/// The iterator returned by [`facet_distribution_iter`].
+pub struct FacetDistributionIter<'t> {
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    /// The groups we are currently visiting, from the highest level to the lowest,
+    /// along with the candidates contained in the parent group.
+    stack: Vec<(
+        std::iter::Take<heed::RoRange<'t, FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>>,
+        RoaringBitmap,
+    )>,
+}
+
+impl<'t> Iterator for FacetDistributionIter<'t> {
+    type Item = Result<(&'t [u8], u64, DocumentId)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (iter, candidates) = self.stack.last_mut()?;
+            let (key, value) = match iter.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            // The range is unbounded on the right and the group size for the highest level is MAX,
+            // so we need to check that we are not iterating over the next field id
+            if key.field_id != self.field_id {
+                self.stack.clear();
+                return None;
+            }
+            let docids_in_common = value.bitmap & &*candidates;
+            if docids_in_common.is_empty() {
+                continue;
+            }
+
+            if key.level == 0 {
+                let any_docid_in_common = docids_in_common.min().unwrap();
+                return Some(Ok((key.left_bound, docids_in_common.len(), any_docid_in_common)));
+            }
+
+            let starting_key = FacetGroupKey {
+                field_id: self.field_id,
+                level: key.level - 1,
+                left_bound: key.left_bound,
+            };
+            match self.db.range(self.rtxn, &(&starting_key..)) {
+                Ok(iter) => self.stack.push((iter.take(value.size as usize), docids_in_common)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
 /// Iterate over the facets values by lexicographic order.
 struct LexicographicFacetDistribution<'t, CB>
 where
@@ -230,7 +316,7 @@ mod tests {
     use heed::BytesDecode;
     use roaring::RoaringBitmap;
 
-    use super::lexicographically_iterate_over_facet_distribution;
+    use super::{facet_distribution_iter, lexicographically_iterate_over_facet_distribution};
     use crate::heed_codec::facet::OrderedF64Codec;
     use crate::milli_snap;
     use crate::search::facet::tests::{get_random_looking_index, get_simple_index};
@@ -290,4 +376,41 @@ mod tests {
             txn.commit().unwrap();
         }
     }
+
+    #[test]
+    fn filter_distribution_iter_matches_callback() {
+        let indexes = [get_simple_index(), get_random_looking_index()];
+        for index in indexes.iter() {
+            let txn = index.env.read_txn().unwrap();
+            let candidates = (0..=255).filter(|x| x % 3 != 0).collect::<RoaringBitmap>();
+
+            let mut expected = Vec::new();
+            lexicographically_iterate_over_facet_distribution(
+                &txn,
+                index.content,
+                0,
+                &candidates,
+                |facet, count, _| {
+                    expected.push((OrderedF64Codec::bytes_decode(facet).unwrap(), count));
+                    Ok(ControlFlow::Continue(()))
+                },
+            )
+            .unwrap();
+
+            let iter = facet_distribution_iter(&txn, index.content, 0, candidates).unwrap();
+            let head: Vec<_> = iter
+                .take(20)
+                .map(|result| {
+                    let (facet, count, _) = result.unwrap();
+                    (OrderedF64Codec::bytes_decode(facet).unwrap(), count)
+                })
+                .collect();
+
+            assert_eq!(head.len(), 20);
+            assert_eq!(head, expected[..20]);
+            assert!(head.iter().all(|(_, count)| *count != 0));
+
+            txn.commit().unwrap();
+        }
+    }
 }
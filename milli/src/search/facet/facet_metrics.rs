@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use roaring::RoaringBitmap;
+
+use crate::error::UserError;
+use crate::{FieldId, Result};
+
+/// A sink receiving, per field id, metrics about the evaluation of a filter.
+///
+/// Every method does nothing by default, so an implementor only needs to override
+/// the metrics it is interested in.
+pub trait FacetMetrics {
+    /// A facet value was read from the database.
+    fn database_read(&mut self, _field_id: FieldId) {}
+
+    /// A level of the facet tree was visited while searching for a range of values.
+    fn level_descended(&mut self, _field_id: FieldId, _level: u8) {}
+
+    /// A condition on the field matched this number of documents.
+    fn documents_matched(&mut self, _field_id: FieldId, _count: u64) {}
+
+    /// An element of an `IN` list on the field matched these documents.
+    fn in_value_matched(&mut self, _field_id: FieldId, _value: &str, _docids: &RoaringBitmap) {}
+
+    /// A string facet value of the field, as it is indexed, matched a condition with these
+    /// documents, e.g. the value of an `=` or one of the values of a `LIKE`.
+    fn string_value_matched(&mut self, _field_id: FieldId, _value: &str, _docids: &RoaringBitmap) {}
+
+    /// A search for a range of values of the field just visited a slice of the `level`, having
+    /// traversed about this `fraction`, between 0 and 1, of the range.
+    ///
+    /// It is reported at most once every few hundreds of facet values read, so slow searches
+    /// over wide ranges can surface their progress without slowing down the others.
+    fn range_progress(&mut self, _field_id: FieldId, _level: u8, _fraction: f64) {}
+
+    /// Returns `true` to stop the evaluation, which then fails with
+    /// [`UserError::FilterBudgetExceeded`]. It is checked after each database read.
+    fn budget_exceeded(&self) -> bool {
+        false
+    }
+}
+
+/// The metrics sink used when nobody is interested in the metrics.
+pub(crate) struct NoopFacetMetrics;
+
+impl FacetMetrics for NoopFacetMetrics {}
+
+/// Forwards the metrics to another sink and stops the evaluation once more than `max_reads`
+/// facet values were read.
+///
+/// The reads are counted in `reads`, shared by the budgets of the leaves of an evaluation
+/// read one by one or by several threads.
+pub(crate) struct ReadBudget<'m> {
+    metrics: &'m mut dyn FacetMetrics,
+    max_reads: u64,
+    reads: &'m AtomicU64,
+}
+
+impl<'m> ReadBudget<'m> {
+    pub(crate) fn new(
+        metrics: &'m mut dyn FacetMetrics,
+        max_reads: u64,
+        reads: &'m AtomicU64,
+    ) -> Self {
+        ReadBudget { metrics, max_reads, reads }
+    }
+}
+
+impl FacetMetrics for ReadBudget<'_> {
+    fn database_read(&mut self, field_id: FieldId) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.metrics.database_read(field_id);
+    }
+
+    fn level_descended(&mut self, field_id: FieldId, level: u8) {
+        self.metrics.level_descended(field_id, level);
+    }
+
+    fn documents_matched(&mut self, field_id: FieldId, count: u64) {
+        self.metrics.documents_matched(field_id, count);
+    }
+
+    fn in_value_matched(&mut self, field_id: FieldId, value: &str, docids: &RoaringBitmap) {
+        self.metrics.in_value_matched(field_id, value, docids);
+    }
+
+    fn string_value_matched(&mut self, field_id: FieldId, value: &str, docids: &RoaringBitmap) {
+        self.metrics.string_value_matched(field_id, value, docids);
+    }
+
+    fn range_progress(&mut self, field_id: FieldId, level: u8, fraction: f64) {
+        self.metrics.range_progress(field_id, level, fraction);
+    }
+
+    fn budget_exceeded(&self) -> bool {
+        self.reads.load(Ordering::Relaxed) > self.max_reads || self.metrics.budget_exceeded()
+    }
+}
+
+/// Collects the documents of the string facet values matched during an evaluation.
+#[derive(Default)]
+pub(crate) struct MatchedStringValues {
+    pub(crate) values: HashMap<(FieldId, String), RoaringBitmap>,
+}
+
+impl FacetMetrics for MatchedStringValues {
+    fn string_value_matched(&mut self, field_id: FieldId, value: &str, docids: &RoaringBitmap) {
+        *self.values.entry((field_id, value.to_string())).or_default() |= docids;
+    }
+}
+
+/// Reports a database read and fails if the budget of the evaluation is exceeded.
+pub(crate) fn record_read(metrics: &mut dyn FacetMetrics, field_id: FieldId) -> Result<()> {
+    metrics.database_read(field_id);
+    if metrics.budget_exceeded() {
+        return Err(UserError::FilterBudgetExceeded.into());
+    }
+    Ok(())
+}
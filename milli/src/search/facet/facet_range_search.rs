@@ -3,11 +3,15 @@ use std::ops::{Bound, RangeBounds};
 use heed::BytesEncode;
 use roaring::RoaringBitmap;
 
-use super::{get_first_facet_value, get_highest_level, get_last_facet_value};
+use super::facet_metrics::record_read;
+use super::{get_first_facet_value, get_highest_level, get_last_facet_value, FacetMetrics};
 use crate::heed_codec::facet::{FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec};
 use crate::heed_codec::BytesRefCodec;
 use crate::Result;
 
+/// The number of facet values read between two reports of the progress of a range search.
+const PROGRESS_INTERVAL: u64 = 256;
+
 /// Find all the document ids for which the given field contains a value contained within
 /// the two bounds.
 pub fn find_docids_of_facet_within_bounds<'t, BoundCodec>(
@@ -17,7 +21,139 @@ pub fn find_docids_of_facet_within_bounds<'t, BoundCodec>(
     left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
     right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
     docids: &mut RoaringBitmap,
+    metrics: &mut dyn FacetMetrics,
+) -> Result<()>
+where
+    BoundCodec: for<'a> BytesEncode<'a>,
+    for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
+{
+    search_within_bounds::<BoundCodec>(
+        rtxn, db, field_id, left, right, None, None, docids, metrics,
+    )?;
+    Ok(())
+}
+
+/// Same as [`find_docids_of_facet_within_bounds`], but skips the groups of values containing
+/// none of the `candidates`, without reading the levels below them, e.g. to evaluate a range
+/// within the documents already selected by the other operands of an `AND`.
+///
+/// The groups containing some candidates are still taken whole, so the document ids are only
+/// exact once intersected with the candidates.
+#[allow(clippy::too_many_arguments)]
+pub fn find_docids_of_facet_within_bounds_among<'t, BoundCodec>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    candidates: &RoaringBitmap,
+    docids: &mut RoaringBitmap,
+    metrics: &mut dyn FacetMetrics,
 ) -> Result<()>
+where
+    BoundCodec: for<'a> BytesEncode<'a>,
+    for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
+{
+    search_within_bounds::<BoundCodec>(
+        rtxn,
+        db,
+        field_id,
+        left,
+        right,
+        None,
+        Some(candidates),
+        docids,
+        metrics,
+    )?;
+    Ok(())
+}
+
+/// Same as [`find_docids_of_facet_within_bounds`], but without descending more than
+/// `max_levels_down` levels below the highest level of the database.
+///
+/// The groups that only partially intersect the bounds at the lowest visited level are
+/// taken whole, so the document ids are a superset of the exact result, but much fewer
+/// bitmaps are read and merged for wide ranges. Returns `true` if such a group was taken,
+/// i.e. if the document ids may contain values outside the bounds.
+#[allow(clippy::too_many_arguments)]
+pub fn find_approximate_docids_of_facet_within_bounds<'t, BoundCodec>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    max_levels_down: u8,
+    docids: &mut RoaringBitmap,
+    metrics: &mut dyn FacetMetrics,
+) -> Result<bool>
+where
+    BoundCodec: for<'a> BytesEncode<'a>,
+    for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
+{
+    search_within_bounds::<BoundCodec>(
+        rtxn,
+        db,
+        field_id,
+        left,
+        right,
+        Some(max_levels_down),
+        None,
+        docids,
+        metrics,
+    )
+}
+
+/// Iterates over the values of the given field contained within the two bounds, yielding
+/// the document ids of each value with its encoded bytes.
+///
+/// The values are yielded in ascending order, or in descending order when `ascending` is `false`,
+/// e.g. to display the highest values first. Unlike [`find_docids_of_facet_within_bounds`],
+/// which merges the groups of the highest levels in no particular order, the values are read
+/// one by one from the level 0, so a document is yielded once per value it contains.
+pub fn facet_range_iter<'t>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: Bound<&[u8]>,
+    right: Bound<&[u8]>,
+    ascending: bool,
+) -> heed::Result<impl Iterator<Item = heed::Result<(RoaringBitmap, &'t [u8])>> + 't> {
+    let key = |left_bound| FacetGroupKey { field_id, level: 0, left_bound };
+    let start = match left {
+        Bound::Included(left) => Bound::Included(key(left)),
+        Bound::Excluded(left) => Bound::Excluded(key(left)),
+        // the empty bound sorts before all the values of the level
+        Bound::Unbounded => Bound::Included(key(&[][..])),
+    };
+    let end = match right {
+        Bound::Included(right) => Bound::Included(key(right)),
+        Bound::Excluded(right) => Bound::Excluded(key(right)),
+        // and the first key of the level 1 after all of them
+        Bound::Unbounded => {
+            Bound::Excluded(FacetGroupKey { field_id, level: 1, left_bound: &[][..] })
+        }
+    };
+
+    let iter = if ascending {
+        itertools::Either::Left(db.range(rtxn, &(start, end))?)
+    } else {
+        itertools::Either::Right(db.rev_range(rtxn, &(start, end))?)
+    };
+    Ok(iter.map(|result| result.map(|(key, value)| (value.bitmap, key.left_bound))))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_within_bounds<'t, BoundCodec>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    max_levels_down: Option<u8>,
+    candidates: Option<&RoaringBitmap>,
+    docids: &mut RoaringBitmap,
+    metrics: &mut dyn FacetMetrics,
+) -> Result<bool>
 where
     BoundCodec: for<'a> BytesEncode<'a>,
     for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
@@ -47,36 +183,83 @@ where
         Bound::Unbounded => Bound::Unbounded,
     };
     let db = db.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
-    let mut f = FacetRangeSearch { rtxn, db, field_id, left, right, docids };
     let highest_level = get_highest_level(rtxn, db, field_id)?;
+    let lowest_level = max_levels_down.map_or(0, |down| highest_level.saturating_sub(down));
+    let mut f = FacetRangeSearch {
+        rtxn,
+        db,
+        field_id,
+        left,
+        right,
+        lowest_level,
+        approximate: false,
+        candidates,
+        docids,
+        metrics,
+        entries_read: 0,
+    };
 
     if let Some(starting_left_bound) = get_first_facet_value::<BytesRefCodec>(rtxn, db, field_id)? {
         let rightmost_bound =
             Bound::Included(get_last_facet_value::<BytesRefCodec>(rtxn, db, field_id)?.unwrap()); // will not fail because get_first_facet_value succeeded
         let group_size = usize::MAX;
         f.run(highest_level, starting_left_bound, rightmost_bound, group_size)?;
-        Ok(())
+        Ok(f.approximate)
     } else {
-        Ok(())
+        Ok(false)
     }
 }
 
+/// A slice of a level whose elements are still to be visited by [`FacetRangeSearch::run`].
+struct PendingSlice<'t> {
+    level: u8,
+    /// The left bound of the first element of the slice
+    starting_left_bound: &'t [u8],
+    /// The right bound of the last element of the slice
+    rightmost_bound: Bound<&'t [u8]>,
+    /// The number of elements of the slice
+    group_size: usize,
+    /// The fraction of the whole search the slice stands for, set once its parent is visited
+    share: f64,
+}
+
 /// Fetch the document ids that have a facet with a value between the two given bounds
-struct FacetRangeSearch<'t, 'b, 'bitmap> {
+struct FacetRangeSearch<'t, 'b, 'bitmap, 'm> {
     rtxn: &'t heed::RoTxn<'t>,
     db: heed::Database<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>,
     field_id: u16,
     left: Bound<&'b [u8]>,
     right: Bound<&'b [u8]>,
+    /// The level below which the search doesn't descend, 0 for an exact search
+    lowest_level: u8,
+    /// Whether a group only partially within the bounds was taken whole at the `lowest_level`
+    approximate: bool,
+    /// The documents outside of which the groups of values are skipped, if any
+    candidates: Option<&'b RoaringBitmap>,
     docids: &'bitmap mut RoaringBitmap,
+    metrics: &'m mut dyn FacetMetrics,
+    /// The number of elements read so far, at any level
+    entries_read: u64,
 }
-impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
+impl<'t, 'b, 'bitmap, 'm> FacetRangeSearch<'t, 'b, 'bitmap, 'm> {
+    /// Returns `true` if the group contains none of the candidates, in which case neither its
+    /// documents nor its children are needed.
+    fn excludes_candidates(&self, group_docids: &RoaringBitmap) -> bool {
+        self.candidates.map_or(false, |candidates| candidates.is_disjoint(group_docids))
+    }
+
+    fn record_read(&mut self) -> Result<()> {
+        self.entries_read += 1;
+        record_read(self.metrics, self.field_id)
+    }
+
     fn run_level_0(&mut self, starting_left_bound: &'t [u8], group_size: usize) -> Result<()> {
         let left_key =
             FacetGroupKey { field_id: self.field_id, level: 0, left_bound: starting_left_bound };
         let iter = self.db.range(self.rtxn, &(left_key..))?.take(group_size);
         for el in iter {
             let (key, value) = el?;
+            self.record_read()?;
             // the right side of the iter range is unbounded, so we need to make sure that we are not iterating
             // on the next field id
             if key.field_id != self.field_id {
@@ -110,19 +293,13 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
         Ok(())
     }
 
-    /// Recursive part of the algorithm for level > 0.
+    /// Visits the elements of a level and, through a stack of the slices of the levels below
+    /// that are still to visit, their children intersecting the bounds.
     ///
-    /// It works by visiting a slice of a level and checking whether the range asscociated
-    /// with each visited element is contained within the bounds.
-    ///
-    /// 1. So long as the element's range is less than the left bound, we do nothing and keep iterating
-    /// 2. If the element's range is fully contained by the bounds, then all of its docids are added to
-    /// the roaring bitmap.
-    /// 3. If the element's range merely intersects the bounds, then we call the algorithm recursively
-    /// on the children of the element from the level below.
-    /// 4. If the element's range is greater than the right bound, we do nothing and stop iterating.
-    /// Note that the right bound is found through either the `left_bound` of the *next* element,
-    /// or from the `rightmost_bound` argument
+    /// The slices are visited iteratively and not recursively, from left to right, so the
+    /// depth of the levels doesn't grow the call stack. Every [`PROGRESS_INTERVAL`] elements
+    /// read, the fraction of the range traversed so far is reported to the metrics, each
+    /// element of a slice standing for an equal share of it.
     ///
     /// ## Arguments
     /// - `level`: the level being visited
@@ -136,6 +313,57 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
         rightmost_bound: Bound<&'t [u8]>,
         group_size: usize,
     ) -> Result<()> {
+        let mut pending = vec![PendingSlice {
+            level,
+            starting_left_bound,
+            rightmost_bound,
+            group_size,
+            share: 1.0,
+        }];
+        let mut traversed = 0.0;
+        let mut reported_entries = 0;
+        while let Some(slice) = pending.pop() {
+            let (level, share) = (slice.level, slice.share);
+            let entries_before = self.entries_read;
+            let first_child = pending.len();
+            self.visit(slice, &mut pending)?;
+
+            // the slices of the children are pushed from left to right, the stack being LIFO
+            // we reverse them to visit them in this order
+            let children = &mut pending[first_child..];
+            children.reverse();
+            let child_share = share / (self.entries_read - entries_before).max(1) as f64;
+            for child in children.iter_mut() {
+                child.share = child_share;
+            }
+            traversed += share - child_share * children.len() as f64;
+
+            if self.entries_read - reported_entries >= PROGRESS_INTERVAL {
+                reported_entries = self.entries_read;
+                self.metrics.range_progress(self.field_id, level, f64::min(traversed, 1.0));
+            }
+        }
+        Ok(())
+    }
+
+    /// Visits a slice of a level and checks whether the range associated with each visited
+    /// element is contained within the bounds.
+    ///
+    /// 1. So long as the element's range is less than the left bound, we do nothing and keep iterating
+    /// 2. If the element's range is fully contained by the bounds, then all of its docids are added to
+    /// the roaring bitmap.
+    /// 3. If the element's range merely intersects the bounds, then the slice of its children from
+    /// the level below is pushed to the `pending` slices.
+    /// 4. If the element's range is greater than the right bound, we do nothing and stop iterating.
+    /// Note that the right bound is found through either the `left_bound` of the *next* element,
+    /// or from the `rightmost_bound` of the slice
+    fn visit(
+        &mut self,
+        slice: PendingSlice<'t>,
+        pending: &mut Vec<PendingSlice<'t>>,
+    ) -> Result<()> {
+        let PendingSlice { level, starting_left_bound, rightmost_bound, group_size, .. } = slice;
+        self.metrics.level_descended(self.field_id, level);
         if level == 0 {
             return self.run_level_0(starting_left_bound, group_size);
         }
@@ -146,8 +374,10 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
 
         // We iterate over the range while keeping in memory the previous value
         let (mut previous_key, mut previous_value) = iter.next().unwrap()?;
+        self.record_read()?;
         for el in iter {
             let (next_key, next_value) = el?;
+            self.record_read()?;
             // the right of the iter range is potentially unbounded (e.g. if `group_size` is usize::MAX),
             // so we need to make sure that we are not iterating on the next field id
             if next_key.field_id != self.field_id {
@@ -180,6 +410,11 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
             if should_stop {
                 return Ok(());
             }
+            if self.excludes_candidates(&previous_value.bitmap) {
+                previous_key = next_key;
+                previous_value = next_value;
+                continue;
+            }
             // should we take the whole thing, without recursing down?
             let should_take_whole_group = {
                 let left_condition = match self.left {
@@ -200,15 +435,21 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
                 previous_value = next_value;
                 continue;
             }
-            // from here, we should visit the children of the previous element and
-            // call the function recursively
-
-            let level = level - 1;
-            let starting_left_bound = previous_key.left_bound;
-            let rightmost_bound = Bound::Excluded(next_key.left_bound);
-            let group_size = previous_value.size as usize;
+            // from here, we should visit the children of the previous element,
+            // unless we can't go any deeper
 
-            self.run(level, starting_left_bound, rightmost_bound, group_size)?;
+            if level <= self.lowest_level {
+                *self.docids |= &previous_value.bitmap;
+                self.approximate = true;
+            } else {
+                pending.push(PendingSlice {
+                    level: level - 1,
+                    starting_left_bound: previous_key.left_bound,
+                    rightmost_bound: Bound::Excluded(next_key.left_bound),
+                    group_size: previous_value.size as usize,
+                    share: 0.0,
+                });
+            }
 
             previous_key = next_key;
             previous_value = next_value;
@@ -241,7 +482,7 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
                 Bound::Unbounded => false,
             }
         };
-        if should_stop {
+        if should_stop || self.excludes_candidates(&previous_value.bitmap) {
             return Ok(());
         }
         // should we take the whole thing, without recursing down?
@@ -292,12 +533,17 @@ impl<'t, 'b, 'bitmap> FacetRangeSearch<'t, 'b, 'bitmap> {
         };
         if should_take_whole_group {
             *self.docids |= &previous_value.bitmap;
+        } else if level <= self.lowest_level {
+            *self.docids |= &previous_value.bitmap;
+            self.approximate = true;
         } else {
-            let level = level - 1;
-            let starting_left_bound = previous_key.left_bound;
-            let group_size = previous_value.size as usize;
-
-            self.run(level, starting_left_bound, rightmost_bound, group_size)?;
+            pending.push(PendingSlice {
+                level: level - 1,
+                starting_left_bound: previous_key.left_bound,
+                rightmost_bound,
+                group_size: previous_value.size as usize,
+                share: 0.0,
+            });
         }
 
         Ok(())
@@ -310,14 +556,24 @@ mod tests {
 
     use roaring::RoaringBitmap;
 
-    use super::find_docids_of_facet_within_bounds;
+    use heed::{BytesDecode, BytesEncode};
+
+    use super::{
+        facet_range_iter, find_approximate_docids_of_facet_within_bounds,
+        find_docids_of_facet_within_bounds,
+    };
     use crate::heed_codec::facet::{FacetGroupKeyCodec, OrderedF64Codec};
+    use crate::heed_codec::BytesRefCodec;
     use crate::milli_snap;
+    use crate::search::facet::facet_metrics::NoopFacetMetrics;
     use crate::search::facet::tests::{
         get_random_looking_index, get_random_looking_index_with_multiple_field_ids,
         get_simple_index, get_simple_index_with_multiple_field_ids,
     };
+    use crate::search::facet::FacetMetrics;
     use crate::snapshot_tests::display_bitmap;
+    use crate::update::facet::test_helpers::FacetIndex;
+    use crate::FieldId;
 
     #[test]
     fn random_looking_index_snap() {
@@ -343,6 +599,300 @@ mod tests {
         milli_snap!(format!("{index}"), @"a4893298218f682bc76357f46777448c");
     }
 
+    #[test]
+    fn filter_range_approximate() {
+        let indexes = [get_simple_index(), get_random_looking_index()];
+        for (i, index) in indexes.iter().enumerate() {
+            let txn = index.env.read_txn().unwrap();
+            let db = index.content.remap_key_type::<FacetGroupKeyCodec<OrderedF64Codec>>();
+            let start = Bound::Included(3.);
+            let end = Bound::Excluded(250.);
+
+            let mut exact = RoaringBitmap::new();
+            find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                &txn,
+                db,
+                0,
+                &start,
+                &end,
+                &mut exact,
+                &mut NoopFacetMetrics,
+            )
+            .unwrap();
+
+            // the less levels we descend, the bigger the superset
+            let mut previous = None;
+            for max_levels_down in 0..4 {
+                let mut docids = RoaringBitmap::new();
+                let approximate =
+                    find_approximate_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                        &txn,
+                        db,
+                        0,
+                        &start,
+                        &end,
+                        max_levels_down,
+                        &mut docids,
+                        &mut NoopFacetMetrics,
+                    )
+                    .unwrap();
+                assert!(docids.is_superset(&exact));
+                if docids != exact {
+                    assert!(approximate);
+                }
+                if let Some(previous) = previous {
+                    assert!(docids.is_subset(&previous));
+                }
+                previous = Some(docids);
+            }
+            // with the highest levels only, the range of the simple index can't be exact
+            let mut docids = RoaringBitmap::new();
+            let approximate = find_approximate_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                &txn,
+                db,
+                0,
+                &start,
+                &end,
+                0,
+                &mut docids,
+                &mut NoopFacetMetrics,
+            )
+            .unwrap();
+            if i == 0 {
+                assert!(approximate);
+                assert!(docids.len() > exact.len());
+            }
+
+            // descending through all the levels gives the exact result
+            let mut docids = RoaringBitmap::new();
+            let approximate = find_approximate_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                &txn,
+                db,
+                0,
+                &start,
+                &end,
+                u8::MAX,
+                &mut docids,
+                &mut NoopFacetMetrics,
+            )
+            .unwrap();
+            assert!(!approximate);
+            assert_eq!(docids, exact);
+            txn.commit().unwrap();
+        }
+    }
+
+    #[test]
+    fn filter_range_matches_level_0() {
+        fn encode(bound: &Bound<f64>) -> Bound<Vec<u8>> {
+            let encode = |x| OrderedF64Codec::bytes_encode(x).unwrap().into_owned();
+            match bound {
+                Bound::Included(x) => Bound::Included(encode(x)),
+                Bound::Excluded(x) => Bound::Excluded(encode(x)),
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        }
+        fn as_slice(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+            match bound {
+                Bound::Included(x) => Bound::Included(x.as_slice()),
+                Bound::Excluded(x) => Bound::Excluded(x.as_slice()),
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        }
+
+        let indexes = [
+            get_simple_index(),
+            get_random_looking_index(),
+            get_simple_index_with_multiple_field_ids(),
+            get_random_looking_index_with_multiple_field_ids(),
+        ];
+        let shapes = [
+            (0., 0.),
+            (3., 250.),
+            (17., 18.),
+            (100., 99.),
+            (-10., 1000.),
+            (255., 255.),
+            (64., 192.),
+        ];
+
+        for index in &indexes {
+            let txn = index.env.read_txn().unwrap();
+            let db = index.content.remap_key_type::<FacetGroupKeyCodec<OrderedF64Codec>>();
+            let bytes_db = index.content.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+            for field_id in 0..2 {
+                for (left, right) in shapes {
+                    for start in [Bound::Included(left), Bound::Excluded(left), Bound::Unbounded] {
+                        for end in
+                            [Bound::Included(right), Bound::Excluded(right), Bound::Unbounded]
+                        {
+                            let mut docids = RoaringBitmap::new();
+                            find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                                &txn,
+                                db,
+                                field_id,
+                                &start,
+                                &end,
+                                &mut docids,
+                                &mut NoopFacetMetrics,
+                            )
+                            .unwrap();
+
+                            // the union of the values read one by one from the level 0
+                            let (left_bytes, right_bytes) = (encode(&start), encode(&end));
+                            let mut expected = RoaringBitmap::new();
+                            let iter = facet_range_iter(
+                                &txn,
+                                bytes_db,
+                                field_id,
+                                as_slice(&left_bytes),
+                                as_slice(&right_bytes),
+                                true,
+                            )
+                            .unwrap();
+                            for result in iter {
+                                expected |= result.unwrap().0;
+                            }
+                            assert_eq!(docids, expected, "{field_id}: {start:?} {end:?}");
+                        }
+                    }
+                }
+            }
+            txn.commit().unwrap();
+        }
+    }
+
+    #[test]
+    fn filter_range_progress() {
+        #[derive(Default)]
+        struct ProgressMetrics {
+            progress: Vec<(u8, f64)>,
+        }
+
+        impl FacetMetrics for ProgressMetrics {
+            fn range_progress(&mut self, _field_id: FieldId, level: u8, fraction: f64) {
+                self.progress.push((level, fraction));
+            }
+        }
+
+        // a wide highest level, whose elements are all read by a wide range
+        let index = FacetIndex::<OrderedF64Codec>::new(4, 8, u8::MAX);
+        let mut txn = index.env.write_txn().unwrap();
+        for i in 0..4000u32 {
+            index.insert(&mut txn, 0, &(i as f64), &RoaringBitmap::from_iter([i]));
+        }
+        txn.commit().unwrap();
+
+        let txn = index.env.read_txn().unwrap();
+        let db = index.content.remap_key_type::<FacetGroupKeyCodec<OrderedF64Codec>>();
+        let (start, end) = (Bound::Included(3.5), Bound::Excluded(3990.5));
+        let mut docids = RoaringBitmap::new();
+        let mut metrics = ProgressMetrics::default();
+        find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+            &txn,
+            db,
+            0,
+            &start,
+            &end,
+            &mut docids,
+            &mut metrics,
+        )
+        .unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter(4..=3990));
+
+        assert!(!metrics.progress.is_empty());
+        let mut previous = 0.0;
+        for &(_level, fraction) in &metrics.progress {
+            assert!(previous <= fraction && fraction <= 1.0);
+            previous = fraction;
+        }
+
+        // a narrow range reads too few values to report its progress
+        let (start, end) = (Bound::Included(100.), Bound::Included(120.));
+        let mut metrics = ProgressMetrics::default();
+        find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+            &txn,
+            db,
+            0,
+            &start,
+            &end,
+            &mut RoaringBitmap::new(),
+            &mut metrics,
+        )
+        .unwrap();
+        assert!(metrics.progress.is_empty());
+    }
+
+    #[test]
+    fn range_iter_ascending_and_descending() {
+        let start = OrderedF64Codec::bytes_encode(&3.).unwrap();
+        let end = OrderedF64Codec::bytes_encode(&250.).unwrap();
+
+        let index = get_simple_index_with_multiple_field_ids();
+        let txn = index.env.read_txn().unwrap();
+        let db = index.content.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+        let ids = |left, right, ascending| {
+            facet_range_iter(&txn, db, 1, left, right, ascending)
+                .unwrap()
+                .map(|result| result.unwrap().0.iter().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+                .concat()
+        };
+
+        let ascending = ids(Bound::Included(&start[..]), Bound::Excluded(&end[..]), true);
+        assert_eq!(ascending, (3..250).collect::<Vec<_>>());
+        let descending = ids(Bound::Included(&start[..]), Bound::Excluded(&end[..]), false);
+        assert_eq!(descending, (3..250).rev().collect::<Vec<_>>());
+
+        let descending = ids(Bound::Excluded(&start[..]), Bound::Unbounded, false);
+        assert_eq!(descending, (4..256).rev().collect::<Vec<_>>());
+        let ascending = ids(Bound::Unbounded, Bound::Included(&end[..]), true);
+        assert_eq!(ascending, (0..=250).collect::<Vec<_>>());
+        txn.commit().unwrap();
+
+        // the values are yielded in order, but not the documents ids, and the same documents
+        // as the ones of a range search are yielded
+        let index = get_random_looking_index();
+        let txn = index.env.read_txn().unwrap();
+        let db = index.content.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+        let values = |ascending| {
+            facet_range_iter(
+                &txn,
+                db,
+                0,
+                Bound::Included(&start[..]),
+                Bound::Excluded(&end[..]),
+                ascending,
+            )
+            .unwrap()
+            .map(|result| {
+                let (docids, value) = result.unwrap();
+                (docids, OrderedF64Codec::bytes_decode(value).unwrap())
+            })
+            .collect::<Vec<_>>()
+        };
+        let ascending = values(true);
+        let mut descending = values(false);
+        assert!(ascending.windows(2).all(|window| window[0].1 < window[1].1));
+        descending.reverse();
+        assert_eq!(ascending, descending);
+
+        let mut expected = RoaringBitmap::new();
+        find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+            &txn,
+            index.content.remap_key_type::<FacetGroupKeyCodec<OrderedF64Codec>>(),
+            0,
+            &Bound::Included(3.),
+            &Bound::Excluded(250.),
+            &mut expected,
+            &mut NoopFacetMetrics,
+        )
+        .unwrap();
+        let docids = ascending.into_iter().map(|(docids, _)| docids).collect::<RoaringBitmap>();
+        assert_eq!(docids, expected);
+        txn.commit().unwrap();
+    }
+
     #[test]
     fn filter_range_increasing() {
         let indexes = [
@@ -366,6 +916,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
@@ -385,6 +936,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
@@ -419,6 +971,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 results.push_str(&format!("{i} <= . <= 255 : {}\n", display_bitmap(&docids)));
@@ -440,6 +993,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 results.push_str(&format!("{i} < . < 255 : {}\n", display_bitmap(&docids)));
@@ -475,6 +1029,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 results.push_str(&format!(
@@ -500,6 +1055,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 results.push_str(&format!(
@@ -538,6 +1094,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
@@ -557,6 +1114,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
@@ -572,6 +1130,7 @@ mod tests {
                 &Bound::Unbounded,
                 &Bound::Unbounded,
                 &mut docids,
+                &mut NoopFacetMetrics,
             )
             .unwrap();
             milli_snap!(
@@ -587,6 +1146,7 @@ mod tests {
                 &Bound::Unbounded,
                 &Bound::Unbounded,
                 &mut docids,
+                &mut NoopFacetMetrics,
             )
             .unwrap();
             milli_snap!(
@@ -622,6 +1182,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
@@ -635,6 +1196,7 @@ mod tests {
                     &start,
                     &end,
                     &mut docids,
+                    &mut NoopFacetMetrics,
                 )
                 .unwrap();
                 #[allow(clippy::format_push_string)]
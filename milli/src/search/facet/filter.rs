@@ -1,18 +1,37 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display};
+use std::io::Read;
+use std::mem;
 use std::ops::Bound::{self, Excluded, Included};
+use std::ops::{RangeBounds, RangeInclusive};
+use std::sync::atomic::AtomicU64;
 
 use either::Either;
-pub use filter_parser::{Condition, Error as FPError, FilterCondition, Token};
+pub use filter_parser::{
+    Condition, Error as FPError, FilterCondition, Token, TypeAnnotation, ValueFunction,
+};
 use roaring::RoaringBitmap;
 use serde_json::Value;
+use time::macros::format_description;
+use time::{Date, UtcOffset};
+use unicode_normalization::UnicodeNormalization;
 
-use super::facet_range_search;
-use crate::error::{Error, UserError};
+use super::facet_metrics::{record_read, MatchedStringValues, NoopFacetMetrics, ReadBudget};
+use super::{facet_range_search, get_first_facet_value, FacetColumns, FacetMetrics, ReadTxnSource};
+use crate::error::{Error, FieldIdMapMissingEntry, UserError};
+use crate::facet::FacetType;
 use crate::heed_codec::facet::{
     FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, OrderedF64Codec,
 };
-use crate::{distance_between_two_points, lat_lng_to_xyz, FieldId, Index, Result};
+use crate::heed_codec::BytesRefCodec;
+use crate::{
+    distance_between_two_points, lat_lng_to_xyz, DocumentId, FieldId, FieldsIdsMap, Index, Result,
+};
+
+#[cfg(test)]
+pub(crate) mod mock;
 
 /// The maximum number of filters the filter AST can process.
 const MAX_FILTER_DEPTH: usize = 2000;
@@ -20,6 +39,52 @@ const MAX_FILTER_DEPTH: usize = 2000;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Filter<'a> {
     condition: FilterCondition<'a>,
+    check_fields: bool,
+    check_values: bool,
+    strict_types: bool,
+    cache_string_lookups: bool,
+    not_equal_present_only: bool,
+    document_id_field: Option<String>,
+    in_list_order: InListOrder,
+    has_all_duplicates: HasAllDuplicates,
+    /// The aliases used by the filter and the attributes they were resolved to.
+    aliases: BTreeMap<String, String>,
+    max_database_reads: Option<u64>,
+    boolean_fields: BTreeSet<String>,
+    /// The units of the fields, with the number of base units each of them is worth.
+    units: BTreeMap<String, BTreeMap<String, u64>>,
+    /// The type of the numbers of the fields, overriding the one of their values in the index.
+    number_types: BTreeMap<String, NumberType>,
+    number_locale: NumberLocale,
+}
+
+/// The string values looked up during an evaluation.
+#[derive(Default)]
+struct StringLookups {
+    /// The documents of the values already looked up, when the lookups are cached.
+    cache: Option<HashMap<(FieldId, String), RoaringBitmap>>,
+    /// The interned fields of the index, read on the first lookup.
+    interned_fields: Option<HashSet<FieldId>>,
+}
+
+impl StringLookups {
+    fn new(cache: bool) -> Self {
+        StringLookups { cache: cache.then(HashMap::new), interned_fields: None }
+    }
+
+    /// Returns `true` if the string values of the field are interned, see
+    /// [`Index::interned_string_facets`].
+    fn is_interned(
+        &mut self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+    ) -> Result<bool> {
+        if self.interned_fields.is_none() {
+            self.interned_fields = Some(index.interned_string_facets_ids(rtxn)?);
+        }
+        Ok(self.interned_fields.as_ref().map_or(false, |fields| fields.contains(&field_id)))
+    }
 }
 
 #[derive(Debug)]
@@ -55,6 +120,25 @@ impl Display for BadGeoError {
 enum FilterError<'a> {
     AttributeNotFilterable { attribute: &'a str, filterable_fields: HashSet<String> },
     ParseGeoError(BadGeoError),
+    NumericOperatorOnString { attribute: String, operator: &'static str },
+    InvalidAnnotatedValue { value: String, annotation: TypeAnnotation },
+    AnnotationConflict { attribute: String, annotation: TypeAnnotation, indexed: FacetType },
+    AttributeNotFaceted { attribute: &'a str },
+    DanglingAlias { alias: &'a str, attribute: &'a str },
+    UnknownValue { attribute: &'a str, value: &'a str },
+    DuplicateHasAllValue { attribute: &'a str, value: &'a str },
+    BooleanOnNumericField { attribute: &'a str, value: &'a str },
+    UnknownUnit { attribute: &'a str, unit: &'a str, units: Vec<String> },
+    WildcardMatchesNoAttribute { wildcard: &'a str },
+    InvalidDocumentId { value: &'a str },
+    InvalidStepRange { value: &'a str },
+    InvalidWindow { value: &'a str },
+    WindowOnFloats { attribute: String },
+    StrictTypeMismatch { attribute: String, value: &'a str, floats: bool },
+    NotAnInteger { attribute: &'a str, value: &'a str },
+    UnsupportedFunctionOperator { function: ValueFunction },
+    UnboundPlaceholder,
+    UnboundExpression,
     TooDeep,
 }
 impl<'a> std::error::Error for FilterError<'a> {}
@@ -90,6 +174,105 @@ impl<'a> Display for FilterError<'a> {
                     )
                 }
             }
+            Self::NumericOperatorOnString { attribute, operator } => write!(
+                f,
+                "Attribute `{}` only contains string values, but the `{}` operator can only be applied to numbers. \
+Use `=`, `!=`, or `IN` to filter on string values instead.",
+                attribute, operator,
+            ),
+            Self::InvalidAnnotatedValue { value, annotation } => {
+                write!(f, "Value `{}` is not a valid `{}`.", value, annotation)
+            }
+            Self::AnnotationConflict { attribute, annotation, indexed } => write!(
+                f,
+                "The value is annotated as `{}`, but attribute `{}` only contains {} values.",
+                annotation, attribute, indexed,
+            ),
+            Self::AttributeNotFaceted { attribute } => write!(
+                f,
+                "Attribute `{}` is not faceted in this index anymore. It may have been removed from the settings or never indexed.",
+                attribute,
+            ),
+            Self::DanglingAlias { alias, attribute } => write!(
+                f,
+                "Alias `{}` refers to the attribute `{}`, which does not exist in this index.",
+                alias, attribute,
+            ),
+            Self::UnknownValue { attribute, value } => write!(
+                f,
+                "Attribute `{}` does not contain the value `{}`.",
+                attribute, value,
+            ),
+            Self::DuplicateHasAllValue { attribute, value } => write!(
+                f,
+                "Value `{}` is listed more than once in the `HAS_ALL` list of attribute `{}`.",
+                value, attribute,
+            ),
+            Self::BooleanOnNumericField { attribute, value } => write!(
+                f,
+                "Attribute `{}` contains numbers and is not boolean-like, it can't be compared with `{}`.",
+                attribute, value,
+            ),
+            Self::UnknownUnit { attribute, unit, units } => write!(
+                f,
+                "Unit `{}` is not a unit of attribute `{}`. Available units are: `{}`.",
+                unit,
+                attribute,
+                units.join(" "),
+            ),
+            Self::WildcardMatchesNoAttribute { wildcard } => write!(
+                f,
+                "Attribute wildcard `{}` does not match any attribute of this index.",
+                wildcard,
+            ),
+            Self::InvalidDocumentId { value } => write!(
+                f,
+                "`{}` is not a valid document id. Document ids must be positive integers.",
+                value,
+            ),
+            Self::InvalidStepRange { value } => write!(
+                f,
+                "`{}` is not valid in a `STEP` range. The bounds must be integers and the step a positive integer.",
+                value,
+            ),
+            Self::InvalidWindow { value } => write!(
+                f,
+                "`{}` is not valid in a `FROM` window. The start must be an integer and the `LIMIT` a non-negative integer.",
+                value,
+            ),
+            Self::WindowOnFloats { attribute } => write!(
+                f,
+                "Attribute `{}` contains floats, but a `FROM` window can only be applied to integers. Use `TO` or an interval instead.",
+                attribute,
+            ),
+            Self::StrictTypeMismatch { attribute, value, floats: true } => write!(
+                f,
+                "Attribute `{}` contains floats, but `{}` is an integer. Write it as a float, e.g. `{}.0`, or disable the strict types.",
+                attribute, value, value,
+            ),
+            Self::StrictTypeMismatch { attribute, value, floats: false } => write!(
+                f,
+                "Attribute `{}` only contains integers, but `{}` is a float. Write it as an integer or disable the strict types.",
+                attribute, value,
+            ),
+            Self::NotAnInteger { attribute, value } => write!(
+                f,
+                "Attribute `{}` is set to contain integers, but `{}` is not an integer.",
+                attribute, value,
+            ),
+            Self::UnsupportedFunctionOperator { function } => write!(
+                f,
+                "The `{}` function can only be used with the `=`, `!=`, `>`, `>=`, `<`, `<=` and `TO` operators or with an interval.",
+                function,
+            ),
+            Self::UnboundPlaceholder => write!(
+                f,
+                "The `?` placeholder has not been bound to a value. Use `Filter::bind` before evaluating the filter.",
+            ),
+            Self::UnboundExpression => write!(
+                f,
+                "The expression uses parameters that have not been bound. Use `Filter::bind_named` before evaluating the filter.",
+            ),
             Self::TooDeep => write!(
                 f,
                 "Too many filter conditions, can't process more than {} filters.",
@@ -201,12 +384,66 @@ impl<'a> Filter<'a> {
             return Err(token.as_external_error(FilterError::TooDeep).into());
         }
 
-        Ok(Some(Self { condition: and }))
+        Ok(Some(Self::from(and)))
     }
 
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(expression: &'a str) -> Result<Option<Self>> {
-        let condition = match FilterCondition::parse(expression) {
+        Self::from_str_with_legacy_syntax(expression, false)
+    }
+
+    /// Parses the filter like [`Filter::from_str`], the attributes found in `aliases` being
+    /// replaced by the attribute they refer to, e.g. `price` by `product_price`.
+    ///
+    /// The aliases are resolved before the attributes are looked up in the index, and an alias
+    /// referring to an attribute that does not exist in the index makes the evaluation fail.
+    pub fn from_str_with_aliases(
+        expression: &'a str,
+        aliases: &HashMap<String, String>,
+    ) -> Result<Option<Self>> {
+        let mut filter = match Self::from_str(expression)? {
+            Some(filter) => filter,
+            None => return Ok(None),
+        };
+
+        let mut used_aliases = BTreeMap::new();
+        for attribute in filter.condition.attributes_mut() {
+            if let Some(resolved) = aliases.get(attribute.value()) {
+                used_aliases.insert(attribute.value().to_string(), resolved.clone());
+                attribute.rename(resolved.clone());
+            }
+        }
+        filter.aliases = used_aliases;
+
+        Ok(Some(filter))
+    }
+
+    /// Parses the filter like [`Filter::from_str`], the errors about the numbers that can't be
+    /// parsed being written for the users of the `locale`, e.g. showing how `1,5` is written in
+    /// a filter to the users writing their numbers with a comma.
+    ///
+    /// The numbers are parsed the same way whatever the locale.
+    pub fn from_str_with_locale(expression: &'a str, locale: NumberLocale) -> Result<Option<Self>> {
+        let filter = Self::from_str(expression).map_err(|error| locale.localize_error(error))?;
+        Ok(filter.map(|filter| Filter { number_locale: locale, ..filter }))
+    }
+
+    /// Parses the filter read from `reader`, e.g. a large generated filter stored in a file.
+    ///
+    /// The parser needs the whole expression, so it is first read into `buffer`, which the
+    /// filter borrows its values from. Invalid UTF-8 is reported as an IO error.
+    pub fn from_reader(mut reader: impl Read, buffer: &'a mut String) -> Result<Option<Self>> {
+        buffer.clear();
+        reader.read_to_string(buffer)?;
+        Self::from_str(buffer)
+    }
+
+    /// Parses the filter, also accepting the legacy `field:value` syntax when `legacy_syntax` is set.
+    pub fn from_str_with_legacy_syntax(
+        expression: &'a str,
+        legacy_syntax: bool,
+    ) -> Result<Option<Self>> {
+        let condition = match FilterCondition::parse_with_legacy_syntax(expression, legacy_syntax) {
             Ok(Some(fc)) => Ok(fc),
             Ok(None) => return Ok(None),
             Err(e) => Err(Error::UserError(UserError::InvalidFilter(e.to_string()))),
@@ -216,916 +453,7569 @@ impl<'a> Filter<'a> {
             return Err(token.as_external_error(FilterError::TooDeep).into());
         }
 
-        Ok(Some(Self { condition }))
+        Ok(Some(Self::from(condition)))
+    }
+
+    /// Builds the filter matching the numbers of the field between the two bounds, like the
+    /// interval `field [left, right)` for an `Included` left and an `Excluded` right bound, for
+    /// the callers building their filters in code.
+    ///
+    /// The infinite bounds leave the interval unbounded and a NaN bound matches no document.
+    /// Fails if the field is unknown or only contains strings.
+    pub fn numeric_bounds(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        left: Bound<f64>,
+        right: Bound<f64>,
+    ) -> Result<Filter<'static>> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let attribute = fields_ids_map.name(field_id).ok_or(FieldIdMapMissingEntry::FieldId {
+            field_id,
+            process: "Filter::numeric_bounds",
+        })?;
+        let mut fid = Token::from("");
+        fid.rename(attribute.to_string());
+        if indexed_facet_type(rtxn, index, field_id)? == Some(FacetType::String) {
+            let attribute = attribute.to_string();
+            return Err(fid.as_external_error(FilterError::NumericOperatorOnString {
+                attribute,
+                operator: "interval",
+            }))?;
+        }
+
+        let is_nan = |bound: &Bound<f64>| matches!(bound, Included(n) | Excluded(n) if n.is_nan());
+        if is_nan(&left) || is_nan(&right) {
+            return Ok(Filter::from(FilterCondition::Const(false)));
+        }
+        let value = |number: f64| Token::new(fid.original_span(), Some(number.to_string()));
+        let bound = |bound: Bound<f64>| match bound {
+            Included(number) if number.is_finite() => Included(value(number)),
+            Excluded(number) if number.is_finite() => Excluded(value(number)),
+            _ => Bound::Unbounded,
+        };
+        let op = Condition::Interval { from: bound(left), to: bound(right) };
+        Ok(Filter::from(FilterCondition::Condition { fid, op }))
     }
 }
 
 impl<'a> Filter<'a> {
+    /// When enabled, `evaluate` first makes sure that every attribute the filter refers to
+    /// is still faceted and known by the index, instead of silently matching no document.
+    pub fn check_fields(&mut self, check: bool) -> &mut Self {
+        self.check_fields = check;
+        self
+    }
+
+    /// When enabled, `evaluate` first makes sure that every value compared with `=`, `!=`, `IN`
+    /// or `HAS_ALL` is contained by its attribute, e.g. to report a typo in a value selected
+    /// from a UI, instead of the condition silently matching no document.
+    ///
+    /// It is disabled by default. The values of the attributes that are not indexed are not
+    /// checked, see [`Filter::check_fields`].
+    pub fn check_values(&mut self, check: bool) -> &mut Self {
+        self.check_values = check;
+        self
+    }
+
+    /// When enabled, `evaluate` first makes sure that the numbers of the filter are written like
+    /// the values of their attribute: `price = 10` is rejected when `price` contains floats and
+    /// must be written `price = 10.0`, while `year = 2020.0` is rejected when `year` only
+    /// contains integers. The quoted and annotated values are not checked.
+    ///
+    /// It is disabled by default, the numbers being compared regardless of how they are written.
+    pub fn strict_types(&mut self, strict: bool) -> &mut Self {
+        self.strict_types = strict;
+        self
+    }
+
+    /// When enabled, the documents of a string value compared with `=`, `!=`, `IN` or `HAS_ALL`
+    /// are looked up once per evaluation and reused by the other conditions on the same value,
+    /// e.g. in `genre = horror OR (genre = horror AND year > 2000)`.
+    ///
+    /// The looked up values are only kept during an evaluation, including each of the indexes
+    /// of [`Filter::evaluate_many`], and never between two of them.
+    pub fn cache_string_lookups(&mut self, cache: bool) -> &mut Self {
+        self.cache_string_lookups = cache;
+        self
+    }
+
+    /// When enabled, `!=` only matches the documents that contain the attribute: the documents
+    /// with a value of the attribute minus the ones equal to the value, ignoring the case like
+    /// `=`. `brand != nike` then matches neither `Nike` nor the documents without a brand, like
+    /// `- brand = nike`.
+    ///
+    /// It is disabled by default, `!=` matching every document that isn't equal to the value.
+    pub fn not_equal_present_only(&mut self, present_only: bool) -> &mut Self {
+        self.not_equal_present_only = present_only;
+        self
+    }
+
+    /// Makes the `name` pseudo-field refer to the internal document ids, e.g. `_docid IN [1, 5, 9]`.
+    /// Conditions on this pseudo-field are evaluated without the field having to be filterable.
+    pub fn document_id_field(&mut self, name: impl Into<String>) -> &mut Self {
+        self.document_id_field = Some(name.into());
+        self
+    }
+
+    /// Sets the order in which the elements of an `IN` list are evaluated and reported to
+    /// [`FacetMetrics::in_value_matched`], e.g. for a caller streaming the documents of each
+    /// element as soon as they are read.
+    ///
+    /// The documents returned by the evaluation are the same in every order. With the default
+    /// [`InListOrder::Any`], the runs of contiguous integers are read as a single range and
+    /// their elements are not reported.
+    pub fn in_list_order(&mut self, order: InListOrder) -> &mut Self {
+        self.in_list_order = order;
+        self
+    }
+
+    /// Sets how the values listed more than once in a `HAS_ALL` list are handled, e.g.
+    /// `tags HAS_ALL [rust, Rust]`.
+    ///
+    /// The facet databases only know which documents contain a value, not how many times, so a
+    /// duplicate can't require a document to contain the value twice: with the default
+    /// [`HasAllDuplicates::Ignore`], the documents of each distinct value are intersected once.
+    pub fn has_all_duplicates(&mut self, duplicates: HasAllDuplicates) -> &mut Self {
+        self.has_all_duplicates = duplicates;
+        self
+    }
+
+    /// Interprets the `YYYY-MM-DD` dates compared with `field`, storing UTC timestamps in
+    /// seconds, as the days of the timezone at `offset`, e.g. with an offset of `+02:00`,
+    /// `created = "2021-03-01"` matches the timestamps from `2021-02-28T22:00:00Z` included
+    /// to `2021-03-01T22:00:00Z` excluded.
+    ///
+    /// The `=`, `!=`, `>`, `>=`, `<`, `<=` and `TO` conditions on dates, and the `IN` lists made
+    /// only of dates, are replaced by the corresponding ranges of timestamps. The other values
+    /// are compared as they are written.
+    pub fn date_field(&mut self, field: &str, offset: UtcOffset) -> &mut Self {
+        let condition = mem::replace(&mut self.condition, FilterCondition::Const(true));
+        self.condition = expand_dates(condition, field, offset);
+        self
+    }
+
+    /// Makes the evaluation fail with [`UserError::FilterBudgetExceeded`] once more than `max`
+    /// facet values were read from the database, e.g. to bound the cost of a filter scanning a
+    /// wide range of values on a huge facet, or `None` to read as many values as needed.
+    ///
+    /// The reads are counted like by [`FacetMetrics::database_read`], the `_geoRadius` filters
+    /// and the conditions on the document id pseudo-field are not bounded.
+    pub fn max_database_reads(&mut self, max: Option<u64>) -> &mut Self {
+        self.max_database_reads = max;
+        self
+    }
+
+    /// Makes the `true` and `false` compared with the `fields` match their numbers `1` and `0`,
+    /// e.g. `active = true` becomes `active = 1`, for the fields storing booleans as integers.
+    ///
+    /// Once boolean-like fields are set, comparing `true` or `false` with another field
+    /// containing only numbers fails instead of matching no document. The quoted values, like
+    /// `"true"`, are compared as they are written.
+    pub fn boolean_fields(
+        &mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.boolean_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Converts the numbers written with one of the `units` and compared with `field` to its
+    /// base unit, e.g. with `kb` worth `1024`, `size > 5kb` becomes `size > 5120`, for the
+    /// fields storing sizes or durations as integers.
+    ///
+    /// The units are the letters following a number, like `kb` in `5kb`, and are matched
+    /// exactly. The `=`, `!=`, `>`, `>=`, `<`, `<=` and `TO` conditions with another unit
+    /// on the field fail to evaluate.
+    pub fn units(
+        &mut self,
+        field: impl Into<String>,
+        units: impl IntoIterator<Item = (impl Into<String>, u64)>,
+    ) -> &mut Self {
+        let units = units.into_iter().map(|(unit, worth)| (unit.into(), worth)).collect();
+        self.units.insert(field.into(), units);
+        self
+    }
+
+    /// Sets the type of the numbers of `field`, used instead of the one of its values in the
+    /// index by [`Filter::strict_types`], e.g. to evaluate the same filter on an index storing
+    /// the prices as integers and another one storing them as floats.
+    ///
+    /// The numbers compared with the field are read as the given type: with
+    /// [`NumberType::Float`], `price = 10` is read as `price = 10.0`, and with
+    /// [`NumberType::Integer`], `price = 10.0` is read as `price = 10`, while a number with a
+    /// fractional part like `10.5` fails to evaluate.
+    pub fn number_type(&mut self, field: impl Into<String>, number_type: NumberType) -> &mut Self {
+        self.number_types.insert(field.into(), number_type);
+        self
+    }
+
+    /// Returns the documents matching the filter. Like any [`RoaringBitmap`], they are iterated
+    /// in ascending order of their internal ids, whatever the order of the conditions.
     pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
-        // to avoid doing this for each recursive call we're going to do it ONCE ahead of time
-        let filterable_fields = index.filterable_fields(rtxn)?;
+        self.evaluate_with_metrics(rtxn, index, &mut NoopFacetMetrics)
+    }
 
-        self.inner_evaluate(rtxn, index, &filterable_fields)
+    /// Evaluates the filter like [`Filter::evaluate`] and returns the internal ids of the
+    /// matching documents, sorted in ascending order without duplicates, see
+    /// [`Filter::evaluate_sorted_vec`]. The order only depends on the matching documents, e.g.
+    /// for the APIs returning lists or the tests comparing them.
+    pub fn evaluate_ordered(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<Vec<DocumentId>> {
+        self.evaluate_sorted_vec(rtxn, index)
     }
 
-    fn evaluate_operator(
-        rtxn: &heed::RoTxn,
+    /// Evaluates the filter like [`Filter::evaluate`] with a read transaction taken from `rtxns`,
+    /// e.g. a [`ReadTxnPool`] shared by the requests of a server, and given back to it once the
+    /// evaluation is done, even when it fails.
+    pub fn evaluate_pooled<'e>(
+        &self,
         index: &Index,
-        field_id: FieldId,
-        operator: &Condition<'a>,
+        rtxns: &impl ReadTxnSource<'e>,
     ) -> Result<RoaringBitmap> {
-        let numbers_db = index.facet_id_f64_docids;
-        let strings_db = index.facet_id_string_docids;
+        let rtxn = rtxns.take()?;
+        let result = self.evaluate(&rtxn, index);
+        rtxns.give_back(rtxn);
+        result
+    }
 
-        // Make sure we always bound the ranges with the field id and the level,
-        // as the facets values are all in the same database and prefixed by the
-        // field id and the level.
+    /// Returns the comparisons of the filter, in the order they are written, without the `AND`,
+    /// `OR` and `NOT` combining them, e.g. `a = 1 AND NOT (b > 2 OR c IN [3])` has three leaves.
+    ///
+    /// The constants `TRUE` and `FALSE` are not comparisons and are skipped.
+    pub fn leaf_operators<'f>(
+        &'f self,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> impl Iterator<Item = LeafOperator<'f, 'a>> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            let (field, kind) = match condition {
+                FilterCondition::Not(condition) => {
+                    stack.push(condition);
+                    continue;
+                }
+                // the stack is LIFO, we push the conditions in reverse to keep them in order
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions.iter().rev());
+                    continue;
+                }
+                FilterCondition::Condition { fid, op } => {
+                    let kind = match op {
+                        Condition::GreaterThan(_) => LeafKind::GreaterThan,
+                        Condition::GreaterThanOrEqual(_) => LeafKind::GreaterThanOrEqual,
+                        Condition::Equal(_) => LeafKind::Equal,
+                        Condition::NotEqual(_) => LeafKind::NotEqual,
+                        Condition::Null => LeafKind::Null,
+                        Condition::Empty => LeafKind::Empty,
+                        Condition::Exists => LeafKind::Exists,
+                        Condition::LowerThan(_) => LeafKind::LowerThan,
+                        Condition::LowerThanOrEqual(_) => LeafKind::LowerThanOrEqual,
+                        Condition::Between { .. }
+                        | Condition::BetweenStep { .. }
+                        | Condition::Interval { .. }
+                        | Condition::Window { .. } => LeafKind::Range,
+                        Condition::Like(_) => LeafKind::Like,
+                        Condition::Integer => LeafKind::Integer,
+                        Condition::Fractional => LeafKind::Fractional,
+                    };
+                    (fid.value(), kind)
+                }
+                FilterCondition::In { fid, .. } => (fid.value(), LeafKind::In),
+                FilterCondition::HasAll { fid, .. } => (fid.value(), LeafKind::HasAll),
+                FilterCondition::GeoLowerThan { .. } => ("_geo", LeafKind::GeoRadius),
+                FilterCondition::GeoBoundingBox { .. } => ("_geo", LeafKind::GeoBoundingBox),
+                FilterCondition::Const(_) => continue,
+            };
+            leaves.push(LeafOperator {
+                field_id: fields_ids_map.id(field),
+                field,
+                kind,
+                condition,
+            });
+        }
+        leaves.into_iter()
+    }
 
-        let (left, right) = match operator {
-            Condition::GreaterThan(val) => {
-                (Excluded(val.parse_finite_float()?), Included(f64::MAX))
-            }
-            Condition::GreaterThanOrEqual(val) => {
-                (Included(val.parse_finite_float()?), Included(f64::MAX))
-            }
-            Condition::LowerThan(val) => (Included(f64::MIN), Excluded(val.parse_finite_float()?)),
-            Condition::LowerThanOrEqual(val) => {
-                (Included(f64::MIN), Included(val.parse_finite_float()?))
-            }
-            Condition::Between { from, to } => {
-                (Included(from.parse_finite_float()?), Included(to.parse_finite_float()?))
-            }
-            Condition::Null => {
-                let is_null = index.null_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(is_null);
-            }
-            Condition::Empty => {
-                let is_empty = index.empty_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(is_empty);
-            }
-            Condition::Exists => {
-                let exist = index.exists_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(exist);
-            }
-            Condition::Equal(val) => {
-                let string_docids = strings_db
-                    .get(
-                        rtxn,
-                        &FacetGroupKey {
-                            field_id,
-                            level: 0,
-                            left_bound: &crate::normalize_facet(val.value()),
-                        },
-                    )?
-                    .map(|v| v.bitmap)
-                    .unwrap_or_default();
-                let number = val.parse_finite_float().ok();
-                let number_docids = match number {
-                    Some(n) => numbers_db
-                        .get(rtxn, &FacetGroupKey { field_id, level: 0, left_bound: n })?
-                        .map(|v| v.bitmap)
-                        .unwrap_or_default(),
-                    None => RoaringBitmap::new(),
-                };
-                return Ok(string_docids | number_docids);
-            }
-            Condition::NotEqual(val) => {
-                let operator = Condition::Equal(val.clone());
-                let docids = Self::evaluate_operator(rtxn, index, field_id, &operator)?;
-                let all_ids = index.documents_ids(rtxn)?;
-                return Ok(all_ids - docids);
-            }
-        };
+    /// Starts a chain of conditions combined with [`LazyFilter::and`] and [`LazyFilter::or`]
+    /// that is only evaluated on [`LazyFilter::collect`].
+    pub fn lazy(self) -> LazyFilter<'a> {
+        LazyFilter { filter: self }
+    }
 
-        let mut output = RoaringBitmap::new();
-        Self::explore_facet_number_levels(rtxn, numbers_db, field_id, left, right, &mut output)?;
-        Ok(output)
+    /// Evaluates the filter like [`Filter::evaluate`] while recording, for each field,
+    /// the database reads, visited levels and matched documents into `metrics`.
+    pub fn evaluate_with_metrics(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        // to avoid doing this for each recursive call we're going to do it ONCE ahead of time
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_fields = index.filterable_fields(rtxn)?;
+
+        self.evaluate_with_fields_and_metrics(
+            rtxn,
+            index,
+            &fields_ids_map,
+            &filterable_fields,
+            metrics,
+        )
     }
 
-    /// Aggregates the documents ids that are part of the specified range automatically
-    /// going deeper through the levels.
-    fn explore_facet_number_levels(
+    /// Evaluates the filter like [`Filter::evaluate`] and also returns the number of distinct
+    /// string facet values matched by its conditions that contain at least one of the matching
+    /// documents, e.g. to show "3 brands, 412 products" for `brand IN [a, b, c] AND price < 10`.
+    ///
+    /// The values are counted per attribute, as they are indexed, lowercased: the same value of
+    /// two attributes counts twice. Only the string values are counted, and a value matched by
+    /// a condition under a `NOT` only counts if some of its documents still match.
+    pub fn evaluate_with_values_count(
+        &self,
         rtxn: &heed::RoTxn,
-        db: heed::Database<FacetGroupKeyCodec<OrderedF64Codec>, FacetGroupValueCodec>,
-        field_id: FieldId,
-        left: Bound<f64>,
-        right: Bound<f64>,
-        output: &mut RoaringBitmap,
-    ) -> Result<()> {
-        match (left, right) {
-            // lower TO upper when lower > upper must return no result
-            (Included(l), Included(r)) if l > r => return Ok(()),
-            (Included(l), Excluded(r)) if l >= r => return Ok(()),
-            (Excluded(l), Excluded(r)) if l >= r => return Ok(()),
-            (Excluded(l), Included(r)) if l >= r => return Ok(()),
-            (_, _) => (),
-        }
-        facet_range_search::find_docids_of_facet_within_bounds::<OrderedF64Codec>(
-            rtxn, db, field_id, &left, &right, output,
-        )?;
+        index: &Index,
+    ) -> Result<(RoaringBitmap, u64)> {
+        let mut matched = MatchedStringValues::default();
+        let docids = self.evaluate_with_metrics(rtxn, index, &mut matched)?;
+        let values =
+            matched.values.values().filter(|values_docids| !values_docids.is_disjoint(&docids));
+        Ok((docids, values.count() as u64))
+    }
 
-        Ok(())
+    /// Evaluates the filter like [`Filter::evaluate`] with the fields of the index given by the
+    /// caller instead of being read from the index, e.g. by a server keeping them between its
+    /// requests.
+    ///
+    /// They must be up to date with the index: the attributes missing from `fields_ids_map`
+    /// match no document and the ones missing from `filterable_fields` are rejected.
+    pub fn evaluate_with_fields(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+    ) -> Result<RoaringBitmap> {
+        self.evaluate_with_fields_and_metrics(
+            rtxn,
+            index,
+            fields_ids_map,
+            filterable_fields,
+            &mut NoopFacetMetrics,
+        )
     }
 
-    fn inner_evaluate(
+    fn evaluate_with_fields_and_metrics(
         &self,
         rtxn: &heed::RoTxn,
         index: &Index,
+        fields_ids_map: &FieldsIdsMap,
         filterable_fields: &HashSet<String>,
+        metrics: &mut dyn FacetMetrics,
     ) -> Result<RoaringBitmap> {
-        match &self.condition {
-            FilterCondition::Not(f) => {
-                let all_ids = index.documents_ids(rtxn)?;
-                let selected = Self::inner_evaluate(
-                    &(f.as_ref().clone()).into(),
-                    rtxn,
-                    index,
-                    filterable_fields,
-                )?;
-                Ok(all_ids - selected)
+        let filter = self.prepared(rtxn, index, fields_ids_map)?;
+
+        let reads = AtomicU64::new(0);
+        let mut budget;
+        let metrics: &mut dyn FacetMetrics = match filter.max_database_reads {
+            Some(max_reads) => {
+                budget = ReadBudget::new(metrics, max_reads, &reads);
+                &mut budget
             }
-            FilterCondition::In { fid, els } => {
-                if crate::is_faceted(fid.value(), filterable_fields) {
-                    let field_ids_map = index.fields_ids_map(rtxn)?;
+            None => metrics,
+        };
+        let mut string_lookups = StringLookups::new(filter.cache_string_lookups);
+        filter
+            .inner_evaluate(
+                rtxn,
+                index,
+                fields_ids_map,
+                filterable_fields,
+                metrics,
+                &mut string_lookups,
+                filter.document_id_field.as_deref(),
+                filter.in_list_order,
+            )
+            .map_err(|error| filter.number_locale.localize_error(error))
+    }
 
-                    if let Some(fid) = field_ids_map.id(fid.value()) {
-                        let mut bitmap = RoaringBitmap::new();
+    /// Returns the filter as it is evaluated on the index, once the checks enabled on it passed:
+    /// with the attributes written like they are stored, the `attr.* EXISTS` wildcards expanded,
+    /// the units converted and the booleans replaced by numbers.
+    ///
+    /// Every evaluation goes through it before splitting the condition into leaves, so that they
+    /// all match the same documents.
+    fn prepared(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> Result<Cow<'_, Filter<'a>>> {
+        let mut filter = Cow::Borrowed(self);
+        if let Some(condition) = with_stored_attribute_names(&filter.condition, fields_ids_map) {
+            filter = Cow::Owned(Filter { condition, ..filter.into_owned() });
+        }
+        if has_exists_wildcard(&filter.condition) {
+            let condition = expand_exists_wildcards(filter.condition.clone(), fields_ids_map)?;
+            filter = Cow::Owned(Filter { condition, ..filter.into_owned() });
+        }
+        if !filter.units.is_empty() {
+            let condition = filter.with_units()?;
+            let units = BTreeMap::new();
+            filter = Cow::Owned(Filter { condition, units, ..filter.into_owned() });
+        }
+        if !filter.boolean_fields.is_empty() {
+            let condition = filter.with_boolean_numbers(rtxn, index, fields_ids_map)?;
+            let boolean_fields = BTreeSet::new();
+            filter = Cow::Owned(Filter { condition, boolean_fields, ..filter.into_owned() });
+        }
+        if filter.not_equal_present_only {
+            let document_id_field = filter.document_id_field.as_deref();
+            let condition = with_present_not_equals(filter.condition.clone(), document_id_field);
+            let not_equal_present_only = false;
+            filter =
+                Cow::Owned(Filter { condition, not_equal_present_only, ..filter.into_owned() });
+        }
+        filter.check_before_evaluation(rtxn, index, fields_ids_map)?;
+        Ok(filter)
+    }
 
-                        for el in els {
-                            let op = Condition::Equal(el.clone());
-                            let el_bitmap = Self::evaluate_operator(rtxn, index, fid, &op)?;
-                            bitmap |= el_bitmap;
-                        }
-                        Ok(bitmap)
-                    } else {
-                        Ok(RoaringBitmap::new())
-                    }
-                } else {
-                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
+    /// Returns the condition where the numbers written with a unit are converted to the base unit
+    /// of the field they are compared with, see [`Filter::units`].
+    fn with_units(&self) -> Result<FilterCondition<'a>> {
+        let mut condition = self.condition.clone();
+        for (fid, value) in condition.unit_values_mut() {
+            let units = match self.units.get(fid.value()) {
+                Some(units) => units,
+                None => continue,
+            };
+            // the values are only returned when they have a unit
+            let (number, unit) = value.number_with_unit().unwrap();
+            match units.get(unit) {
+                Some(&worth) => value.bind((number * worth as f64).to_string()),
+                None => {
+                    return Err(value.as_external_error(FilterError::UnknownUnit {
                         attribute: fid.value(),
-                        filterable_fields: filterable_fields.clone(),
+                        unit,
+                        units: units.keys().cloned().collect(),
                     }))?
                 }
             }
-            FilterCondition::Condition { fid, op } => {
-                if crate::is_faceted(fid.value(), filterable_fields) {
-                    let field_ids_map = index.fields_ids_map(rtxn)?;
-                    if let Some(fid) = field_ids_map.id(fid.value()) {
-                        Self::evaluate_operator(rtxn, index, fid, op)
-                    } else {
-                        Ok(RoaringBitmap::new())
+        }
+        Ok(condition)
+    }
+
+    /// Returns the condition where the `true` and `false` compared with the boolean-like fields
+    /// are replaced by `1` and `0`, see [`Filter::boolean_fields`].
+    fn with_boolean_numbers(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> Result<FilterCondition<'a>> {
+        let mut condition = self.condition.clone();
+        let mut stack = vec![&mut condition];
+        while let Some(condition) = stack.pop() {
+            let (fid, values) = match condition {
+                FilterCondition::Not(condition) => {
+                    stack.push(condition);
+                    continue;
+                }
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions.iter_mut());
+                    continue;
+                }
+                FilterCondition::Condition {
+                    fid,
+                    op: Condition::Equal(value) | Condition::NotEqual(value),
+                } => (&*fid, vec![value]),
+                FilterCondition::In { fid, els } => (&*fid, els.iter_mut().collect()),
+                _ => continue,
+            };
+
+            for value in values {
+                if value.is_quoted() || value.annotation().is_some() {
+                    continue;
+                }
+                let number = match value.value() {
+                    "true" => "1",
+                    "false" => "0",
+                    _ => continue,
+                };
+                if self.boolean_fields.contains(fid.value()) {
+                    value.bind(number.to_string());
+                } else if let Some(field_id) = fields_ids_map.id(fid.value()) {
+                    if indexed_facet_type(rtxn, index, field_id)? == Some(FacetType::Number) {
+                        return Err(value.as_external_error(FilterError::BooleanOnNumericField {
+                            attribute: fid.value(),
+                            value: value.value(),
+                        }))?;
                     }
-                } else {
-                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: fid.value(),
-                        filterable_fields: filterable_fields.clone(),
-                    }))?
                 }
             }
-            FilterCondition::Or(subfilters) => {
-                let mut bitmap = RoaringBitmap::new();
-                for f in subfilters {
-                    bitmap |=
-                        Self::inner_evaluate(&(f.clone()).into(), rtxn, index, filterable_fields)?;
+        }
+        Ok(condition)
+    }
+
+    /// Runs the checks enabled on this filter before evaluating it.
+    fn check_before_evaluation(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> Result<()> {
+        if self.check_fields {
+            self.check_referenced_fields(rtxn, index)?;
+        }
+        if self.strict_types {
+            self.check_strict_types(rtxn, index)?;
+        }
+        if self.check_values {
+            self.check_known_values(rtxn, index)?;
+        }
+        if self.has_all_duplicates == HasAllDuplicates::Reject {
+            self.check_has_all_duplicates()?;
+        }
+        if !self.number_types.is_empty() {
+            self.check_number_types()?;
+        }
+        self.check_aliases(fields_ids_map)
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] but reads the documents ids of all its
+    /// leaves ahead of time, in parallel, before combining them.
+    ///
+    /// Each thread reads its leaves with its own read transaction: if the index is written
+    /// concurrently, they can see a more recent version of the index than `rtxn`. The leaves
+    /// of an `AND` are all read, even when the first ones already match no document, which
+    /// makes this slower than [`Filter::evaluate`] on selective filters. The string lookups
+    /// are not cached.
+    #[cfg(feature = "parallel")]
+    pub fn evaluate_prefetched(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        self.evaluate_prefetched_pooled(rtxn, index, &|| -> Result<_> { Ok(index.read_txn()?) })
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate_prefetched`], each thread borrowing its read
+    /// transaction from `rtxns` instead of opening a new one.
+    #[cfg(feature = "parallel")]
+    pub fn evaluate_prefetched_pooled<'e>(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        rtxns: &impl ReadTxnSource<'e>,
+    ) -> Result<RoaringBitmap> {
+        use rayon::prelude::*;
+
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_fields = index.filterable_fields(rtxn)?;
+        let filter = self.prepared(rtxn, index, &fields_ids_map)?;
+
+        let mut leaves = Vec::new();
+        collect_leaves(&filter.condition, &mut leaves);
+        if leaves.is_empty() {
+            return self.evaluate_with_fields(rtxn, index, &fields_ids_map, &filterable_fields);
+        }
+
+        let reads = AtomicU64::new(0);
+        let chunk_size = leaves.len().div_ceil(rayon::current_num_threads());
+        let chunks: Vec<Vec<RoaringBitmap>> = leaves
+            .par_chunks(chunk_size)
+            .map(|leaves| -> Result<Vec<RoaringBitmap>> {
+                let rtxn = rtxns.take()?;
+                let docids = leaves
+                    .iter()
+                    .map(|leaf| {
+                        filter.evaluate_leaf(
+                            leaf,
+                            &rtxn,
+                            index,
+                            &fields_ids_map,
+                            &filterable_fields,
+                            &reads,
+                        )
+                    })
+                    .collect();
+                rtxns.give_back(rtxn);
+                docids
+            })
+            .collect::<Result<_>>()?;
+
+        let all_ids = index.documents_ids(rtxn)?;
+        let mut prefetched = chunks.into_iter().flatten();
+        Ok(combine_prefetched(&filter.condition, &mut prefetched, &all_ids))
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] on both the `index` and an `overlay` of
+    /// staged updates not merged into it yet, so that the staged documents match too.
+    ///
+    /// Each condition matches the documents it matches in either of the indexes, and a `NOT`
+    /// matches the documents of both indexes that its condition doesn't match. The overlay must
+    /// be an index with the same filterable attributes, where the documents have the internal
+    /// ids they have or will have in the main index, e.g. a copy of it receiving the updates
+    /// first. Being an [`Index`] too, its facet databases have the same codecs, and they are read
+    /// with its own fields ids map. The string lookups are not cached.
+    pub fn evaluate_with_overlay(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        overlay_rtxn: &heed::RoTxn,
+        overlay: &Index,
+    ) -> Result<RoaringBitmap> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_fields = index.filterable_fields(rtxn)?;
+        let filter = self.prepared(rtxn, index, &fields_ids_map)?;
+        let overlay_fields_ids_map = overlay.fields_ids_map(overlay_rtxn)?;
+        let overlay_filterable_fields = overlay.filterable_fields(overlay_rtxn)?;
+
+        let mut leaves = Vec::new();
+        collect_leaves(&filter.condition, &mut leaves);
+        let reads = AtomicU64::new(0);
+        let mut leaves_docids = Vec::with_capacity(leaves.len());
+        for leaf in leaves {
+            let docids = filter.evaluate_leaf(
+                leaf,
+                rtxn,
+                index,
+                &fields_ids_map,
+                &filterable_fields,
+                &reads,
+            )?;
+            let staged_docids = filter.evaluate_leaf(
+                leaf,
+                overlay_rtxn,
+                overlay,
+                &overlay_fields_ids_map,
+                &overlay_filterable_fields,
+                &reads,
+            )?;
+            leaves_docids.push(docids | staged_docids);
+        }
+
+        let all_ids = index.documents_ids(rtxn)? | overlay.documents_ids(overlay_rtxn)?;
+        let mut leaves_docids = leaves_docids.into_iter();
+        Ok(combine_prefetched(&filter.condition, &mut leaves_docids, &all_ids))
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`], but the numeric ranges on the fields
+    /// loaded in the `columns` are evaluated in memory, see [`FacetColumns`].
+    ///
+    /// The other conditions are read from the index like by [`Filter::evaluate`], the columns
+    /// only avoid reading the facet levels of the hot fields. A range over a column costs two
+    /// binary searches and the union of the bitmaps of the values within it, where a range read
+    /// from the index decodes a few groups of each level it traverses, which makes the columns
+    /// faster when the same fields are queried with many ranges. The string lookups are not
+    /// cached.
+    pub fn evaluate_with_columns(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        columns: &FacetColumns,
+    ) -> Result<RoaringBitmap> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_fields = index.filterable_fields(rtxn)?;
+        let filter = self.prepared(rtxn, index, &fields_ids_map)?;
+
+        let mut leaves = Vec::new();
+        collect_leaves(&filter.condition, &mut leaves);
+        let document_id_field = filter.document_id_field.as_deref();
+        let reads = AtomicU64::new(0);
+        let mut leaves_docids = Vec::with_capacity(leaves.len());
+        for leaf in leaves {
+            let column_docids = Self::evaluate_column_range(
+                rtxn,
+                index,
+                &fields_ids_map,
+                &filterable_fields,
+                columns,
+                document_id_field,
+                leaf,
+            )
+            .map_err(|error| filter.number_locale.localize_error(error))?;
+            let docids = match column_docids {
+                Some(docids) => docids,
+                None => filter.evaluate_leaf(
+                    leaf,
+                    rtxn,
+                    index,
+                    &fields_ids_map,
+                    &filterable_fields,
+                    &reads,
+                )?,
+            };
+            leaves_docids.push(docids);
+        }
+
+        let all_ids = index.documents_ids(rtxn)?;
+        let mut leaves_docids = leaves_docids.into_iter();
+        Ok(combine_prefetched(&filter.condition, &mut leaves_docids, &all_ids))
+    }
+
+    /// Evaluates one of the leaves of this filter, already prepared, with the configuration of
+    /// the filter. The database reads of all the leaves are counted in `reads`, against the
+    /// budget of the filter.
+    fn evaluate_leaf(
+        &self,
+        leaf: &FilterCondition<'a>,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        reads: &AtomicU64,
+    ) -> Result<RoaringBitmap> {
+        let leaf = Filter { condition: leaf.clone(), ..self.clone() };
+        let mut noop = NoopFacetMetrics;
+        let mut budget;
+        let metrics: &mut dyn FacetMetrics = match leaf.max_database_reads {
+            Some(max_reads) => {
+                budget = ReadBudget::new(&mut noop, max_reads, reads);
+                &mut budget
+            }
+            None => &mut noop,
+        };
+        leaf.inner_evaluate(
+            rtxn,
+            index,
+            fields_ids_map,
+            filterable_fields,
+            metrics,
+            &mut StringLookups::default(),
+            leaf.document_id_field.as_deref(),
+            leaf.in_list_order,
+        )
+        .map_err(|error| leaf.number_locale.localize_error(error))
+    }
+
+    /// Returns the documents of the leaf from the `columns` if it is a numeric range on one of
+    /// their fields, or `None` if it must be read from the index.
+    fn evaluate_column_range(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        columns: &FacetColumns,
+        document_id_field: Option<&str>,
+        leaf: &FilterCondition<'a>,
+    ) -> Result<Option<RoaringBitmap>> {
+        let (fid, op) = match leaf {
+            FilterCondition::Condition { fid, op }
+                if fid.function().is_none() && Some(fid.value()) != document_id_field =>
+            {
+                (fid, op)
+            }
+            _ => return Ok(None),
+        };
+        let field_id = match fields_ids_map.id(fid.value()) {
+            Some(field_id)
+                if columns.contains(field_id)
+                    && crate::is_faceted(fid.value(), filterable_fields) =>
+            {
+                field_id
+            }
+            _ => return Ok(None),
+        };
+
+        Self::check_placeholder(op)?;
+        Self::check_numeric_operator(rtxn, index, field_id, op)?;
+        Self::check_type_annotation(rtxn, index, field_id, op)?;
+
+        let (left, right) = match op {
+            Condition::GreaterThan(val) => (Excluded(val.parse_finite_float()?), Bound::Unbounded),
+            Condition::GreaterThanOrEqual(val) => {
+                (Included(val.parse_finite_float()?), Bound::Unbounded)
+            }
+            Condition::LowerThan(val) => (Bound::Unbounded, Excluded(val.parse_finite_float()?)),
+            Condition::LowerThanOrEqual(val) => {
+                (Bound::Unbounded, Included(val.parse_finite_float()?))
+            }
+            Condition::Between { from, to }
+                if !Self::is_string_range(rtxn, index, field_id, from, to)? =>
+            {
+                (Included(from.parse_finite_float()?), Included(to.parse_finite_float()?))
+            }
+            Condition::Interval { from, to } => (
+                map_bound(from, |from| Ok(from.parse_finite_float()?))?,
+                map_bound(to, |to| Ok(to.parse_finite_float()?))?,
+            ),
+            _ => return Ok(None),
+        };
+        Ok(columns.range_docids(field_id, left, right))
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] but returns the matching documents ids
+    /// as a vector sorted in ascending order.
+    ///
+    /// The facet databases are ordered by value and not by document id, so the ids are sorted
+    /// by iterating over the resulting bitmap, which already yields them in ascending order.
+    pub fn evaluate_sorted_vec(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<Vec<DocumentId>> {
+        let docids = self.evaluate(rtxn, index)?;
+        let mut output = Vec::with_capacity(docids.len() as usize);
+        output.extend(&docids);
+        Ok(output)
+    }
+
+    /// Returns the `k` smallest matching documents ids in ascending order, e.g. to prefetch the
+    /// first page of the results.
+    ///
+    /// The whole filter is still evaluated before keeping the first ids: the facet values are
+    /// stored by value and not by document id, stopping the traversal of a range after `k`
+    /// documents would return the documents of the smallest values, not the smallest ids.
+    pub fn evaluate_first_k(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        k: usize,
+    ) -> Result<Vec<DocumentId>> {
+        let docids = self.evaluate(rtxn, index)?;
+        Ok(docids.into_iter().take(k).collect())
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] and returns the matching documents ids in
+    /// the portable serialization format of the roaring bitmaps, e.g. to share the results
+    /// between processes through a cache. They are read back with
+    /// [`Filter::deserialize_evaluated`].
+    pub fn evaluate_serialized(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<Vec<u8>> {
+        let docids = self.evaluate(rtxn, index)?;
+        let mut bytes = Vec::with_capacity(docids.serialized_size());
+        docids.serialize_into(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads the documents ids serialized by [`Filter::evaluate_serialized`].
+    pub fn deserialize_evaluated(bytes: &[u8]) -> Result<RoaringBitmap> {
+        Ok(RoaringBitmap::deserialize_from(bytes)?)
+    }
+
+    /// Evaluates the filter on several indexes, e.g. for a federated search, and returns the
+    /// documents matching in each index, in the same order as `indexes`.
+    ///
+    /// The fields are resolved in each index independently. A condition on a field that is not
+    /// filterable in one of the indexes matches no document of this index instead of failing,
+    /// so `NOT` of such a condition matches all of them.
+    pub fn evaluate_many(&self, indexes: &[(&heed::RoTxn, &Index)]) -> Result<Vec<RoaringBitmap>> {
+        indexes
+            .iter()
+            .map(|(rtxn, index)| {
+                let filterable_fields = index.filterable_fields(rtxn)?;
+                let condition = without_unfilterable_fields(
+                    &self.condition,
+                    &filterable_fields,
+                    self.document_id_field.as_deref(),
+                );
+                Filter { condition, ..self.clone() }.evaluate(rtxn, index)
+            })
+            .collect()
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] and, when no document matches, returns
+    /// the condition that made the result empty, to tell a mistake in a filter from a result
+    /// that is legitimately empty.
+    ///
+    /// The conditions are evaluated again one by one to find the reason, which only happens when
+    /// the result is empty. As every condition of an `OR` is empty, the reason of the first one
+    /// is returned.
+    pub fn evaluate_explained(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, Option<EmptyReason<'_, 'a>>)> {
+        let docids = self.evaluate(rtxn, index)?;
+        if !docids.is_empty() {
+            return Ok((docids, None));
+        }
+        if index.number_of_documents(rtxn)? == 0 {
+            return Ok((docids, Some(EmptyReason::NoDocuments)));
+        }
+        let reason = self.empty_reason(rtxn, index, &self.condition)?;
+        Ok((docids, Some(reason)))
+    }
+
+    /// Returns why the `condition`, that matches no document, is empty.
+    fn empty_reason<'f>(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        condition: &'f FilterCondition<'a>,
+    ) -> Result<EmptyReason<'f, 'a>> {
+        match condition {
+            FilterCondition::Or(conditions) if !conditions.is_empty() => {
+                self.empty_reason(rtxn, index, &conditions[0])
+            }
+            FilterCondition::And(conditions) => {
+                for condition in conditions {
+                    let filter = Filter { condition: condition.clone(), ..self.clone() };
+                    if filter.evaluate(rtxn, index)?.is_empty() {
+                        return self.empty_reason(rtxn, index, condition);
+                    }
                 }
-                Ok(bitmap)
+                Ok(EmptyReason::EmptyIntersection(condition))
             }
-            FilterCondition::And(subfilters) => {
-                let mut subfilters_iter = subfilters.iter();
-                if let Some(first_subfilter) = subfilters_iter.next() {
-                    let mut bitmap = Self::inner_evaluate(
-                        &(first_subfilter.clone()).into(),
-                        rtxn,
-                        index,
-                        filterable_fields,
-                    )?;
-                    for f in subfilters_iter {
-                        if bitmap.is_empty() {
-                            return Ok(bitmap);
+            FilterCondition::Not(negated) => Ok(EmptyReason::FullNegation(negated)),
+            condition => Ok(EmptyReason::EmptyLeaf(condition)),
+        }
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] and, when it is an `AND` matching no
+    /// document, returns the position and the subtree of its first operand after which the
+    /// intersection of the operands is empty, e.g. to point at the over-restrictive clause of
+    /// a filter whose conditions each match some documents.
+    ///
+    /// The operands are evaluated one by one, in their order in the filter, and the operands
+    /// after the one returned are not evaluated.
+    pub fn evaluate_emptying_operand<'f>(
+        &'f self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, Option<(usize, &'f FilterCondition<'a>)>)> {
+        let conditions = match &self.condition {
+            FilterCondition::And(conditions) if !conditions.is_empty() => conditions,
+            _ => return Ok((self.evaluate(rtxn, index)?, None)),
+        };
+
+        let mut docids: Option<RoaringBitmap> = None;
+        for (position, condition) in conditions.iter().enumerate() {
+            let filter = Filter { condition: condition.clone(), ..self.clone() };
+            let condition_docids = filter.evaluate(rtxn, index)?;
+            let intersection = match docids {
+                Some(docids) => docids & condition_docids,
+                None => condition_docids,
+            };
+            if intersection.is_empty() {
+                return Ok((intersection, Some((position, condition))));
+            }
+            docids = Some(intersection);
+        }
+        Ok((docids.unwrap_or_default(), None))
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`] and also returns the documents matched by
+    /// each of its leaves on their own, in the order of [`Filter::leaf_operators`], e.g. to find
+    /// which comparison let a document through.
+    ///
+    /// Only the leaves are traced, the `AND`, `OR` and `NOT` combining them are not, and each
+    /// leaf is evaluated a second time, on the whole index.
+    pub fn evaluate_traced<'f>(
+        &'f self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, Vec<(LeafOperator<'f, 'a>, RoaringBitmap)>)> {
+        let docids = self.evaluate(rtxn, index)?;
+
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let mut leaf_filter = Filter { condition: FilterCondition::Const(true), ..self.clone() };
+        let mut leaves = Vec::new();
+        for leaf in self.leaf_operators(&fields_ids_map) {
+            leaf_filter.condition = leaf.condition().clone();
+            let leaf_docids = leaf_filter.evaluate(rtxn, index)?;
+            leaves.push((leaf, leaf_docids));
+        }
+
+        Ok((docids, leaves))
+    }
+
+    /// Evaluates the filter like [`Filter::evaluate`], comparing the numbers of the fields of
+    /// `tolerances` with `=`, `!=` and `IN` up to the given tolerance, e.g. `price = 10` matches
+    /// the prices between `9.995` and `10.005` with a tolerance of `0.005` on `price`.
+    ///
+    /// The equalities on the other fields, and with the quoted or `string:` values, stay exact.
+    pub fn evaluate_with_tolerances(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        tolerances: &HashMap<String, f64>,
+    ) -> Result<RoaringBitmap> {
+        let condition = with_equality_tolerances(&self.condition, tolerances);
+        Filter { condition, ..self.clone() }.evaluate(rtxn, index)
+    }
+
+    /// Evaluates the filter as if the index only contained the `allowed` documents, e.g. the
+    /// documents visible to a tenant: a `NOT` only selects allowed documents and the documents
+    /// outside of `allowed` are never returned, whatever the filter.
+    pub fn evaluate_within(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        allowed: &RoaringBitmap,
+    ) -> Result<RoaringBitmap> {
+        if allowed.is_empty() {
+            return Ok(RoaringBitmap::new());
+        }
+        // negating within the documents of the index then restricting to the allowed documents
+        // is the same as negating within the allowed documents of the index.
+        Ok(self.evaluate(rtxn, index)? & allowed)
+    }
+
+    /// Splits the `candidates` into the documents matching this filter and the other ones,
+    /// e.g. to display how many of the candidates match.
+    pub fn evaluate_partition(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        candidates: &RoaringBitmap,
+    ) -> Result<(RoaringBitmap, RoaringBitmap)> {
+        let matched = self.evaluate(rtxn, index)? & candidates;
+        let excluded = candidates - &matched;
+        Ok((matched, excluded))
+    }
+
+    /// Returns a copy of this filter where the `?` placeholders are replaced, in order, by `params`.
+    ///
+    /// Strings, numbers and booleans can be bound, the number of parameters must match
+    /// the number of placeholders in the filter.
+    pub fn bind(&self, params: &[Value]) -> Result<Filter<'a>> {
+        let mut filter = self.clone();
+        let mut placeholders = filter.condition.placeholders_mut();
+
+        if placeholders.len() != params.len() {
+            return Err(Error::UserError(UserError::InvalidFilter(format!(
+                "The filter expects {} parameters but {} were provided.",
+                placeholders.len(),
+                params.len(),
+            ))));
+        }
+
+        for (placeholder, param) in placeholders.iter_mut().zip(params) {
+            let value = match param {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                v => {
+                    return Err(Error::UserError(UserError::InvalidFilterExpression(
+                        &["String", "Number", "Boolean"],
+                        v.clone(),
+                    )))
+                }
+            };
+            placeholder.bind(value);
+        }
+
+        Ok(filter)
+    }
+
+    /// Returns a copy of this filter where the arithmetic expressions, like `price < base * 2`,
+    /// are computed with the named parameters in `params`.
+    ///
+    /// Expressions only made of numbers are already computed at parse time.
+    pub fn bind_named(&self, params: &HashMap<String, f64>) -> Result<Filter<'a>> {
+        let mut filter = self.clone();
+
+        for token in filter.condition.expressions_mut() {
+            let expression = token.expression().unwrap();
+            let value = expression.evaluate(&|name: &str| params.get(name).copied())?;
+            token.bind(value.to_string());
+        }
+
+        Ok(filter)
+    }
+
+    /// Estimates the number of documents matching this filter, without evaluating it.
+    ///
+    /// The estimate is an upper bound computed from a few reads of the facet databases:
+    /// it is exact for the equalities and bounded by the number of documents having
+    /// the field for the other operators.
+    pub fn estimate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<u64> {
+        SelectivityEstimator::new(rtxn, index)?.estimate(&self.condition)
+    }
+
+    /// Reorders the operands of every `AND` of the filter by increasing [`Filter::estimate`],
+    /// so that the most selective ones are evaluated first and the intersection shrinks early.
+    ///
+    /// The optimized filter matches the same documents. When the filter is invalid, the error
+    /// reported can however come from another operand than with the original order.
+    pub fn optimize(&mut self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        SelectivityEstimator::new(rtxn, index)?.optimize(&mut self.condition)?;
+        Ok(())
+    }
+
+    /// Merges the overlapping or touching numeric ranges on the same field of every `OR` into
+    /// disjoint intervals, e.g. `year > 2000 OR year > 2010` becomes `year (2000, *)`, so that
+    /// the facet levels are only scanned once for the overlapping part of the ranges.
+    ///
+    /// The merged filter matches the same documents, see [`FilterCondition::merge_ranges`].
+    pub fn merge_ranges(&mut self) {
+        let condition = mem::replace(&mut self.condition, FilterCondition::Const(true));
+        self.condition = condition.merge_ranges();
+    }
+
+    /// Moves the operands of every `OR` for which `is_cached` returns `true` before the other
+    /// ones, keeping their relative order, e.g. to read first the branches whose documents a
+    /// caller already has in a cache.
+    ///
+    /// The nested `OR` are reordered before `is_cached` is called on the conditions containing
+    /// them. The filter matches the same documents.
+    pub fn cached_branches_first(
+        &mut self,
+        mut is_cached: impl FnMut(&FilterCondition<'a>) -> bool,
+    ) {
+        let condition = mem::replace(&mut self.condition, FilterCondition::Const(true));
+        self.condition = cached_branches_first(condition, &mut is_cached);
+    }
+
+    /// Returns the documents matching this filter but not the `other` one.
+    pub fn difference(
+        &self,
+        other: &Filter,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<RoaringBitmap> {
+        let (_added, removed) = self.diff(other, rtxn, index)?;
+        Ok(removed)
+    }
+
+    /// Returns the documents matching exactly one of this filter and the `other` one.
+    pub fn symmetric_difference(
+        &self,
+        other: &Filter,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<RoaringBitmap> {
+        let (added, removed) = self.diff(other, rtxn, index)?;
+        Ok(added | removed)
+    }
+
+    /// Returns the documents the `other` filter adds and the ones it removes compared to this
+    /// filter, e.g. to review the effect of changing a filter before deploying it.
+    ///
+    /// The added documents match the `other` filter but not this one, and the removed ones
+    /// match this filter but not the `other` one.
+    pub fn diff(
+        &self,
+        other: &Filter,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, RoaringBitmap)> {
+        let docids = self.evaluate(rtxn, index)?;
+        let other_docids = other.evaluate(rtxn, index)?;
+        let added = &other_docids - &docids;
+        let removed = docids - other_docids;
+        Ok((added, removed))
+    }
+
+    /// Evaluates a condition on the document id pseudo-field directly against the documents ids,
+    /// returns `None` if the condition doesn't apply to this pseudo-field.
+    fn evaluate_document_id_condition(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        document_id_field: Option<&str>,
+    ) -> Result<Option<RoaringBitmap>> {
+        match &self.condition {
+            FilterCondition::In { fid, els } if Some(fid.value()) == document_id_field => {
+                let ids = els.iter().map(parse_document_id).collect::<Result<RoaringBitmap>>()?;
+                Ok(Some(ids & index.documents_ids(rtxn)?))
+            }
+            FilterCondition::Condition { fid, op }
+                if Some(fid.value()) == document_id_field && fid.function().is_none() =>
+            {
+                let mut docids = index.documents_ids(rtxn)?;
+                match op {
+                    Condition::Equal(id) => {
+                        docids &= RoaringBitmap::from_iter([parse_document_id(id)?]);
+                    }
+                    Condition::NotEqual(id) => {
+                        docids.remove(parse_document_id(id)?);
+                    }
+                    Condition::GreaterThan(id) => {
+                        docids.remove_range(..=parse_document_id(id)?);
+                    }
+                    Condition::GreaterThanOrEqual(id) => {
+                        docids.remove_range(..parse_document_id(id)?);
+                    }
+                    Condition::LowerThan(id) => {
+                        docids.remove_range(parse_document_id(id)?..);
+                    }
+                    Condition::LowerThanOrEqual(id) => {
+                        docids.remove_range((Excluded(parse_document_id(id)?), Bound::Unbounded));
+                    }
+                    Condition::Between { from, to } => {
+                        docids.remove_range(..parse_document_id(from)?);
+                        docids.remove_range((Excluded(parse_document_id(to)?), Bound::Unbounded));
+                    }
+                    Condition::BetweenStep { from, to, step } => {
+                        let from = parse_document_id(from)?;
+                        let to = parse_document_id(to)?;
+                        let step = match parse_document_id(step)? {
+                            0 => Err(step.as_external_error(FilterError::InvalidStepRange {
+                                value: step.value(),
+                            }))?,
+                            step => step,
+                        };
+                        docids = docids
+                            .into_iter()
+                            .filter(|id| (from..=to).contains(id) && (id - from) % step == 0)
+                            .collect();
+                    }
+                    Condition::Interval { from, to } => {
+                        let from = map_bound(from, parse_document_id)?;
+                        let to = map_bound(to, parse_document_id)?;
+                        docids = docids.into_iter().filter(|id| (from, to).contains(id)).collect();
+                    }
+                    Condition::Window { from, limit } => {
+                        let from = parse_document_id(from)?;
+                        docids.remove_range(..from);
+                        if let Some(end) = from.checked_add(parse_document_id(limit)?) {
+                            docids.remove_range(end..);
                         }
-                        bitmap &= Self::inner_evaluate(
-                            &(f.clone()).into(),
-                            rtxn,
-                            index,
-                            filterable_fields,
-                        )?;
                     }
-                    Ok(bitmap)
-                } else {
-                    Ok(RoaringBitmap::new())
+                    Condition::Like(pattern) => {
+                        let pattern = LikePattern::new(pattern.value());
+                        docids = docids
+                            .into_iter()
+                            .filter(|id| pattern.matches(&id.to_string()))
+                            .collect();
+                    }
+                    Condition::Exists | Condition::Integer => (),
+                    Condition::Null | Condition::Empty | Condition::Fractional => docids.clear(),
                 }
+                Ok(Some(docids))
             }
-            FilterCondition::GeoLowerThan { point, radius } => {
-                if filterable_fields.contains("_geo") {
-                    let base_point: [f64; 2] =
-                        [point[0].parse_finite_float()?, point[1].parse_finite_float()?];
-                    if !(-90.0..=90.0).contains(&base_point[0]) {
-                        return Err(point[0].as_external_error(BadGeoError::Lat(base_point[0])))?;
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns an error if one of the attributes referenced by the filter is not faceted
+    /// or doesn't exist in the fields ids map.
+    fn check_referenced_fields(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let faceted_fields = index.faceted_fields(rtxn)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                FilterCondition::Not(condition) => stack.push(condition),
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions)
+                }
+                FilterCondition::Condition { fid, .. }
+                | FilterCondition::In { fid, .. }
+                | FilterCondition::HasAll { fid, .. } => {
+                    if Some(fid.value()) == self.document_id_field.as_deref() {
+                        continue;
                     }
-                    if !(-180.0..=180.0).contains(&base_point[1]) {
-                        return Err(point[1].as_external_error(BadGeoError::Lng(base_point[1])))?;
+                    if !crate::is_faceted(fid.value(), &faceted_fields)
+                        || fields_ids_map.id(fid.value()).is_none()
+                    {
+                        return Err(fid.as_external_error(FilterError::AttributeNotFaceted {
+                            attribute: fid.value(),
+                        }))?;
                     }
-                    let radius = radius.parse_finite_float()?;
-                    let rtree = match index.geo_rtree(rtxn)? {
-                        Some(rtree) => rtree,
-                        None => return Ok(RoaringBitmap::new()),
-                    };
+                }
+                FilterCondition::GeoLowerThan { .. }
+                | FilterCondition::GeoBoundingBox { .. }
+                | FilterCondition::Const(_) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure that the aliases used by the filter refer to attributes of the index.
+    fn check_aliases(&self, fields_ids_map: &FieldsIdsMap) -> Result<()> {
+        let dangling =
+            self.aliases.iter().find(|(_, resolved)| fields_ids_map.id(resolved).is_none());
+        let (alias, resolved) = match dangling {
+            Some(dangling) => dangling,
+            None => return Ok(()),
+        };
+
+        // the error points to the first attribute resolved from the dangling alias
+        let mut condition = self.condition.clone();
+        let attributes = condition.attributes_mut();
+        if let Some(attribute) = attributes.into_iter().find(|attr| attr.value() == resolved) {
+            let error = FilterError::DanglingAlias { alias, attribute: resolved };
+            return Err(attribute.as_external_error(error))?;
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure that no value is listed more than once in a `HAS_ALL` list.
+    fn check_has_all_duplicates(&self) -> Result<()> {
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                FilterCondition::Not(condition) => stack.push(condition),
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions)
+                }
+                FilterCondition::HasAll { fid, els } => {
+                    for (i, el) in els.iter().enumerate() {
+                        if els[..i].iter().any(|previous| same_has_all_value(previous, el)) {
+                            return Err(el.as_external_error(FilterError::DuplicateHasAllValue {
+                                attribute: fid.value(),
+                                value: el.value(),
+                            }))?;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes sure that every value compared with `=`, `!=`, `IN` or `HAS_ALL` is contained by
+    /// its attribute, as a string or as a number.
+    fn check_known_values(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            let (fid, values) = match condition {
+                FilterCondition::Not(condition) => {
+                    stack.push(condition);
+                    continue;
+                }
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions);
+                    continue;
+                }
+                FilterCondition::Condition {
+                    fid,
+                    op: Condition::Equal(value) | Condition::NotEqual(value),
+                } if fid.function().is_none() => (fid, vec![value]),
+                FilterCondition::In { fid, els } | FilterCondition::HasAll { fid, els } => {
+                    (fid, els.iter().collect())
+                }
+                _ => continue,
+            };
+
+            if Some(fid.value()) == self.document_id_field.as_deref() {
+                continue;
+            }
+            let field_id = match fields_ids_map.id(fid.value()) {
+                Some(field_id) => field_id,
+                None => continue,
+            };
+
+            for value in values {
+                if value.is_placeholder() || value.expression().is_some() {
+                    continue;
+                }
+                let string = match value.annotation() {
+                    Some(TypeAnnotation::F64 | TypeAnnotation::I64) => None,
+                    _ => Some(crate::normalize_facet(value.value())),
+                };
+                // a quoted value is a string even if it looks like a number, e.g. `"01234"`
+                let number = match value.annotation() {
+                    Some(TypeAnnotation::String) => None,
+                    Some(_) => value.parse_finite_float().ok(),
+                    None => value.parse_finite_float().ok().filter(|_| !value.is_quoted()),
+                };
+
+                let contains_string = match &string {
+                    Some(string) => {
+                        let key = FacetGroupKey { field_id, level: 0, left_bound: string.as_str() };
+                        index.facet_id_string_docids.get(rtxn, &key)?.is_some()
+                    }
+                    None => false,
+                };
+                let contains_number = match number {
+                    Some(number) => {
+                        let key = FacetGroupKey { field_id, level: 0, left_bound: number };
+                        index.facet_id_f64_docids.get(rtxn, &key)?.is_some()
+                    }
+                    None => false,
+                };
+
+                if !contains_string && !contains_number {
+                    return Err(value.as_external_error(FilterError::UnknownValue {
+                        attribute: fid.value(),
+                        value: value.value(),
+                    }))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure the value of the operator is not a placeholder or an expression that was never bound.
+    fn check_placeholder(operator: &Condition<'a>) -> Result<()> {
+        match operator {
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::Equal(value)
+            | Condition::NotEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value)
+                if value.is_placeholder() =>
+            {
+                Err(value.as_external_error(FilterError::UnboundPlaceholder))?
+            }
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::Equal(value)
+            | Condition::NotEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value)
+                if value.expression().is_some() =>
+            {
+                Err(value.as_external_error(FilterError::UnboundExpression))?
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns an error if a number with a fractional part is compared to an attribute whose
+    /// numbers are overridden as integers.
+    fn check_number_types(&self) -> Result<()> {
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            let (fid, values) = match condition {
+                FilterCondition::Not(condition) => {
+                    stack.push(condition);
+                    continue;
+                }
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions);
+                    continue;
+                }
+                condition => match compared_numbers(condition) {
+                    Some(compared) => compared,
+                    None => continue,
+                },
+            };
+
+            if self.number_types.get(fid.value()) != Some(&NumberType::Integer) {
+                continue;
+            }
+            for value in values {
+                if value.is_quoted() || value.annotation() == Some(TypeAnnotation::String) {
+                    continue;
+                }
+                if let Ok(number) = value.parse_finite_float() {
+                    if number.fract() != 0.0 {
+                        return Err(value.as_external_error(FilterError::NotAnInteger {
+                            attribute: fid.value(),
+                            value: value.value(),
+                        }))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if an integer is compared to an attribute containing floats, or a float
+    /// to an attribute only containing integers.
+    fn check_strict_types(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        // whether each field contains floats, `None` if it doesn't contain numbers
+        let mut contains_floats = HashMap::new();
+
+        let mut stack = vec![&self.condition];
+        while let Some(condition) = stack.pop() {
+            let (fid, values) = match condition {
+                FilterCondition::Not(condition) => {
+                    stack.push(condition);
+                    continue;
+                }
+                FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                    stack.extend(conditions);
+                    continue;
+                }
+                condition => match compared_numbers(condition) {
+                    Some(compared) => compared,
+                    None => continue,
+                },
+            };
+
+            if Some(fid.value()) == self.document_id_field.as_deref() {
+                continue;
+            }
+            // the numbers compared with a field of overridden type are read as this type
+            if self.number_types.contains_key(fid.value()) {
+                continue;
+            }
+            let field_id = match fields_ids_map.id(fid.value()) {
+                Some(field_id) => field_id,
+                None => continue,
+            };
+
+            for value in values {
+                if value.is_quoted()
+                    || value.annotation().is_some()
+                    || value.parse_finite_float().is_err()
+                {
+                    continue;
+                }
+                let floats = match contains_floats.get(&field_id) {
+                    Some(floats) => *floats,
+                    None => {
+                        let floats = facet_contains_floats(rtxn, index, field_id)?;
+                        *contains_floats.entry(field_id).or_insert(floats)
+                    }
+                };
+                let is_integer = value.value().parse::<i64>().is_ok();
+                if let Some(floats) = floats.filter(|floats| *floats == is_integer) {
+                    let attribute = fid.value().to_string();
+                    return Err(value.as_external_error(FilterError::StrictTypeMismatch {
+                        attribute,
+                        value: value.value(),
+                        floats,
+                    }))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_operator(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+        metrics: &mut dyn FacetMetrics,
+        string_lookups: &mut StringLookups,
+    ) -> Result<RoaringBitmap> {
+        Self::evaluate_operator_among(
+            rtxn,
+            index,
+            field_id,
+            operator,
+            metrics,
+            string_lookups,
+            None,
+        )
+    }
+
+    /// Evaluates the operator like [`Self::evaluate_operator`], but only the documents among
+    /// the `candidates` are needed: the groups of the facet levels containing none of them are
+    /// skipped when traversing a numeric range. The returned documents must be intersected with
+    /// the candidates.
+    fn evaluate_operator_among(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+        metrics: &mut dyn FacetMetrics,
+        string_lookups: &mut StringLookups,
+        candidates: Option<&RoaringBitmap>,
+    ) -> Result<RoaringBitmap> {
+        let numbers_db = index.facet_id_f64_docids;
+
+        // Make sure we always bound the ranges with the field id and the level,
+        // as the facets values are all in the same database and prefixed by the
+        // field id and the level.
+
+        Self::check_placeholder(operator)?;
+        Self::check_numeric_operator(rtxn, index, field_id, operator)?;
+        Self::check_type_annotation(rtxn, index, field_id, operator)?;
+
+        let (left, right) = match operator {
+            Condition::GreaterThan(val) => {
+                (Excluded(val.parse_finite_float()?), Included(f64::MAX))
+            }
+            Condition::GreaterThanOrEqual(val) => {
+                (Included(val.parse_finite_float()?), Included(f64::MAX))
+            }
+            Condition::LowerThan(val) => (Included(f64::MIN), Excluded(val.parse_finite_float()?)),
+            Condition::LowerThanOrEqual(val) => {
+                (Included(f64::MIN), Included(val.parse_finite_float()?))
+            }
+            Condition::Between { from, to } => {
+                if Self::is_string_range(rtxn, index, field_id, from, to)? {
+                    return Self::evaluate_string_range(rtxn, index, field_id, from, to, metrics);
+                }
+                (Included(from.parse_finite_float()?), Included(to.parse_finite_float()?))
+            }
+            Condition::Interval { from, to } => {
+                let from = match map_bound(from, |from| Ok(from.parse_finite_float()?))? {
+                    Bound::Unbounded => Included(f64::MIN),
+                    from => from,
+                };
+                let to = match map_bound(to, |to| Ok(to.parse_finite_float()?))? {
+                    Bound::Unbounded => Included(f64::MAX),
+                    to => to,
+                };
+                (from, to)
+            }
+            Condition::BetweenStep { from, to, step: step_token } => {
+                let from = parse_step_range_integer(from)?;
+                let to = parse_step_range_integer(to)?;
+                let step = parse_step_range_integer(step_token)?;
+                if step <= 0 {
+                    return Err(step_token.as_external_error(FilterError::InvalidStepRange {
+                        value: step_token.value(),
+                    }))?;
+                }
+                if from > to {
+                    return Ok(RoaringBitmap::new());
+                }
+                return Self::evaluate_integer_run(rtxn, index, field_id, from..=to, step, metrics);
+            }
+            Condition::Null => {
+                let is_null = index.null_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(is_null);
+            }
+            Condition::Empty => {
+                let is_empty = index.empty_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(is_empty);
+            }
+            Condition::Exists => {
+                let exist = index.exists_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(exist);
+            }
+            Condition::Window { from, limit } => {
+                if facet_contains_floats(rtxn, index, field_id)? == Some(true) {
+                    let fields_ids_map = index.fields_ids_map(rtxn)?;
+                    let attribute = fields_ids_map.name(field_id).unwrap_or_default().to_string();
+                    return Err(from.as_external_error(FilterError::WindowOnFloats { attribute }))?;
+                }
+                let (from, end) = window_bounds(from, limit)?;
+                (Included(from as f64), Excluded(end as f64))
+            }
+            Condition::Like(pattern) => {
+                return Self::evaluate_like(rtxn, index, field_id, pattern, metrics);
+            }
+            Condition::Integer => {
+                return Self::evaluate_fractional_part(rtxn, index, field_id, false, metrics);
+            }
+            Condition::Fractional => {
+                return Self::evaluate_fractional_part(rtxn, index, field_id, true, metrics);
+            }
+            Condition::Equal(val) if val.annotation().is_some() => {
+                let docids = match val.annotation() {
+                    Some(TypeAnnotation::String) => {
+                        return Self::string_docids(
+                            rtxn,
+                            index,
+                            field_id,
+                            val.value(),
+                            metrics,
+                            string_lookups,
+                        );
+                    }
+                    _ => numbers_db
+                        .get(
+                            rtxn,
+                            &FacetGroupKey {
+                                field_id,
+                                level: 0,
+                                left_bound: val.parse_finite_float()?,
+                            },
+                        )?
+                        .map(|v| v.bitmap)
+                        .unwrap_or_default(),
+                };
+                record_read(metrics, field_id)?;
+                return Ok(docids);
+            }
+            Condition::Equal(val) => {
+                let string_docids = Self::string_docids(
+                    rtxn,
+                    index,
+                    field_id,
+                    val.value(),
+                    metrics,
+                    string_lookups,
+                )?;
+                // a quoted value is a string even if it looks like a number, e.g. `"01234"`
+                let number = val.parse_finite_float().ok().filter(|_| !val.is_quoted());
+                let number_docids = match number {
+                    Some(n) => numbers_db
+                        .get(rtxn, &FacetGroupKey { field_id, level: 0, left_bound: n })?
+                        .map(|v| v.bitmap)
+                        .unwrap_or_default(),
+                    None => RoaringBitmap::new(),
+                };
+                if number.is_some() {
+                    record_read(metrics, field_id)?;
+                }
+                return Ok(string_docids | number_docids);
+            }
+            Condition::NotEqual(val) => {
+                let operator = Condition::Equal(val.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    field_id,
+                    &operator,
+                    metrics,
+                    string_lookups,
+                )?;
+                let all_ids = index.documents_ids(rtxn)?;
+                return Ok(all_ids - docids);
+            }
+        };
+
+        let mut output = RoaringBitmap::new();
+        Self::explore_facet_number_levels(
+            rtxn,
+            numbers_db,
+            field_id,
+            left,
+            right,
+            candidates,
+            &mut output,
+            metrics,
+        )?;
+        Ok(output)
+    }
+
+    /// Returns the documents containing the given string, reusing the documents found by a
+    /// previous lookup of the same value when the lookups are cached.
+    fn string_docids(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        value: &str,
+        metrics: &mut dyn FacetMetrics,
+        string_lookups: &mut StringLookups,
+    ) -> Result<RoaringBitmap> {
+        let key = (field_id, crate::normalize_facet(value));
+        if let Some(docids) = string_lookups.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            if !docids.is_empty() {
+                metrics.string_value_matched(field_id, &key.1, docids);
+            }
+            return Ok(docids.clone());
+        }
+
+        let docids = if string_lookups.is_interned(rtxn, index, field_id)? {
+            Self::interned_string_docids(rtxn, index, field_id, &key.1, metrics)?
+        } else {
+            let docids = index
+                .facet_id_string_docids
+                .get(rtxn, &FacetGroupKey { field_id, level: 0, left_bound: key.1.as_str() })?
+                .map(|v| v.bitmap)
+                .unwrap_or_default();
+            record_read(metrics, field_id)?;
+            docids
+        };
+        if !docids.is_empty() {
+            metrics.string_value_matched(field_id, &key.1, &docids);
+        }
+        if let Some(cache) = &mut string_lookups.cache {
+            cache.insert(key, docids.clone());
+        }
+        Ok(docids)
+    }
+
+    /// Returns the documents of the normalized string `value` of an interned field with a lookup
+    /// of its value id and a fetch of the bitmap stored under this id, see
+    /// [`Index::interned_string_facets`].
+    fn interned_string_docids(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        value: &str,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let value_id = index.facet_id_string_value_ids.get(rtxn, &(field_id, value))?;
+        record_read(metrics, field_id)?;
+        let value_id = match value_id {
+            Some(value_id) => value_id,
+            None => return Ok(RoaringBitmap::new()),
+        };
+        let docids = index.facet_id_value_id_docids.get(rtxn, &(field_id, value_id))?;
+        record_read(metrics, field_id)?;
+        Ok(docids.unwrap_or_default())
+    }
+
+    /// Returns an error if a numeric operator (`>`, `>=`, `<`, `<=` or `TO`) is used
+    /// on a field that only contains strings, as it could never match anything.
+    fn check_numeric_operator(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+    ) -> Result<()> {
+        let (operator, token) = match operator {
+            Condition::GreaterThan(val) => (">", val),
+            Condition::GreaterThanOrEqual(val) => (">=", val),
+            Condition::LowerThan(val) => ("<", val),
+            Condition::LowerThanOrEqual(val) => ("<=", val),
+            Condition::Between { from, to } => {
+                if Self::is_string_range(rtxn, index, field_id, from, to)? {
+                    return Ok(());
+                }
+                ("TO", from)
+            }
+            Condition::BetweenStep { from, .. } => ("TO", from),
+            Condition::Window { from, .. } => ("FROM", from),
+            Condition::Interval { from: Included(token) | Excluded(token), .. }
+            | Condition::Interval { to: Included(token) | Excluded(token), .. } => {
+                ("interval", token)
+            }
+            _ => return Ok(()),
+        };
+
+        if indexed_facet_type(rtxn, index, field_id)? == Some(FacetType::String) {
+            let fields_ids_map = index.fields_ids_map(rtxn)?;
+            let attribute = fields_ids_map.name(field_id).unwrap_or_default().to_string();
+            return Err(token
+                .as_external_error(FilterError::NumericOperatorOnString { attribute, operator }))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if an annotated value (e.g. `f64:9`) is not a valid literal of its
+    /// annotated type, or if the annotation conflicts with the type of the indexed values.
+    fn check_type_annotation(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+    ) -> Result<()> {
+        let token = match operator {
+            Condition::GreaterThan(val)
+            | Condition::GreaterThanOrEqual(val)
+            | Condition::LowerThan(val)
+            | Condition::LowerThanOrEqual(val)
+            | Condition::Equal(val)
+            | Condition::NotEqual(val) => val,
+            _ => return Ok(()),
+        };
+        let annotation = match token.annotation() {
+            Some(annotation) => annotation,
+            None => return Ok(()),
+        };
+
+        let (is_valid, expected) = match annotation {
+            TypeAnnotation::F64 => (token.parse_finite_float().is_ok(), FacetType::Number),
+            TypeAnnotation::I64 => (token.value().parse::<i64>().is_ok(), FacetType::Number),
+            TypeAnnotation::String => (true, FacetType::String),
+        };
+        if !is_valid {
+            let value = token.value().to_string();
+            return Err(
+                token.as_external_error(FilterError::InvalidAnnotatedValue { value, annotation })
+            )?;
+        }
+
+        match indexed_facet_type(rtxn, index, field_id)? {
+            Some(indexed) if indexed != expected => {
+                let fields_ids_map = index.fields_ids_map(rtxn)?;
+                let attribute = fields_ids_map.name(field_id).unwrap_or_default().to_string();
+                Err(token.as_external_error(FilterError::AnnotationConflict {
+                    attribute,
+                    annotation,
+                    indexed,
+                }))?
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the documents whose value is one of the integers of the run taken every `step`,
+    /// using a single range over the facet values instead of one lookup per integer.
+    fn evaluate_integer_run(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        run: RangeInclusive<i64>,
+        step: i64,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let left = FacetGroupKey { field_id, level: 0, left_bound: *run.start() as f64 };
+        let right = FacetGroupKey { field_id, level: 0, left_bound: *run.end() as f64 };
+
+        let mut docids = RoaringBitmap::new();
+        for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+            let (key, value) = result?;
+            record_read(metrics, field_id)?;
+            // the range also contains the non-integer values and the values out of step in between,
+            // the offset is computed on 128 bits as it can exceed `i64::MAX`
+            let offset = key.left_bound as i128 - *run.start() as i128;
+            if key.left_bound.fract() == 0.0 && offset % step as i128 == 0 {
+                docids |= value.bitmap;
+            }
+        }
+
+        Ok(docids)
+    }
+
+    /// A `TO` range is lexicographic when one of its bounds isn't a number and the field
+    /// contains strings. On a numbers-only field such a range is reported as an invalid number.
+    fn is_string_range(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        from: &Token,
+        to: &Token,
+    ) -> Result<bool> {
+        if from.parse_finite_float().is_ok() && to.parse_finite_float().is_ok() {
+            return Ok(false);
+        }
+        Ok(indexed_facet_type(rtxn, index, field_id)? != Some(FacetType::Number))
+    }
+
+    /// Returns the documents with a string value between `from` and `to`, ignoring the case.
+    ///
+    /// The string facet values are stored normalized, and thus lowercased, so folding the bounds
+    /// the same way lets the range be scanned directly at level 0 instead of comparing every value
+    /// of the field. It is still a level 0 scan that reads one entry per distinct value in the
+    /// range, where a numeric range only reads a few entries of the higher levels.
+    ///
+    /// The upper bound is a prefix: it includes every value starting with it, even the ones that
+    /// sort after it, so `name A TO M` matches `Mango` and `name a TO ban` matches `Banana`. The
+    /// values sorting after the bound that don't start with it are excluded, like `Banana` for
+    /// `name a TO apples`.
+    fn evaluate_string_range(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        from: &Token,
+        to: &Token,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let from = crate::normalize_facet(from.value());
+        let to = crate::normalize_facet(to.value());
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: from.as_str() };
+        let end_of_level = FacetGroupKey { field_id, level: 1, left_bound: "" };
+
+        let mut docids = RoaringBitmap::new();
+        for result in
+            index.facet_id_string_docids.range(rtxn, &(Included(left), Excluded(end_of_level)))?
+        {
+            let (key, value) = result?;
+            record_read(metrics, field_id)?;
+            if key.left_bound > to.as_str() && !key.left_bound.starts_with(to.as_str()) {
+                break;
+            }
+            metrics.string_value_matched(field_id, key.left_bound, &value.bitmap);
+            docids |= value.bitmap;
+        }
+
+        Ok(docids)
+    }
+
+    /// Returns the documents with a number that has a fractional part, e.g. `10.5`, or the ones
+    /// with a number that doesn't, e.g. `10` or `10.0`, when `fractional` is `false`.
+    ///
+    /// The facet levels group the numbers by value, not by fractional part, so every number of
+    /// the field is read at level 0: the cost grows with the number of distinct values of the
+    /// field. A document with both kinds of numbers, e.g. `[10, 10.5]`, matches both.
+    fn evaluate_fractional_part(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        fractional: bool,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+        let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+
+        let mut docids = RoaringBitmap::new();
+        for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+            let (key, value) = result?;
+            record_read(metrics, field_id)?;
+            if (key.left_bound.fract() != 0.0) == fractional {
+                docids |= value.bitmap;
+            }
+        }
+
+        Ok(docids)
+    }
+
+    /// Returns the documents with a string value matching the `LIKE` pattern, ignoring the case.
+    ///
+    /// When the pattern starts with literal characters, only the values starting with them
+    /// are scanned at level 0, e.g. `A%` only reads the values starting with `a`. Otherwise,
+    /// e.g. with `%b`, every string value of the field is compared to the pattern.
+    fn evaluate_like(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        pattern: &Token,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let pattern = LikePattern::new(&crate::normalize_facet(pattern.value()));
+        let prefix = pattern.prefix();
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: prefix.as_str() };
+        let end_of_level = FacetGroupKey { field_id, level: 1, left_bound: "" };
+
+        let mut docids = RoaringBitmap::new();
+        for result in
+            index.facet_id_string_docids.range(rtxn, &(Included(left), Excluded(end_of_level)))?
+        {
+            let (key, value) = result?;
+            record_read(metrics, field_id)?;
+            if !key.left_bound.starts_with(prefix.as_str()) {
+                break;
+            }
+            if pattern.matches(key.left_bound) {
+                metrics.string_value_matched(field_id, key.left_bound, &value.bitmap);
+                docids |= value.bitmap;
+            }
+        }
+
+        Ok(docids)
+    }
+
+    /// Returns the documents with a string value matching the condition once the `function` is
+    /// applied to it, e.g. `LOWER(name) = foo` or `LENGTH(title) > 5`.
+    ///
+    /// Every string value of the field is scanned at level 0, the numbers are not, except by the
+    /// `COUNT` function which counts both, see [`Self::evaluate_count`]. The function
+    /// is applied to the values as they are indexed, trimmed and lowercased: `UPPER(name) = FOO`
+    /// matches the name `Foo` and `LENGTH(name) = 3` matches the name ` Foo `. As `LOWER` and
+    /// `TRIM` leave these values unchanged, the value they are compared with is normalized the
+    /// same way, so `TRIM(name) = 'Foo Bar'` matches the name ` Foo Bar `.
+    fn evaluate_function(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        function: ValueFunction,
+        attribute: &Token<'a>,
+        operator: &Condition<'a>,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        Self::check_placeholder(operator)?;
+
+        let (from, to) = match operator {
+            Condition::NotEqual(val) => {
+                let operator = Condition::Equal(val.clone());
+                let docids = Self::evaluate_function(
+                    rtxn, index, field_id, function, attribute, &operator, metrics,
+                )?;
+                let all_ids = index.documents_ids(rtxn)?;
+                return Ok(all_ids - docids);
+            }
+            // the function of a missing value is missing too
+            Condition::Null | Condition::Empty | Condition::Exists => {
+                return Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    field_id,
+                    operator,
+                    metrics,
+                    &mut StringLookups::default(),
+                );
+            }
+            Condition::Equal(val) => (Included(val.clone()), Included(val.clone())),
+            Condition::GreaterThan(val) => (Excluded(val.clone()), Bound::Unbounded),
+            Condition::GreaterThanOrEqual(val) => (Included(val.clone()), Bound::Unbounded),
+            Condition::LowerThan(val) => (Bound::Unbounded, Excluded(val.clone())),
+            Condition::LowerThanOrEqual(val) => (Bound::Unbounded, Included(val.clone())),
+            Condition::Between { from, to } => (Included(from.clone()), Included(to.clone())),
+            Condition::Interval { from, to } => (from.clone(), to.clone()),
+            Condition::BetweenStep { .. }
+            | Condition::Like(_)
+            | Condition::Window { .. }
+            | Condition::Integer
+            | Condition::Fractional => {
+                return Err(attribute
+                    .as_external_error(FilterError::UnsupportedFunctionOperator { function }))?;
+            }
+        };
+
+        if function == ValueFunction::Count {
+            let counts = (
+                map_bound(&from, |token| Ok(token.parse_finite_float()?))?,
+                map_bound(&to, |token| Ok(token.parse_finite_float()?))?,
+            );
+            return Self::evaluate_count(rtxn, index, field_id, counts, metrics);
+        }
+
+        // the lengths are compared as numbers, the other functions return strings
+        let string = |token: &Token| -> Result<String> {
+            match function {
+                ValueFunction::Lower | ValueFunction::Trim => {
+                    Ok(crate::normalize_facet(token.value()))
+                }
+                _ => Ok(token.value().to_string()),
+            }
+        };
+        let strings = (map_bound(&from, string)?, map_bound(&to, string)?);
+        let matches: Box<dyn Fn(&str) -> bool> = match function {
+            ValueFunction::Lower => Box::new(|value: &str| strings.contains(&value.to_lowercase())),
+            ValueFunction::Upper => Box::new(|value: &str| strings.contains(&value.to_uppercase())),
+            ValueFunction::Trim => {
+                Box::new(|value: &str| strings.contains(&value.trim().to_string()))
+            }
+            ValueFunction::Length => {
+                let lengths = (
+                    map_bound(&from, |token| Ok(token.parse_finite_float()?))?,
+                    map_bound(&to, |token| Ok(token.parse_finite_float()?))?,
+                );
+                Box::new(move |value: &str| lengths.contains(&(value.chars().count() as f64)))
+            }
+        };
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: "" };
+        let end_of_level = FacetGroupKey { field_id, level: 1, left_bound: "" };
+
+        let mut docids = RoaringBitmap::new();
+        for result in
+            index.facet_id_string_docids.range(rtxn, &(Included(left), Excluded(end_of_level)))?
+        {
+            let (key, value) = result?;
+            record_read(metrics, field_id)?;
+            if matches(key.left_bound) {
+                metrics.string_value_matched(field_id, key.left_bound, &value.bitmap);
+                docids |= value.bitmap;
+            }
+        }
+
+        Ok(docids)
+    }
+
+    /// Returns the documents with a number of distinct values of the field within `counts`,
+    /// e.g. `tags BETWEEN_COUNT 2 TO 5`. The documents without any value have a count of zero.
+    ///
+    /// The facet databases don't store the number of values of the documents, so every string
+    /// and number of the field is read at level 0 and counted for each of its documents. The
+    /// cost grows with the number of values of the field, not with the size of the range.
+    fn evaluate_count(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        counts: (Bound<f64>, Bound<f64>),
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<RoaringBitmap> {
+        let mut documents_counts: HashMap<DocumentId, u32> = HashMap::new();
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: "" };
+        let end_of_level = FacetGroupKey { field_id, level: 1, left_bound: "" };
+        for result in
+            index.facet_id_string_docids.range(rtxn, &(Included(left), Excluded(end_of_level)))?
+        {
+            let (_, value) = result?;
+            record_read(metrics, field_id)?;
+            for docid in value.bitmap {
+                *documents_counts.entry(docid).or_default() += 1;
+            }
+        }
+
+        let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+        let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+        for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+            let (_, value) = result?;
+            record_read(metrics, field_id)?;
+            for docid in value.bitmap {
+                *documents_counts.entry(docid).or_default() += 1;
+            }
+        }
+
+        let mut docids: RoaringBitmap = documents_counts
+            .iter()
+            .filter(|(_, &count)| counts.contains(&(count as f64)))
+            .map(|(&docid, _)| docid)
+            .collect();
+        if counts.contains(&0.0) {
+            let mut without_values = index.documents_ids(rtxn)?;
+            for docid in documents_counts.keys() {
+                without_values.remove(*docid);
+            }
+            docids |= without_values;
+        }
+
+        Ok(docids)
+    }
+
+    /// Aggregates the documents ids that are part of the specified range automatically
+    /// going deeper through the levels, skipping the groups without any of the `candidates`.
+    #[allow(clippy::too_many_arguments)]
+    fn explore_facet_number_levels(
+        rtxn: &heed::RoTxn,
+        db: heed::Database<FacetGroupKeyCodec<OrderedF64Codec>, FacetGroupValueCodec>,
+        field_id: FieldId,
+        left: Bound<f64>,
+        right: Bound<f64>,
+        candidates: Option<&RoaringBitmap>,
+        output: &mut RoaringBitmap,
+        metrics: &mut dyn FacetMetrics,
+    ) -> Result<()> {
+        match (left, right) {
+            // lower TO upper when lower > upper must return no result
+            (Included(l), Included(r)) if l > r => return Ok(()),
+            (Included(l), Excluded(r)) if l >= r => return Ok(()),
+            (Excluded(l), Excluded(r)) if l >= r => return Ok(()),
+            (Excluded(l), Included(r)) if l >= r => return Ok(()),
+            (_, _) => (),
+        }
+        match candidates {
+            Some(candidates) => {
+                facet_range_search::find_docids_of_facet_within_bounds_among::<OrderedF64Codec>(
+                    rtxn, db, field_id, &left, &right, candidates, output, metrics,
+                )?
+            }
+            None => facet_range_search::find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+                rtxn, db, field_id, &left, &right, output, metrics,
+            )?,
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates a comparison of an attribute, only the documents among the `candidates`
+    /// being needed if any, see [`Self::evaluate_operator_among`].
+    #[allow(clippy::too_many_arguments)]
+    fn evaluate_condition(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        token: &Token<'a>,
+        op: &Condition<'a>,
+        metrics: &mut dyn FacetMetrics,
+        string_lookups: &mut StringLookups,
+        candidates: Option<&RoaringBitmap>,
+    ) -> Result<RoaringBitmap> {
+        if crate::is_faceted(token.value(), filterable_fields) {
+            if let Some(fid) = fields_ids_map.id(token.value()) {
+                let docids = match token.function() {
+                    Some(function) => {
+                        Self::evaluate_function(rtxn, index, fid, function, token, op, metrics)?
+                    }
+                    None => Self::evaluate_operator_among(
+                        rtxn,
+                        index,
+                        fid,
+                        op,
+                        metrics,
+                        string_lookups,
+                        candidates,
+                    )?,
+                };
+                metrics.documents_matched(fid, docids.len());
+                Ok(docids)
+            } else {
+                Ok(RoaringBitmap::new())
+            }
+        } else {
+            Err(token.as_external_error(FilterError::AttributeNotFilterable {
+                attribute: token.value(),
+                filterable_fields: filterable_fields.clone(),
+            }))?
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn inner_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        metrics: &mut dyn FacetMetrics,
+        string_lookups: &mut StringLookups,
+        document_id_field: Option<&str>,
+        in_list_order: InListOrder,
+    ) -> Result<RoaringBitmap> {
+        if let Some(docids) = self.evaluate_document_id_condition(rtxn, index, document_id_field)? {
+            return Ok(docids);
+        }
+
+        match &self.condition {
+            FilterCondition::Not(f) => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let selected = Self::inner_evaluate(
+                    &(f.as_ref().clone()).into(),
+                    rtxn,
+                    index,
+                    fields_ids_map,
+                    filterable_fields,
+                    metrics,
+                    string_lookups,
+                    document_id_field,
+                    in_list_order,
+                )?;
+                Ok(all_ids - selected)
+            }
+            FilterCondition::In { fid, els } => {
+                if crate::is_faceted(fid.value(), filterable_fields) {
+                    if let Some(fid) = fields_ids_map.id(fid.value()) {
+                        // the annotations of the whole list are checked before any lookup
+                        for el in els.iter().filter(|el| el.annotation().is_some()) {
+                            let op = Condition::Equal(el.clone());
+                            Self::check_type_annotation(rtxn, index, fid, &op)?;
+                        }
+
+                        let mut bitmap = RoaringBitmap::new();
+
+                        // Runs of contiguous integers are only collapsed on numeric fields,
+                        // as their values can't be matched by a string facet, and when the
+                        // elements don't have to be evaluated in a given order.
+                        let (runs, els) = if in_list_order == InListOrder::Any
+                            && indexed_facet_type(rtxn, index, fid)? == Some(FacetType::Number)
+                        {
+                            integer_runs(els)
+                        } else {
+                            (Vec::new(), ordered_in_list(els, in_list_order))
+                        };
+
+                        for run in runs {
+                            bitmap |=
+                                Self::evaluate_integer_run(rtxn, index, fid, run, 1, metrics)?;
+                        }
+                        for el in els {
+                            let op = Condition::Equal(el.clone());
+                            let el_bitmap = Self::evaluate_operator(
+                                rtxn,
+                                index,
+                                fid,
+                                &op,
+                                metrics,
+                                string_lookups,
+                            )?;
+                            metrics.in_value_matched(fid, el.value(), &el_bitmap);
+                            bitmap |= el_bitmap;
+                        }
+                        metrics.documents_matched(fid, bitmap.len());
+                        Ok(bitmap)
+                    } else {
+                        Ok(RoaringBitmap::new())
+                    }
+                } else {
+                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
+                        attribute: fid.value(),
+                        filterable_fields: filterable_fields.clone(),
+                    }))?
+                }
+            }
+            FilterCondition::HasAll { fid, els } => {
+                if crate::is_faceted(fid.value(), filterable_fields) {
+                    if let Some(fid) = fields_ids_map.id(fid.value()) {
+                        // the documents of a value listed more than once are only intersected once
+                        let mut els = els.iter().enumerate().filter_map(|(i, el)| {
+                            let duplicate =
+                                els[..i].iter().any(|previous| same_has_all_value(previous, el));
+                            (!duplicate).then_some(el)
+                        });
+                        let mut bitmap = match els.next() {
+                            Some(el) => {
+                                let op = Condition::Equal(el.clone());
+                                Self::evaluate_operator(
+                                    rtxn,
+                                    index,
+                                    fid,
+                                    &op,
+                                    metrics,
+                                    string_lookups,
+                                )?
+                            }
+                            None => return Ok(RoaringBitmap::new()),
+                        };
+
+                        for el in els {
+                            if bitmap.is_empty() {
+                                break;
+                            }
+                            let op = Condition::Equal(el.clone());
+                            bitmap &= Self::evaluate_operator(
+                                rtxn,
+                                index,
+                                fid,
+                                &op,
+                                metrics,
+                                string_lookups,
+                            )?;
+                        }
+                        metrics.documents_matched(fid, bitmap.len());
+                        Ok(bitmap)
+                    } else {
+                        Ok(RoaringBitmap::new())
+                    }
+                } else {
+                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
+                        attribute: fid.value(),
+                        filterable_fields: filterable_fields.clone(),
+                    }))?
+                }
+            }
+            FilterCondition::Condition { fid, op } => Self::evaluate_condition(
+                rtxn,
+                index,
+                fields_ids_map,
+                filterable_fields,
+                fid,
+                op,
+                metrics,
+                string_lookups,
+                None,
+            ),
+            FilterCondition::Or(subfilters) => {
+                let mut bitmap = RoaringBitmap::new();
+                for f in subfilters {
+                    bitmap |= Self::inner_evaluate(
+                        &(f.clone()).into(),
+                        rtxn,
+                        index,
+                        fields_ids_map,
+                        filterable_fields,
+                        metrics,
+                        string_lookups,
+                        document_id_field,
+                        in_list_order,
+                    )?;
+                }
+                Ok(bitmap)
+            }
+            FilterCondition::And(subfilters) => {
+                let mut subfilters_iter = subfilters.iter();
+                if let Some(first_subfilter) = subfilters_iter.next() {
+                    let mut bitmap = Self::inner_evaluate(
+                        &(first_subfilter.clone()).into(),
+                        rtxn,
+                        index,
+                        fields_ids_map,
+                        filterable_fields,
+                        metrics,
+                        string_lookups,
+                        document_id_field,
+                        in_list_order,
+                    )?;
+                    for f in subfilters_iter {
+                        if bitmap.is_empty() {
+                            return Ok(bitmap);
+                        }
+                        let docids = match f {
+                            // only the documents already selected are needed, a numeric range
+                            // skips the groups of values containing none of them
+                            FilterCondition::Condition { fid, op }
+                                if Some(fid.value()) != document_id_field =>
+                            {
+                                Self::evaluate_condition(
+                                    rtxn,
+                                    index,
+                                    fields_ids_map,
+                                    filterable_fields,
+                                    fid,
+                                    op,
+                                    metrics,
+                                    string_lookups,
+                                    Some(&bitmap),
+                                )?
+                            }
+                            f => Self::inner_evaluate(
+                                &(f.clone()).into(),
+                                rtxn,
+                                index,
+                                fields_ids_map,
+                                filterable_fields,
+                                metrics,
+                                string_lookups,
+                                document_id_field,
+                                in_list_order,
+                            )?,
+                        };
+                        bitmap &= docids;
+                    }
+                    Ok(bitmap)
+                } else {
+                    Ok(RoaringBitmap::new())
+                }
+            }
+            FilterCondition::Const(true) => Ok(index.documents_ids(rtxn)?),
+            FilterCondition::Const(false) => Ok(RoaringBitmap::new()),
+            FilterCondition::GeoLowerThan { point, radius } => {
+                if filterable_fields.contains("_geo") {
+                    let base_point: [f64; 2] =
+                        [point[0].parse_finite_float()?, point[1].parse_finite_float()?];
+                    if !(-90.0..=90.0).contains(&base_point[0]) {
+                        return Err(point[0].as_external_error(BadGeoError::Lat(base_point[0])))?;
+                    }
+                    if !(-180.0..=180.0).contains(&base_point[1]) {
+                        return Err(point[1].as_external_error(BadGeoError::Lng(base_point[1])))?;
+                    }
+                    let radius = radius.parse_finite_float()?;
+                    let rtree = match index.geo_rtree(rtxn)? {
+                        Some(rtree) => rtree,
+                        None => return Ok(RoaringBitmap::new()),
+                    };
+
+                    let xyz_base_point = lat_lng_to_xyz(&base_point);
+
+                    let result = rtree
+                        .nearest_neighbor_iter(&xyz_base_point)
+                        .take_while(|point| {
+                            distance_between_two_points(&base_point, &point.data.1)
+                                <= radius + f64::EPSILON
+                        })
+                        .map(|point| point.data.0)
+                        .collect();
+
+                    Ok(result)
+                } else {
+                    Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
+                        attribute: "_geo",
+                        filterable_fields: filterable_fields.clone(),
+                    }))?
+                }
+            }
+            FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
+                if filterable_fields.contains("_geo") {
+                    let top_right: [f64; 2] = [
+                        top_right_point[0].parse_finite_float()?,
+                        top_right_point[1].parse_finite_float()?,
+                    ];
+                    let bottom_left: [f64; 2] = [
+                        bottom_left_point[0].parse_finite_float()?,
+                        bottom_left_point[1].parse_finite_float()?,
+                    ];
+                    if !(-90.0..=90.0).contains(&top_right[0]) {
+                        return Err(
+                            top_right_point[0].as_external_error(BadGeoError::Lat(top_right[0]))
+                        )?;
+                    }
+                    if !(-180.0..=180.0).contains(&top_right[1]) {
+                        return Err(
+                            top_right_point[1].as_external_error(BadGeoError::Lng(top_right[1]))
+                        )?;
+                    }
+                    if !(-90.0..=90.0).contains(&bottom_left[0]) {
+                        return Err(bottom_left_point[0]
+                            .as_external_error(BadGeoError::Lat(bottom_left[0])))?;
+                    }
+                    if !(-180.0..=180.0).contains(&bottom_left[1]) {
+                        return Err(bottom_left_point[1]
+                            .as_external_error(BadGeoError::Lng(bottom_left[1])))?;
+                    }
+                    if top_right[0] < bottom_left[0] {
+                        return Err(bottom_left_point[1].as_external_error(
+                            BadGeoError::BoundingBoxTopIsBelowBottom(top_right[0], bottom_left[0]),
+                        ))?;
+                    }
+
+                    // Instead of writing a custom `GeoBoundingBox` filter we're simply going to re-use the range
+                    // filter to create the following filter;
+                    // `_geo.lat {top_right[0]} TO {bottom_left[0]} AND _geo.lng {top_right[1]} TO {bottom_left[1]}`
+                    // As we can see, we need to use a bunch of tokens that don't exist in the original filter,
+                    // thus we're going to create tokens that point to a random span but contain our text.
+
+                    let geo_lat_token = Token::new(
+                        top_right_point[0].original_span(),
+                        Some("_geo.lat".to_string()),
+                    );
+
+                    let condition_lat = FilterCondition::Condition {
+                        fid: geo_lat_token,
+                        op: Condition::Between {
+                            from: bottom_left_point[0].clone(),
+                            to: top_right_point[0].clone(),
+                        },
+                    };
+
+                    let selected_lat = Filter::from(condition_lat).inner_evaluate(
+                        rtxn,
+                        index,
+                        fields_ids_map,
+                        filterable_fields,
+                        metrics,
+                        string_lookups,
+                        document_id_field,
+                        in_list_order,
+                    )?;
+
+                    let geo_lng_token = Token::new(
+                        top_right_point[1].original_span(),
+                        Some("_geo.lng".to_string()),
+                    );
+                    let selected_lng = if top_right[1] < bottom_left[1] {
+                        // In this case the bounding box is wrapping around the earth (going from 180 to -180).
+                        // We need to update the lng part of the filter from;
+                        // `_geo.lng {top_right[1]} TO {bottom_left[1]}` to
+                        // `_geo.lng {bottom_left[1]} TO 180 AND _geo.lng -180 TO {top_right[1]}`
+
+                        let min_lng_token = Token::new(
+                            top_right_point[1].original_span(),
+                            Some("-180.0".to_string()),
+                        );
+                        let max_lng_token = Token::new(
+                            top_right_point[1].original_span(),
+                            Some("180.0".to_string()),
+                        );
+
+                        let condition_left = FilterCondition::Condition {
+                            fid: geo_lng_token.clone(),
+                            op: Condition::Between {
+                                from: bottom_left_point[1].clone(),
+                                to: max_lng_token,
+                            },
+                        };
+                        let left = Filter::from(condition_left).inner_evaluate(
+                            rtxn,
+                            index,
+                            fields_ids_map,
+                            filterable_fields,
+                            metrics,
+                            string_lookups,
+                            document_id_field,
+                            in_list_order,
+                        )?;
+
+                        let condition_right = FilterCondition::Condition {
+                            fid: geo_lng_token,
+                            op: Condition::Between {
+                                from: min_lng_token,
+                                to: top_right_point[1].clone(),
+                            },
+                        };
+                        let right = Filter::from(condition_right).inner_evaluate(
+                            rtxn,
+                            index,
+                            fields_ids_map,
+                            filterable_fields,
+                            metrics,
+                            string_lookups,
+                            document_id_field,
+                            in_list_order,
+                        )?;
+
+                        left | right
+                    } else {
+                        let condition_lng = FilterCondition::Condition {
+                            fid: geo_lng_token,
+                            op: Condition::Between {
+                                from: bottom_left_point[1].clone(),
+                                to: top_right_point[1].clone(),
+                            },
+                        };
+                        Filter::from(condition_lng).inner_evaluate(
+                            rtxn,
+                            index,
+                            fields_ids_map,
+                            filterable_fields,
+                            metrics,
+                            string_lookups,
+                            document_id_field,
+                            in_list_order,
+                        )?
+                    };
+
+                    Ok(selected_lat & selected_lng)
+                } else {
+                    Err(top_right_point[0].as_external_error(
+                        FilterError::AttributeNotFilterable {
+                            attribute: "_geo",
+                            filterable_fields: filterable_fields.clone(),
+                        },
+                    ))?
+                }
+            }
+        }
+    }
+}
+
+fn parse_document_id(token: &Token) -> Result<DocumentId> {
+    token.value().parse().map_err(|_| {
+        token.as_external_error(FilterError::InvalidDocumentId { value: token.value() }).into()
+    })
+}
+
+/// A `LIKE` pattern, where `%` matches any sequence of characters, `_` matches exactly
+/// one character and `\` makes the next character literal.
+struct LikePattern {
+    elements: Vec<LikeElement>,
+}
+
+enum LikeElement {
+    Char(char),
+    AnyChar,
+    AnyChars,
+}
+
+impl LikePattern {
+    fn new(pattern: &str) -> Self {
+        let mut elements = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            elements.push(match c {
+                '%' => LikeElement::AnyChars,
+                '_' => LikeElement::AnyChar,
+                // a trailing backslash is kept as is
+                '\\' => LikeElement::Char(chars.next().unwrap_or('\\')),
+                c => LikeElement::Char(c),
+            });
+        }
+        LikePattern { elements }
+    }
+
+    /// The literal characters every matching value starts with.
+    fn prefix(&self) -> String {
+        self.elements
+            .iter()
+            .map_while(|element| match element {
+                LikeElement::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let value: Vec<char> = value.chars().collect();
+        let (mut e, mut v) = (0, 0);
+        // the position of the last `%` and of the value it was matched against,
+        // to make it match one more character when the rest of the pattern doesn't match
+        let mut backtrack = None;
+
+        while v < value.len() {
+            match self.elements.get(e) {
+                Some(LikeElement::AnyChars) => {
+                    backtrack = Some((e, v));
+                    e += 1;
+                }
+                Some(LikeElement::AnyChar) => (e, v) = (e + 1, v + 1),
+                Some(LikeElement::Char(c)) if *c == value[v] => (e, v) = (e + 1, v + 1),
+                _ => match backtrack {
+                    Some((any, start)) => {
+                        backtrack = Some((any, start + 1));
+                        (e, v) = (any + 1, start + 1);
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.elements[e..].iter().all(|element| matches!(element, LikeElement::AnyChars))
+    }
+}
+
+/// Replaces the equalities with the numbers of the fields of `tolerances` by the intervals of
+/// the numbers within their tolerance.
+fn with_equality_tolerances<'a>(
+    condition: &FilterCondition<'a>,
+    tolerances: &HashMap<String, f64>,
+) -> FilterCondition<'a> {
+    let interval = |token: &Token<'a>, tolerance: f64| {
+        if token.is_placeholder()
+            || token.is_quoted()
+            || token.expression().is_some()
+            || token.annotation() == Some(TypeAnnotation::String)
+        {
+            return None;
+        }
+        let number = token.parse_finite_float().ok()?;
+        let bound = |number: f64| Token::new(token.original_span(), Some(number.to_string()));
+        Some(Condition::Interval {
+            from: Included(bound(number - tolerance)),
+            to: Included(bound(number + tolerance)),
+        })
+    };
+    let recurse = |condition: &FilterCondition<'a>| with_equality_tolerances(condition, tolerances);
+
+    match condition {
+        FilterCondition::Not(condition) => FilterCondition::Not(Box::new(recurse(condition))),
+        FilterCondition::Or(conditions) => {
+            FilterCondition::Or(conditions.iter().map(recurse).collect())
+        }
+        FilterCondition::And(conditions) => {
+            FilterCondition::And(conditions.iter().map(recurse).collect())
+        }
+        FilterCondition::Condition { fid, op: Condition::Equal(token) } => {
+            match tolerances.get(fid.value()).and_then(|tolerance| interval(token, *tolerance)) {
+                Some(op) => FilterCondition::Condition { fid: fid.clone(), op },
+                None => condition.clone(),
+            }
+        }
+        FilterCondition::Condition { fid, op: Condition::NotEqual(token) } => {
+            match tolerances.get(fid.value()).and_then(|tolerance| interval(token, *tolerance)) {
+                Some(op) => FilterCondition::Not(Box::new(FilterCondition::Condition {
+                    fid: fid.clone(),
+                    op,
+                })),
+                None => condition.clone(),
+            }
+        }
+        FilterCondition::In { fid, els } => match tolerances.get(fid.value()) {
+            Some(tolerance) => FilterCondition::Or(
+                els.iter()
+                    .map(|el| match interval(el, *tolerance) {
+                        Some(op) => FilterCondition::Condition { fid: fid.clone(), op },
+                        None => FilterCondition::Condition {
+                            fid: fid.clone(),
+                            op: Condition::Equal(el.clone()),
+                        },
+                    })
+                    .collect(),
+            ),
+            None => condition.clone(),
+        },
+        condition => condition.clone(),
+    }
+}
+
+/// Returns the UTC timestamps of the start and the end of the day of a `YYYY-MM-DD` date in the
+/// timezone at `offset`, `None` if the token isn't a date.
+fn day_range(token: &Token, offset: UtcOffset) -> Option<(i64, i64)> {
+    if token.annotation().is_some() {
+        return None;
+    }
+    let date = Date::parse(token.value(), format_description!("[year]-[month]-[day]")).ok()?;
+    let start = date.midnight().assume_offset(offset).unix_timestamp();
+    Some((start, start + 24 * 60 * 60))
+}
+
+/// Replaces the comparisons of `field` with dates by the ranges of timestamps of their days,
+/// see [`Filter::date_field`].
+fn expand_dates<'a>(
+    condition: FilterCondition<'a>,
+    field: &str,
+    offset: UtcOffset,
+) -> FilterCondition<'a> {
+    let timestamp = |token: &Token<'a>, timestamp: i64| {
+        Token::new(token.original_span(), Some(timestamp.to_string()))
+    };
+    let day = |token: &Token<'a>| {
+        day_range(token, offset)
+            .map(|(start, end)| (timestamp(token, start), timestamp(token, end)))
+    };
+    let interval = |fid: &Token<'a>, from, to| FilterCondition::Condition {
+        fid: fid.clone(),
+        op: Condition::Interval { from, to },
+    };
+
+    match condition {
+        FilterCondition::Not(condition) => {
+            FilterCondition::Not(Box::new(expand_dates(*condition, field, offset)))
+        }
+        FilterCondition::Or(conditions) => FilterCondition::Or(
+            conditions.into_iter().map(|c| expand_dates(c, field, offset)).collect(),
+        ),
+        FilterCondition::And(conditions) => FilterCondition::And(
+            conditions.into_iter().map(|c| expand_dates(c, field, offset)).collect(),
+        ),
+        FilterCondition::Condition { fid, op }
+            if fid.value() == field && fid.function().is_none() =>
+        {
+            let expanded = match &op {
+                Condition::Equal(date) => {
+                    day(date).map(|(start, end)| interval(&fid, Included(start), Excluded(end)))
+                }
+                Condition::NotEqual(date) => day(date).map(|(start, end)| {
+                    FilterCondition::Not(Box::new(interval(&fid, Included(start), Excluded(end))))
+                }),
+                Condition::GreaterThan(date) => {
+                    day(date).map(|(_, end)| interval(&fid, Included(end), Bound::Unbounded))
+                }
+                Condition::GreaterThanOrEqual(date) => {
+                    day(date).map(|(start, _)| interval(&fid, Included(start), Bound::Unbounded))
+                }
+                Condition::LowerThan(date) => {
+                    day(date).map(|(start, _)| interval(&fid, Bound::Unbounded, Excluded(start)))
+                }
+                Condition::LowerThanOrEqual(date) => {
+                    day(date).map(|(_, end)| interval(&fid, Bound::Unbounded, Excluded(end)))
+                }
+                Condition::Between { from, to } => match (day(from), day(to)) {
+                    (Some((start, _)), Some((_, end))) => {
+                        Some(interval(&fid, Included(start), Excluded(end)))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            expanded.unwrap_or(FilterCondition::Condition { fid, op })
+        }
+        FilterCondition::In { fid, els } if fid.value() == field && fid.function().is_none() => {
+            let days: Option<Vec<_>> = els.iter().map(day).collect();
+            match days {
+                Some(days) => FilterCondition::Or(
+                    days.into_iter()
+                        .map(|(start, end)| interval(&fid, Included(start), Excluded(end)))
+                        .collect(),
+                ),
+                None => FilterCondition::In { fid, els },
+            }
+        }
+        condition => condition,
+    }
+}
+
+/// Moves the operands of every `OR` for which `is_cached` returns `true` first, see
+/// [`Filter::cached_branches_first`].
+fn cached_branches_first<'a>(
+    condition: FilterCondition<'a>,
+    is_cached: &mut dyn FnMut(&FilterCondition<'a>) -> bool,
+) -> FilterCondition<'a> {
+    match condition {
+        FilterCondition::Not(condition) => {
+            FilterCondition::Not(Box::new(cached_branches_first(*condition, is_cached)))
+        }
+        FilterCondition::And(conditions) => FilterCondition::And(
+            conditions.into_iter().map(|c| cached_branches_first(c, is_cached)).collect(),
+        ),
+        FilterCondition::Or(conditions) => {
+            let (cached, others): (Vec<_>, Vec<_>) = conditions
+                .into_iter()
+                .map(|c| cached_branches_first(c, is_cached))
+                .partition(|c| is_cached(c));
+            FilterCondition::Or(cached.into_iter().chain(others).collect())
+        }
+        condition => condition,
+    }
+}
+
+/// Replaces the conditions on the fields that are not filterable by `FALSE`.
+fn without_unfilterable_fields<'a>(
+    condition: &FilterCondition<'a>,
+    filterable_fields: &HashSet<String>,
+    document_id_field: Option<&str>,
+) -> FilterCondition<'a> {
+    let is_filterable = |fid: &Token| {
+        Some(fid.value()) == document_id_field || crate::is_faceted(fid.value(), filterable_fields)
+    };
+    let recurse = |condition: &FilterCondition<'a>| {
+        without_unfilterable_fields(condition, filterable_fields, document_id_field)
+    };
+
+    match condition {
+        FilterCondition::Not(condition) => FilterCondition::Not(Box::new(recurse(condition))),
+        FilterCondition::Or(conditions) => {
+            FilterCondition::Or(conditions.iter().map(recurse).collect())
+        }
+        FilterCondition::And(conditions) => {
+            FilterCondition::And(conditions.iter().map(recurse).collect())
+        }
+        FilterCondition::Condition { fid, .. }
+        | FilterCondition::In { fid, .. }
+        | FilterCondition::HasAll { fid, .. }
+            if !is_filterable(fid) =>
+        {
+            FilterCondition::Const(false)
+        }
+        FilterCondition::GeoLowerThan { .. } | FilterCondition::GeoBoundingBox { .. }
+            if !filterable_fields.contains("_geo") =>
+        {
+            FilterCondition::Const(false)
+        }
+        condition => condition.clone(),
+    }
+}
+
+/// Parses the value of a bound of an interval, keeping its inclusivity.
+fn map_bound<T>(bound: &Bound<Token>, parse: impl Fn(&Token) -> Result<T>) -> Result<Bound<T>> {
+    Ok(match bound {
+        Included(token) => Included(parse(token)?),
+        Excluded(token) => Excluded(parse(token)?),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
+fn parse_step_range_integer(token: &Token) -> Result<i64> {
+    token.value().parse().map_err(|_| {
+        token.as_external_error(FilterError::InvalidStepRange { value: token.value() }).into()
+    })
+}
+
+/// Returns the start and the excluded end of a `FROM` window, e.g. `(100, 150)` for
+/// `FROM 100 LIMIT 50`.
+fn window_bounds(from: &Token, limit: &Token) -> Result<(i64, i64)> {
+    let invalid = |token: &Token| -> Error {
+        token.as_external_error(FilterError::InvalidWindow { value: token.value() }).into()
+    };
+    let start: i64 = from.value().parse().map_err(|_| invalid(from))?;
+    match limit.value().parse::<i64>() {
+        Ok(length) if length >= 0 => {
+            Ok((start, start.checked_add(length).ok_or_else(|| invalid(limit))?))
+        }
+        _ => Err(invalid(limit)),
+    }
+}
+
+/// Splits the elements of an `IN` list into runs of contiguous integers, e.g. `1, 2, 3`,
+/// and the remaining elements that must be evaluated one by one.
+fn integer_runs<'a, 'b>(els: &'b [Token<'a>]) -> (Vec<RangeInclusive<i64>>, Vec<&'b Token<'a>>) {
+    let mut integers = Vec::new();
+    let mut others = Vec::new();
+    for el in els {
+        match el.value().parse::<i64>() {
+            Ok(integer) if el.annotation().is_none() && !el.is_quoted() => {
+                integers.push((integer, el))
+            }
+            _ => others.push(el),
+        }
+    }
+    integers.sort_unstable_by_key(|(integer, _)| *integer);
+    integers.dedup_by_key(|(integer, _)| *integer);
+
+    let mut runs = Vec::new();
+    let mut integers = integers.into_iter().peekable();
+    while let Some((start, el)) = integers.next() {
+        let mut end = start;
+        while integers.next_if(|(next, _)| Some(*next) == end.checked_add(1)).is_some() {
+            end += 1;
+        }
+        if start == end {
+            others.push(el);
+        } else {
+            runs.push(start..=end);
+        }
+    }
+
+    (runs, others)
+}
+
+/// Returns the condition where the attributes written in another Unicode form than the name
+/// of the field in the index are renamed to this name, or `None` if there is none.
+///
+/// The names are compared in the NFC form, the composed one, e.g. the `é` of an attribute
+/// written as an `e` followed by a combining acute accent is the same as the single `é` of the
+/// field name. The attributes already written like their field are kept as they are, and the
+/// names of the fields are only normalized when an attribute isn't found as it is written.
+fn with_stored_attribute_names<'a>(
+    condition: &FilterCondition<'a>,
+    fields_ids_map: &FieldsIdsMap,
+) -> Option<FilterCondition<'a>> {
+    let mut condition = condition.clone();
+    let mut stored_names: Option<HashMap<String, &str>> = None;
+    let mut renamed = false;
+    for attribute in condition.attributes_mut() {
+        if fields_ids_map.id(attribute.value()).is_some() {
+            continue;
+        }
+        let stored_names = stored_names.get_or_insert_with(|| {
+            fields_ids_map.names().map(|name| (name.nfc().collect(), name)).collect()
+        });
+        let normalized: String = attribute.value().nfc().collect();
+        if let Some(name) = stored_names.get(&normalized) {
+            attribute.rename(name.to_string());
+            renamed = true;
+        }
+    }
+    renamed.then_some(condition)
+}
+
+/// Returns the condition where the `!=` comparisons only match the documents containing their
+/// attribute, see [`Filter::not_equal_present_only`]. The document id field is left as it is,
+/// every document has an id.
+fn with_present_not_equals<'a>(
+    condition: FilterCondition<'a>,
+    document_id_field: Option<&str>,
+) -> FilterCondition<'a> {
+    let recurse = |condition| with_present_not_equals(condition, document_id_field);
+    match condition {
+        FilterCondition::Not(condition) => FilterCondition::Not(Box::new(recurse(*condition))),
+        FilterCondition::Or(conditions) => {
+            FilterCondition::Or(conditions.into_iter().map(recurse).collect())
+        }
+        FilterCondition::And(conditions) => {
+            FilterCondition::And(conditions.into_iter().map(recurse).collect())
+        }
+        FilterCondition::Condition { fid, op: Condition::NotEqual(value) }
+            if Some(fid.value()) != document_id_field =>
+        {
+            FilterCondition::Condition { fid, op: Condition::Equal(value) }.negate()
+        }
+        condition => condition,
+    }
+}
+
+/// Returns `true` if the condition contains an `EXISTS` on the sub-fields of an object,
+/// e.g. `author.* EXISTS`.
+fn has_exists_wildcard(condition: &FilterCondition) -> bool {
+    match condition {
+        FilterCondition::Not(condition) => has_exists_wildcard(condition),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().any(has_exists_wildcard)
+        }
+        FilterCondition::Condition { fid, op: Condition::Exists } => fid.value().ends_with(".*"),
+        _ => false,
+    }
+}
+
+/// Replaces the `EXISTS` on the sub-fields of an object, e.g. `author.* EXISTS`, by the `OR`
+/// of the `EXISTS` on every field of the index starting with the object name and a dot.
+///
+/// Fails if no field of the index is a sub-field of the object.
+fn expand_exists_wildcards<'a>(
+    condition: FilterCondition<'a>,
+    fields_ids_map: &FieldsIdsMap,
+) -> Result<FilterCondition<'a>> {
+    let expand = |condition| expand_exists_wildcards(condition, fields_ids_map);
+    Ok(match condition {
+        FilterCondition::Not(condition) => FilterCondition::Not(Box::new(expand(*condition)?)),
+        FilterCondition::Or(conditions) => {
+            FilterCondition::Or(conditions.into_iter().map(expand).collect::<Result<_>>()?)
+        }
+        FilterCondition::And(conditions) => {
+            FilterCondition::And(conditions.into_iter().map(expand).collect::<Result<_>>()?)
+        }
+        FilterCondition::Condition { fid, op: Condition::Exists }
+            if fid.value().ends_with(".*") =>
+        {
+            let wildcard = fid.value();
+            // the prefix keeps the dot, `author.*` doesn't match `author` or `authors`
+            let prefix = &wildcard[..wildcard.len() - 1];
+            let conditions: Vec<_> = fields_ids_map
+                .iter()
+                .filter(|(_, name)| name.starts_with(prefix) && *name != wildcard)
+                .map(|(_, name)| {
+                    let mut fid = fid.clone();
+                    fid.rename(name.to_string());
+                    FilterCondition::Condition { fid, op: Condition::Exists }
+                })
+                .collect();
+            if conditions.is_empty() {
+                return Err(
+                    fid.as_external_error(FilterError::WildcardMatchesNoAttribute { wildcard })
+                )?;
+            }
+            FilterCondition::Or(conditions)
+        }
+        condition => condition,
+    })
+}
+
+/// Pushes the leaves of the condition read by [`Filter::evaluate_prefetched`] and
+/// [`Filter::evaluate_with_overlay`], in the order [`combine_prefetched`] consumes them.
+fn collect_leaves<'c, 'a>(
+    condition: &'c FilterCondition<'a>,
+    leaves: &mut Vec<&'c FilterCondition<'a>>,
+) {
+    match condition {
+        FilterCondition::Not(condition) => collect_leaves(condition, leaves),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().for_each(|condition| collect_leaves(condition, leaves))
+        }
+        FilterCondition::Const(_) => (),
+        leaf => leaves.push(leaf),
+    }
+}
+
+/// Combines the documents ids read for the leaves of the condition, in the order they were
+/// pushed by [`collect_leaves`].
+fn combine_prefetched(
+    condition: &FilterCondition,
+    prefetched: &mut impl Iterator<Item = RoaringBitmap>,
+    all_ids: &RoaringBitmap,
+) -> RoaringBitmap {
+    match condition {
+        FilterCondition::Not(condition) => {
+            all_ids - combine_prefetched(condition, prefetched, all_ids)
+        }
+        FilterCondition::Or(conditions) => conditions
+            .iter()
+            .map(|condition| combine_prefetched(condition, prefetched, all_ids))
+            .fold(RoaringBitmap::new(), |acc, docids| acc | docids),
+        FilterCondition::And(conditions) => {
+            let mut conditions = conditions.iter();
+            match conditions.next() {
+                Some(first) => {
+                    let mut docids = combine_prefetched(first, prefetched, all_ids);
+                    // the leaves of every operand must be consumed, even once nothing matches
+                    for condition in conditions {
+                        docids &= combine_prefetched(condition, prefetched, all_ids);
+                    }
+                    docids
+                }
+                None => RoaringBitmap::new(),
+            }
+        }
+        FilterCondition::Const(true) => all_ids.clone(),
+        FilterCondition::Const(false) => RoaringBitmap::new(),
+        _ => prefetched.next().unwrap_or_default(),
+    }
+}
+
+/// Returns `true` if the two values of a `HAS_ALL` list match the same documents, e.g. `Rust`
+/// and `rust`.
+fn same_has_all_value(a: &Token, b: &Token) -> bool {
+    a.is_quoted() == b.is_quoted()
+        && a.annotation() == b.annotation()
+        && crate::normalize_facet(a.value()) == crate::normalize_facet(b.value())
+}
+
+/// Returns the elements of an `IN` list in the order they must be evaluated.
+fn ordered_in_list<'a, 'b>(els: &'b [Token<'a>], order: InListOrder) -> Vec<&'b Token<'a>> {
+    let mut els: Vec<_> = els.iter().collect();
+    match order {
+        InListOrder::Any | InListOrder::Given => (),
+        InListOrder::Ascending => els.sort_by(|a, b| compare_in_list_values(a, b)),
+        InListOrder::Descending => els.sort_by(|a, b| compare_in_list_values(b, a)),
+    }
+    els
+}
+
+/// Compares two elements of an `IN` list, numerically when both are numbers.
+fn compare_in_list_values(left: &Token, right: &Token) -> Ordering {
+    match (left.value().parse::<f64>(), right.value().parse::<f64>()) {
+        (Ok(left), Ok(right)) => left.total_cmp(&right),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => left.value().cmp(right.value()),
+    }
+}
+
+/// Returns the type of the values indexed for this field.
+///
+/// `None` is returned if the field contains both strings and numbers, or no value at all.
+fn indexed_facet_type(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    field_id: FieldId,
+) -> Result<Option<FacetType>> {
+    let numbers_db =
+        index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+    let strings_db =
+        index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+
+    let has_numbers = get_first_facet_value::<BytesRefCodec>(rtxn, numbers_db, field_id)?.is_some();
+    let has_strings = get_first_facet_value::<BytesRefCodec>(rtxn, strings_db, field_id)?.is_some();
+
+    Ok(match (has_numbers, has_strings) {
+        (true, false) => Some(FacetType::Number),
+        (false, true) => Some(FacetType::String),
+        _ => None,
+    })
+}
+
+/// Returns the attribute of a comparison and the values it is compared with, or `None` if the
+/// condition doesn't compare an attribute with numbers, e.g. an `AND` or `HAS_ALL`.
+fn compared_numbers<'f, 'a>(
+    condition: &'f FilterCondition<'a>,
+) -> Option<(&'f Token<'a>, Vec<&'f Token<'a>>)> {
+    match condition {
+        FilterCondition::Condition { fid, op } => {
+            let values = match op {
+                Condition::GreaterThan(value)
+                | Condition::GreaterThanOrEqual(value)
+                | Condition::Equal(value)
+                | Condition::NotEqual(value)
+                | Condition::LowerThan(value)
+                | Condition::LowerThanOrEqual(value) => vec![value],
+                Condition::Between { from, to } | Condition::BetweenStep { from, to, .. } => {
+                    vec![from, to]
+                }
+                Condition::Interval { from, to } => [from, to]
+                    .into_iter()
+                    .filter_map(|bound| match bound {
+                        Included(value) | Excluded(value) => Some(value),
+                        Bound::Unbounded => None,
+                    })
+                    .collect(),
+                _ => return None,
+            };
+            Some((fid, values))
+        }
+        FilterCondition::In { fid, els } => Some((fid, els.iter().collect())),
+        _ => None,
+    }
+}
+
+/// Returns whether one of the numbers of this field isn't an integer,
+/// or `None` if the field doesn't contain numbers.
+fn facet_contains_floats(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    field_id: FieldId,
+) -> Result<Option<bool>> {
+    let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+
+    let mut contains_numbers = false;
+    for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+        let (key, _) = result?;
+        if key.left_bound.fract() != 0.0 {
+            return Ok(Some(true));
+        }
+        contains_numbers = true;
+    }
+
+    Ok(contains_numbers.then_some(false))
+}
+
+/// Estimates the number of documents matched by the conditions of a filter.
+struct SelectivityEstimator<'t> {
+    rtxn: &'t heed::RoTxn<'t>,
+    index: &'t Index,
+    filterable_fields: HashSet<String>,
+    fields_ids_map: FieldsIdsMap,
+    number_of_documents: u64,
+}
+
+impl<'t> SelectivityEstimator<'t> {
+    fn new(rtxn: &'t heed::RoTxn<'t>, index: &'t Index) -> Result<Self> {
+        Ok(Self {
+            rtxn,
+            index,
+            filterable_fields: index.filterable_fields(rtxn)?,
+            fields_ids_map: index.fields_ids_map(rtxn)?,
+            number_of_documents: index.number_of_documents(rtxn)?,
+        })
+    }
+
+    /// Reorders the operands of the `AND`s by increasing estimate and returns the estimate.
+    fn optimize(&self, condition: &mut FilterCondition) -> Result<u64> {
+        match condition {
+            FilterCondition::Not(condition) => {
+                Ok(self.number_of_documents.saturating_sub(self.optimize(condition)?))
+            }
+            FilterCondition::Or(conditions) => {
+                let mut estimate = 0u64;
+                for condition in conditions {
+                    estimate = estimate.saturating_add(self.optimize(condition)?);
+                }
+                Ok(estimate.min(self.number_of_documents))
+            }
+            FilterCondition::And(conditions) => {
+                let mut estimated = Vec::with_capacity(conditions.len());
+                for mut condition in conditions.drain(..) {
+                    estimated.push((self.optimize(&mut condition)?, condition));
+                }
+                // the sort is stable, operands with the same estimate keep their order
+                estimated.sort_by_key(|(estimate, _)| *estimate);
+                let estimate = estimated.first().map_or(0, |(estimate, _)| *estimate);
+                conditions.extend(estimated.into_iter().map(|(_, condition)| condition));
+                Ok(estimate)
+            }
+            condition => self.estimate(condition),
+        }
+    }
+
+    fn estimate(&self, condition: &FilterCondition) -> Result<u64> {
+        let total = self.number_of_documents;
+        match condition {
+            FilterCondition::Not(condition) => Ok(total.saturating_sub(self.estimate(condition)?)),
+            FilterCondition::Or(conditions) => {
+                let mut estimate = 0u64;
+                for condition in conditions {
+                    estimate = estimate.saturating_add(self.estimate(condition)?);
+                }
+                Ok(estimate.min(total))
+            }
+            FilterCondition::And(conditions) => {
+                let mut estimate = if conditions.is_empty() { 0 } else { total };
+                for condition in conditions {
+                    estimate = estimate.min(self.estimate(condition)?);
+                }
+                Ok(estimate)
+            }
+            FilterCondition::Const(true) => Ok(total),
+            FilterCondition::Const(false) => Ok(0),
+            FilterCondition::GeoLowerThan { .. } | FilterCondition::GeoBoundingBox { .. } => {
+                Ok(self.index.geo_faceted_documents_ids(self.rtxn)?.len())
+            }
+            FilterCondition::In { fid, els } => match self.field_id(fid) {
+                Some(field_id) => {
+                    let mut estimate = 0u64;
+                    for el in els {
+                        estimate = estimate.saturating_add(self.equal_estimate(field_id, el)?);
+                    }
+                    Ok(estimate.min(total))
+                }
+                None => Ok(0),
+            },
+            FilterCondition::HasAll { fid, els } => match self.field_id(fid) {
+                Some(field_id) => {
+                    let mut estimate = if els.is_empty() { 0 } else { total };
+                    for el in els {
+                        estimate = estimate.min(self.equal_estimate(field_id, el)?);
+                    }
+                    Ok(estimate)
+                }
+                None => Ok(0),
+            },
+            FilterCondition::Condition { fid, op } => {
+                let field_id = match self.field_id(fid) {
+                    Some(field_id) => field_id,
+                    None => return Ok(0),
+                };
+                let (rtxn, index) = (self.rtxn, self.index);
+                Ok(match op {
+                    Condition::Equal(val) => self.equal_estimate(field_id, val)?,
+                    Condition::NotEqual(val) => {
+                        total.saturating_sub(self.equal_estimate(field_id, val)?)
+                    }
+                    Condition::Null => index.null_faceted_documents_ids(rtxn, field_id)?.len(),
+                    Condition::Empty => index.empty_faceted_documents_ids(rtxn, field_id)?.len(),
+                    _ => index.exists_faceted_documents_ids(rtxn, field_id)?.len(),
+                })
+            }
+        }
+    }
+
+    /// The number of documents with exactly this string or number value.
+    fn equal_estimate(&self, field_id: FieldId, value: &Token) -> Result<u64> {
+        let normalized = crate::normalize_facet(value.value());
+        let string_key = FacetGroupKey { field_id, level: 0, left_bound: normalized.as_str() };
+        let mut estimate = self
+            .index
+            .facet_id_string_docids
+            .get(self.rtxn, &string_key)?
+            .map_or(0, |group| group.bitmap.len());
+        if let (false, Ok(number)) = (value.is_quoted(), value.parse_finite_float()) {
+            let number_key = FacetGroupKey { field_id, level: 0, left_bound: number };
+            estimate += self
+                .index
+                .facet_id_f64_docids
+                .get(self.rtxn, &number_key)?
+                .map_or(0, |group| group.bitmap.len());
+        }
+        Ok(estimate)
+    }
+
+    /// The id of a filterable field, conditions on other fields are estimated to match nothing.
+    fn field_id(&self, fid: &Token) -> Option<FieldId> {
+        if crate::is_faceted(fid.value(), &self.filterable_fields) {
+            self.fields_ids_map.id(fid.value())
+        } else {
+            None
+        }
+    }
+}
+
+/// A chain of filters combined with `AND` and `OR`, e.g. `a.lazy().and(b).or(c)` is `(a AND b) OR c`.
+///
+/// This is only sugar over [`Filter`], not a new evaluation engine: the chain builds a single
+/// filter condition, without reading the database, which is evaluated like any other filter by
+/// [`LazyFilter::collect`]. The whole chain is therefore known at evaluation time, e.g. an `AND`
+/// stops evaluating its operands as soon as no candidate is left.
+///
+/// The `check_fields`, `strict_types` and `document_id_field` options of the first filter
+/// apply to the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyFilter<'a> {
+    filter: Filter<'a>,
+}
+
+impl<'a> LazyFilter<'a> {
+    /// Keeps the documents matching both the chain so far and `other`.
+    pub fn and(self, other: Filter<'a>) -> Self {
+        self.combine(other, FilterCondition::And, |condition| match condition {
+            FilterCondition::And(conditions) => Ok(conditions),
+            condition => Err(condition),
+        })
+    }
+
+    /// Keeps the documents matching either the chain so far or `other`.
+    pub fn or(self, other: Filter<'a>) -> Self {
+        self.combine(other, FilterCondition::Or, |condition| match condition {
+            FilterCondition::Or(conditions) => Ok(conditions),
+            condition => Err(condition),
+        })
+    }
+
+    /// Appends `other` to the chain, extending the top level condition when it is already
+    /// of the same kind to keep the tree flat.
+    fn combine(
+        mut self,
+        other: Filter<'a>,
+        wrap: fn(Vec<FilterCondition<'a>>) -> FilterCondition<'a>,
+        unwrap: fn(
+            FilterCondition<'a>,
+        ) -> std::result::Result<Vec<FilterCondition<'a>>, FilterCondition<'a>>,
+    ) -> Self {
+        let condition = mem::replace(&mut self.filter.condition, FilterCondition::Const(true));
+        let conditions = match unwrap(condition) {
+            Ok(mut conditions) => {
+                conditions.push(other.condition);
+                conditions
+            }
+            Err(condition) => vec![condition, other.condition],
+        };
+        self.filter.condition = wrap(conditions);
+        self
+    }
+
+    /// Evaluates the whole chain and returns the matching documents.
+    pub fn collect(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        self.filter.evaluate(rtxn, index)
+    }
+
+    /// Returns the filter equivalent to the chain.
+    pub fn into_filter(self) -> Filter<'a> {
+        self.filter
+    }
+}
+
+/// The order in which the elements of an `IN` list are evaluated, see [`Filter::in_list_order`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InListOrder {
+    /// Any order, letting the runs of contiguous integers like `1, 2, 3` be read as a range.
+    #[default]
+    Any,
+    /// The order in which the elements are written.
+    Given,
+    /// The ascending order of the values, the numbers before the strings.
+    Ascending,
+    /// The descending order of the values, the strings before the numbers.
+    Descending,
+}
+
+/// How the values listed more than once in a `HAS_ALL` list are handled, see
+/// [`Filter::has_all_duplicates`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HasAllDuplicates {
+    /// The duplicates are ignored, a document must only contain each distinct value once.
+    #[default]
+    Ignore,
+    /// The filter is rejected, e.g. to report a mistake in a list built by a UI.
+    Reject,
+}
+
+/// The type of the numbers of a field, see [`Filter::number_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberType {
+    Integer,
+    Float,
+}
+
+/// The way the users write their numbers, used to word the errors about the numbers of a filter
+/// that can't be parsed, see [`Filter::from_str_with_locale`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    decimal_separator: char,
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        NumberLocale { decimal_separator: '.' }
+    }
+}
+
+impl NumberLocale {
+    /// The locale of the users separating the integer and the fractional parts of their
+    /// numbers with `decimal_separator`, e.g. `,` for `1,5`.
+    pub fn new(decimal_separator: char) -> Self {
+        NumberLocale { decimal_separator }
+    }
+
+    /// Rewrites the message of an error about a number that can't be parsed to show how a
+    /// number written in this locale is written in a filter, keeping the position of the number.
+    /// The other errors, and all of them with the default locale, are returned unchanged.
+    fn localize_error(&self, error: Error) -> Error {
+        let message = match error {
+            Error::UserError(UserError::InvalidFilter(message)) => message,
+            error => return error,
+        };
+        // the messages of the float parsing errors of the standard library
+        let parse_errors = ["", "-"].map(|number| number.parse::<f64>().unwrap_err().to_string());
+        match message.split_once('\n') {
+            Some((first_line, position))
+                if self.decimal_separator != '.'
+                    && parse_errors.iter().any(|e| e == first_line) =>
+            {
+                let separator = self.decimal_separator;
+                Error::UserError(UserError::InvalidFilter(format!(
+                    "{first_line}: the numbers of a filter are written with a `.` as decimal separator, e.g. `1{separator}5` is written `1.5`.\n{position}"
+                )))
+            }
+            _ => Error::UserError(UserError::InvalidFilter(message)),
+        }
+    }
+}
+
+/// The kind of comparison of a [`LeafOperator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafKind {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LowerThan,
+    LowerThanOrEqual,
+    /// `TO`, `TO ... STEP` and the intervals like `[10, 20)`.
+    Range,
+    Null,
+    Empty,
+    Exists,
+    Like,
+    Integer,
+    Fractional,
+    In,
+    HasAll,
+    GeoRadius,
+    GeoBoundingBox,
+}
+
+/// A comparison of a filter, as returned by [`Filter::leaf_operators`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafOperator<'f, 'a> {
+    field: &'f str,
+    field_id: Option<FieldId>,
+    kind: LeafKind,
+    condition: &'f FilterCondition<'a>,
+}
+
+impl<'f, 'a> LeafOperator<'f, 'a> {
+    /// The name of the compared field, `_geo` for the geo filters.
+    pub fn field(&self) -> &'f str {
+        self.field
+    }
+
+    /// The id of the compared field, `None` if the index doesn't know this field.
+    pub fn field_id(&self) -> Option<FieldId> {
+        self.field_id
+    }
+
+    pub fn kind(&self) -> LeafKind {
+        self.kind
+    }
+
+    /// The comparison in its filter form, e.g. `{price} > {10}`.
+    pub fn condition(&self) -> &'f FilterCondition<'a> {
+        self.condition
+    }
+}
+
+/// Why a filter matches no document, as returned by [`Filter::evaluate_explained`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmptyReason<'f, 'a> {
+    /// The index doesn't contain any document.
+    NoDocuments,
+    /// This comparison, or the constant `FALSE`, matches no document.
+    EmptyLeaf(&'f FilterCondition<'a>),
+    /// Each condition of this `AND` matches some documents, but none matches all of them.
+    EmptyIntersection(&'f FilterCondition<'a>),
+    /// This condition, under a `NOT`, matches all the documents.
+    FullNegation(&'f FilterCondition<'a>),
+}
+
+impl<'a> From<FilterCondition<'a>> for Filter<'a> {
+    fn from(fc: FilterCondition<'a>) -> Self {
+        Self {
+            condition: fc,
+            check_fields: false,
+            check_values: false,
+            strict_types: false,
+            cache_string_lookups: false,
+            not_equal_present_only: false,
+            document_id_field: None,
+            in_list_order: InListOrder::default(),
+            has_all_duplicates: HasAllDuplicates::default(),
+            aliases: BTreeMap::new(),
+            max_database_reads: None,
+            boolean_fields: BTreeSet::new(),
+            units: BTreeMap::new(),
+            number_types: BTreeMap::new(),
+            number_locale: NumberLocale::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fmt::Write;
+    use std::io::Cursor;
+    use std::iter::FromIterator;
+    use std::ops::Bound::{self, Excluded, Included};
+
+    use big_s::S;
+    use either::Either;
+    use maplit::hashset;
+    use roaring::RoaringBitmap;
+    use time::UtcOffset;
+
+    use super::integer_runs;
+    use crate::index::tests::TempIndex;
+    use crate::{
+        EmptyReason, FacetColumns, FacetMetrics, FieldId, FieldsIdsMap, Filter, FilterCondition,
+        HasAllDuplicates, InListOrder, LeafKind, NumberLocale, NumberType, ReadTxnPool,
+    };
+
+    #[test]
+    fn empty_db() {
+        let index = TempIndex::new();
+        //Set the filterable fields to be the channel.
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("PrIcE") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+
+        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+    }
+
+    #[test]
+    fn from_array() {
+        // Simple array with Left
+        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple array with Right
+        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
+            .unwrap()
+            .unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped simple quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped simple quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple with parenthesis
+        let condition =
+            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
+        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Test that the facet condition is correctly generated.
+        let condition = Filter::from_array(vec![
+            Either::Right("channel = gotaga"),
+            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
+        ])
+        .unwrap()
+        .unwrap();
+        let expected =
+            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
+                .unwrap()
+                .unwrap();
+        assert_eq!(condition, expected);
+    }
+
+    #[test]
+    fn from_str_with_aliases() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("product_price"), S("name") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "product_price": 5, "name": "kefir" },
+                { "id": 1, "product_price": 15, "name": "intel" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let aliases = HashMap::from([
+            (S("price"), S("product_price")),
+            (S("category"), S("product_category")),
+        ]);
+
+        let filter = Filter::from_str_with_aliases("price > 10", &aliases).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+
+        // the aliases and the attributes can be mixed, the unused dangling alias is ignored
+        let filter = Filter::from_str_with_aliases(
+            "price < 10 OR product_price > 10 OR name = kefir",
+            &aliases,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1]));
+
+        let filter = Filter::from_str_with_aliases("price > 1 AND category = food", &aliases)
+            .unwrap()
+            .unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Alias `category` refers to the attribute `product_category`, which does not exist in this index."
+        ));
+
+        assert!(Filter::from_str_with_aliases("  ", &aliases).unwrap().is_none());
+    }
+
+    #[test]
+    fn from_reader() {
+        let expression = "channel = gotaga AND (timestamp = 44 OR channel != ponce)";
+        let mut buffer = String::new();
+        let filter =
+            Filter::from_reader(Cursor::new(expression.as_bytes()), &mut buffer).unwrap().unwrap();
+        assert_eq!(filter, Filter::from_str(expression).unwrap().unwrap());
+
+        // a large generated filter
+        let expression = (0..1000).map(|i| format!("id = {i}")).collect::<Vec<_>>().join(" OR ");
+        let mut buffer = String::new();
+        let filter =
+            Filter::from_reader(Cursor::new(expression.as_bytes()), &mut buffer).unwrap().unwrap();
+        assert_eq!(filter, Filter::from_str(&expression).unwrap().unwrap());
+
+        let mut buffer = String::new();
+        assert!(Filter::from_reader(Cursor::new(b"  "), &mut buffer).unwrap().is_none());
+
+        let mut buffer = String::new();
+        let error = Filter::from_reader(Cursor::new(b"id = \xff"), &mut buffer);
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn not_filterable() {
+        let index = TempIndex::new();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. This index does not have configured filterable attributes."
+        ));
+
+        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. This index does not have configured filterable attributes."
+        ));
+
+        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `dog` is not filterable. This index does not have configured filterable attributes."
+        ));
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title")]);
+                settings.set_filterable_fields(hashset! { S("title") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
+        ));
+
+        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
+        ));
+
+        let filter = Filter::from_str("name = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `name` is not filterable. Available filterable attributes are: `title`."
+        ));
+    }
+
+    #[test]
+    fn escaped_quote_in_filter_value_2380() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                {
+                    "id": "test_1",
+                    "monitor_diagonal": "27' to 30'"
+                },
+                {
+                    "id": "test_2",
+                    "monitor_diagonal": "27\" to 30\""
+                },
+                {
+                    "id": "test_3",
+                    "monitor_diagonal": "27\" to 30'"
+                },
+            ]))
+            .unwrap();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("monitor_diagonal")));
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let mut search = crate::Search::new(&rtxn, &index);
+        // this filter is copy pasted from #2380 with the exact same espace sequence
+        search.filter(Filter::from_str("monitor_diagonal = '27\" to 30\\''").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27' to 30'" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30\"" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30'" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+    }
+
+    #[test]
+    fn zero_radius() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+              {
+                "id": 1,
+                "name": "Nàpiz' Milano",
+                "address": "Viale Vittorio Veneto, 30, 20124, Milan, Italy",
+                "type": "pizza",
+                "rating": 9,
+                "_geo": {
+                  "lat": 45.4777599,
+                  "lng": 9.1967508
+                }
+              },
+              {
+                "id": 2,
+                "name": "Artico Gelateria Tradizionale",
+                "address": "Via Dogana, 1, 20123 Milan, Italy",
+                "type": "ice cream",
+                "rating": 10,
+                "_geo": {
+                  "lat": 45.4632046,
+                  "lng": 9.1719421
+                }
+              },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let mut search = crate::Search::new(&rtxn, &index);
+
+        search.filter(Filter::from_str("_geoRadius(45.4777599, 9.1967508, 0)").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+    }
+
+    #[test]
+    fn geo_radius_error() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
+                settings.set_filterable_fields(hashset! { S("_geo"), S("price") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().contains(
+                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
+            ),
+            "{}",
+            error.to_string(),
+        );
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+    }
+
+    #[test]
+    fn geo_bounding_box_error() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
+                settings.set_filterable_fields(hashset! { S("_geo"), S("price") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // geoboundingbox top left coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-90.0000001, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // geoboundingbox top left coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([90.0000001, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // geoboundingbox bottom right coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-90.0000001, 150])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [90.0000001, 150])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // geoboundingbox top left coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-10, 180.000001], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox top left coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-10, -180.000001], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-10, -180.000001])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-10, 180.000001])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+    }
+
+    #[test]
+    fn filter_depth() {
+        // generates a big (2 MiB) filter with too much of ORs.
+        let tipic_filter = "account_ids=14361 OR ";
+        let mut filter_string = String::with_capacity(tipic_filter.len() * 14360);
+        for i in 1..=14361 {
+            let _ = write!(&mut filter_string, "account_ids={}", i);
+            if i != 14361 {
+                let _ = write!(&mut filter_string, " OR ");
+            }
+        }
+
+        // Note: the filter used to be rejected for being too deep, but that is
+        // no longer the case
+        let filter = Filter::from_str(&filter_string).unwrap();
+        assert!(filter.is_some());
+    }
+
+    #[test]
+    fn empty_filter() {
+        let option = Filter::from_str("     ").unwrap();
+        assert_eq!(option, None);
+    }
+
+    #[test]
+    fn non_finite_float() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("price")]); // to keep the fields order
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                {
+                    "id": "test_1",
+                    "price": "inf"
+                },
+                {
+                    "id": "test_2",
+                    "price": "2000"
+                },
+                {
+                    "id": "test_3",
+                    "price": "infinity"
+                },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("price = inf").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(0));
+        let filter = Filter::from_str("price < inf").unwrap().unwrap();
+        assert!(matches!(
+            filter.evaluate(&rtxn, &index),
+            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
+        ));
+
+        let filter = Filter::from_str("price = NaN").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.is_empty());
+        let filter = Filter::from_str("price < NaN").unwrap().unwrap();
+        assert!(matches!(
+            filter.evaluate(&rtxn, &index),
+            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
+        ));
+
+        let filter = Filter::from_str("price = infinity").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(2));
+        let filter = Filter::from_str("price < infinity").unwrap().unwrap();
+        assert!(matches!(
+            filter.evaluate(&rtxn, &index),
+            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
+        ));
+    }
+
+    #[test]
+    fn has_any_has_all() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("keywords") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "keywords": ["rust", "search"] },
+                { "id": 1, "keywords": ["rust"] },
+                { "id": 2, "keywords": ["search", "engine"] },
+                { "id": 3, "keywords": ["python"] },
+                { "id": 4 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str(r#"keywords HAS_ANY ["rust", "search"]"#).unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1, 2]));
+
+        let filter = Filter::from_str(r#"keywords HAS_ALL ["rust", "search"]"#).unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("keywords HAS_ALL [rust, python]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.is_empty());
+
+        let filter = Filter::from_str("NOT keywords HAS_ALL [rust, search]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn has_all_duplicates() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("keywords") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "keywords": ["rust", "search", "engine"] },
+                { "id": 1, "keywords": ["rust", "rust"] },
+                { "id": 2, "keywords": ["Rust", "search"] },
+                { "id": 3, "keywords": ["search"] },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("keywords HAS_ALL [rust, search, Rust]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2]));
+
+        // a document only contains a value once, whatever the duplicates of the list
+        let filter = Filter::from_str("keywords HAS_ALL [rust, rust]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1, 2]));
+
+        let mut filter =
+            Filter::from_str("keywords HAS_ALL [rust, search, Rust]").unwrap().unwrap();
+        filter.has_all_duplicates(HasAllDuplicates::Reject);
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Value `Rust` is listed more than once in the `HAS_ALL` list of attribute `keywords`."
+        ));
+
+        let mut filter = Filter::from_str("keywords HAS_ALL [rust, search]").unwrap().unwrap();
+        filter.has_all_duplicates(HasAllDuplicates::Reject);
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+    }
+
+    #[test]
+    fn numeric_operator_on_string_field() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name"), S("age") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "kefir", "age": 5 },
+                { "id": 1, "name": "intel", "age": 7 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("name > 5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `name` only contains string values, but the `>` operator can only be applied to numbers. Use `=`, `!=`, or `IN` to filter on string values instead."
+        ));
+
+        let filter = Filter::from_str("name 1 TO 5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains("the `TO` operator can only be applied to numbers"));
+
+        // numeric fields are not affected
+        let filter = Filter::from_str("age > 5").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1]));
+    }
+
+    #[test]
+    fn type_annotations() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "9", "price": 9 },
+                { "id": 1, "name": "kefir", "price": 9.5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price = f64:9").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("price != i64:9").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1]));
+
+        let filter = Filter::from_str("name = string:9").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("price = i64:9.5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Value `9.5` is not a valid `i64`."));
+
+        let filter = Filter::from_str("price = string:9").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "The value is annotated as `string`, but attribute `price` only contains number values."
+        ));
+
+        let filter = Filter::from_str("name > f64:5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains("only contains string values"));
+    }
+
+    #[test]
+    fn in_list_type_annotations() {
+        #[derive(Default)]
+        struct ReadsMetrics {
+            reads: u64,
+        }
+
+        impl FacetMetrics for ReadsMetrics {
+            fn database_read(&mut self, _field_id: FieldId) {
+                self.reads += 1;
+            }
+        }
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "9", "price": 9 },
+                { "id": 1, "name": "kefir", "price": 9.5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price IN [i64:9, f64:9.5]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1]));
+
+        let filter = Filter::from_str("name IN [string:9, kefir]").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1]));
+
+        // an invalid literal is rejected with the rest of the list when parsing
+        let error = Filter::from_str("price IN [i64:9, i64:9.5, f64:10]").unwrap_err();
+        assert!(error.to_string().starts_with("Value `9.5` is not a valid `i64`."));
+
+        // a value conflicting with the type of the field is rejected before any value is read
+        let filter = Filter::from_str("price IN [i64:9, f64:9.5, string:9]").unwrap().unwrap();
+        let mut metrics = ReadsMetrics::default();
+        let error = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "The value is annotated as `string`, but attribute `price` only contains number values."
+        ));
+        assert_eq!(metrics.reads, 0);
+    }
+
+    #[test]
+    fn difference_and_symmetric_difference() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 1 },
+                { "id": 1, "price": 2 },
+                { "id": 2, "price": 3 },
+                { "id": 3, "price": 4 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // overlapping results
+        let left = Filter::from_str("price <= 3").unwrap().unwrap();
+        let right = Filter::from_str("price >= 2").unwrap().unwrap();
+        let result = left.difference(&right, &rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+        let result = right.difference(&left, &rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([3]));
+        let result = left.symmetric_difference(&right, &rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 3]));
+        let (added, removed) = left.diff(&right, &rtxn, &index).unwrap();
+        assert_eq!(added, RoaringBitmap::from_iter([3]));
+        assert_eq!(removed, RoaringBitmap::from_iter([0]));
+        let (added, removed) = right.diff(&left, &rtxn, &index).unwrap();
+        assert_eq!(
+            (added, removed),
+            (RoaringBitmap::from_iter([0]), RoaringBitmap::from_iter([3]))
+        );
+        let (added, removed) = left.diff(&left, &rtxn, &index).unwrap();
+        assert!(added.is_empty() && removed.is_empty());
+
+        // disjoint results
+        let left = Filter::from_str("price < 2").unwrap().unwrap();
+        let right = Filter::from_str("price > 3").unwrap().unwrap();
+        let result = left.difference(&right, &rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+        let result = left.symmetric_difference(&right, &rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 3]));
+
+        // the filters are evaluated like by `evaluate`, with the same errors
+        let unfilterable = Filter::from_str("title = kefir").unwrap().unwrap();
+        let expected = unfilterable.evaluate(&rtxn, &index).unwrap_err().to_string();
+        let error = left.difference(&unfilterable, &rtxn, &index).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+        let error = unfilterable.symmetric_difference(&left, &rtxn, &index).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+        let error = left.diff(&unfilterable, &rtxn, &index).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn check_referenced_fields() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age"), S("name") });
+            })
+            .unwrap();
+
+        index.add_documents(documents!([{ "id": 0, "age": 5 }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // `name` is filterable but was never indexed, it silently matches nothing by default
+        let mut filter = Filter::from_str("age = 5 OR name = kefir").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        let error = filter.check_fields(true).evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `name` is not faceted in this index anymore. It may have been removed from the settings or never indexed."
+        ));
+        drop(rtxn);
+
+        // `age` stops being faceted after the filter was parsed
+        let mut filter = Filter::from_str("age = 5").unwrap().unwrap();
+        filter.check_fields(true);
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Attribute `age` is not faceted in this index anymore."));
+    }
+
+    #[test]
+    fn negated_comparison() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 5 },
+                { "id": 1, "color": "blue", "price": 15 },
+                { "id": 2 },
+                { "id": 3, "color": ["red", "blue"], "price": [5, 15] },
+                { "id": 4, "color": null, "price": null },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str| {
+            Filter::from_str(expression).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        // the documents without the attribute are only matched by `NOT`, a `null` attribute
+        // exists like with `EXISTS`
+        assert_eq!(evaluate("- color = red"), RoaringBitmap::from_iter([1, 4]));
+        assert_eq!(evaluate("NOT color = red"), RoaringBitmap::from_iter([1, 2, 4]));
+        assert_eq!(evaluate("- price > 10"), RoaringBitmap::from_iter([0, 4]));
+        assert_eq!(evaluate("NOT price > 10"), RoaringBitmap::from_iter([0, 2, 4]));
+        assert_eq!(evaluate("- color NOT IN [blue]"), RoaringBitmap::from_iter([1, 3]));
+        assert_eq!(evaluate("price < 10 AND - color = blue"), RoaringBitmap::from_iter([0]));
+
+        for expression in ["color = red", "price > 10", "color IN [red, green]", "price 1 TO 5"] {
+            let negated = FilterCondition::parse(expression).unwrap().unwrap().negate();
+            let expected = Filter::from(negated).evaluate(&rtxn, &index).unwrap();
+            assert_eq!(evaluate(&format!("- {expression}")), expected);
+        }
+    }
+
+    #[test]
+    fn check_values() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age"), S("name"), S("zip") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "Kefir", "age": 5 },
+                { "id": 1, "name": "intel", "age": 5.5 },
+                { "id": 2, "zip": "01234" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // a missing value silently matches nothing by default
+        let mut filter = Filter::from_str("name = kefr").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).unwrap().is_empty());
+
+        let error = filter.check_values(true).evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Attribute `name` does not contain the value `kefr`."));
+
+        for expression in [
+            "name = KEFIR",
+            "NOT name = intel",
+            "age IN [5, 5.5]",
+            "age != f64:5.5",
+            "zip = '01234'",
+            "name = kefir AND age 0 TO 10",
+        ] {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.check_values(true).evaluate(&rtxn, &index).unwrap();
+        }
+
+        for (expression, value) in [
+            ("age IN [5, 6]", "6"),
+            ("age != 7", "7"),
+            ("zip = 1234", "1234"),
+            ("age = string:5", "5"),
+            ("name = intel OR (age > 1 AND name = bob)", "bob"),
+        ] {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            let error = filter.check_values(true).evaluate(&rtxn, &index).unwrap_err();
+            assert!(error.to_string().contains(&format!("does not contain the value `{value}`")));
+        }
+    }
+
+    #[test]
+    fn in_integer_runs() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("rating") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "rating": 1 },
+                { "id": 1, "rating": 2 },
+                { "id": 2, "rating": 2.5 },
+                { "id": 3, "rating": 3 },
+                { "id": 4, "rating": 5 },
+                { "id": 5, "rating": 8 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let in_elements = |filter: &Filter<'static>| match &filter.condition {
+            FilterCondition::In { els, .. } => els.clone(),
+            _ => panic!("not an IN condition"),
+        };
+
+        // a fully contiguous list is collapsed to a single range
+        let filter = Filter::from_str("rating IN [3, 1, 2, 4, 5]").unwrap().unwrap();
+        let (runs, others) = integer_runs(&in_elements(&filter));
+        assert_eq!(runs, vec![1..=5]);
+        assert!(others.is_empty());
+
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        let unions =
+            Filter::from_str("rating = 1 OR rating = 2 OR rating = 3 OR rating = 4 OR rating = 5")
+                .unwrap()
+                .unwrap();
+        assert_eq!(result, unions.evaluate(&rtxn, &index).unwrap());
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1, 3, 4]));
+
+        // a sparse list is kept as unions
+        let filter = Filter::from_str("rating IN [1, 3, 5, 8]").unwrap().unwrap();
+        let (runs, others) = integer_runs(&in_elements(&filter));
+        assert!(runs.is_empty());
+        assert_eq!(others.len(), 4);
+
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        let unions = Filter::from_str("rating = 1 OR rating = 3 OR rating = 5 OR rating = 8")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, unions.evaluate(&rtxn, &index).unwrap());
+        assert_eq!(result, RoaringBitmap::from_iter([0, 3, 4, 5]));
+    }
+
+    #[test]
+    fn constants() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "age": 1 },
+                { "id": 1, "age": 2 },
+                { "id": 2, "age": 3 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("TRUE").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1, 2]));
+
+        let filter = Filter::from_str("FALSE").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).unwrap().is_empty());
+
+        let filter = Filter::from_str("age > 1 AND TRUE").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 2]));
+
+        let filter = Filter::from_str("age > 1 OR NOT TRUE").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 2]));
+    }
+
+    #[test]
+    fn legacy_syntax() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 2020 },
+                { "id": 1, "genre": "horror", "year": 2021 },
+                { "id": 2, "genre": "comedy", "year": 2020 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str_with_legacy_syntax("genre:horror", true).unwrap().unwrap();
+        assert_eq!(filter, Filter::from_str("genre = horror").unwrap().unwrap());
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1]));
+
+        let filter = Filter::from_str_with_legacy_syntax("year:2020", true).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+
+        let filter = Filter::from_str_with_legacy_syntax("genre:horror AND year:2020", true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+
+        assert!(Filter::from_str_with_legacy_syntax("genre:horror", false).is_err());
+    }
+
+    #[test]
+    fn evaluate_with_metrics() {
+        #[derive(Default)]
+        struct RecordingMetrics {
+            reads: HashMap<FieldId, u64>,
+            levels: HashMap<FieldId, u64>,
+            matched: HashMap<FieldId, u64>,
+        }
+
+        impl FacetMetrics for RecordingMetrics {
+            fn database_read(&mut self, field_id: FieldId) {
+                *self.reads.entry(field_id).or_default() += 1;
+            }
+
+            fn level_descended(&mut self, field_id: FieldId, _level: u8) {
+                *self.levels.entry(field_id).or_default() += 1;
+            }
+
+            fn documents_matched(&mut self, field_id: FieldId, count: u64) {
+                *self.matched.entry(field_id).or_default() += count;
+            }
+        }
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age"), S("name") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "age": 1, "name": "kefir" },
+                { "id": 1, "age": 2, "name": "intel" },
+                { "id": 2, "age": 3, "name": "kefir" },
+                { "id": 3, "age": 4, "name": "bob" },
+                { "id": 4, "age": 5, "name": "kefir" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let age = fields_ids_map.id("age").unwrap();
+        let name = fields_ids_map.id("name").unwrap();
+
+        let filter = Filter::from_str("age 2 TO 4").unwrap().unwrap();
+        let mut metrics = RecordingMetrics::default();
+        let result = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2, 3]));
+        // the five values fit in a single level, which is read until the first value above 4
+        assert_eq!(metrics.levels, HashMap::from([(age, 1)]));
+        assert_eq!(metrics.reads, HashMap::from([(age, 5)]));
+        assert_eq!(metrics.matched, HashMap::from([(age, 3)]));
+
+        let filter = Filter::from_str("age 2 TO 4 AND name = kefir").unwrap().unwrap();
+        let mut metrics = RecordingMetrics::default();
+        let result = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([2]));
+        // only the string database is read for `kefir`
+        assert_eq!(metrics.reads[&name], 1);
+        assert_eq!(metrics.matched, HashMap::from([(age, 3), (name, 3)]));
+    }
+
+    #[test]
+    fn document_id_field() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("age") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "age": 1 },
+                { "id": 1, "age": 2 },
+                { "id": 2, "age": 3 },
+                { "id": 3, "age": 4 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the ids that don't exist are ignored
+        let mut filter = Filter::from_str("_docid IN [0, 2, 9, 42]").unwrap().unwrap();
+        let result = filter.document_id_field("_docid").evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2]));
+
+        let mut filter = Filter::from_str("_docid > 0 AND age < 4").unwrap().unwrap();
+        let result = filter.document_id_field("_docid").evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2]));
+
+        let mut filter = Filter::from_str("NOT _docid 1 TO 2").unwrap().unwrap();
+        let result = filter.document_id_field("_docid").evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 3]));
+
+        let mut filter = Filter::from_str("_docid IN [1, kefir]").unwrap().unwrap();
+        let error = filter.document_id_field("_docid").evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "`kefir` is not a valid document id. Document ids must be positive integers."
+        ));
+
+        // without configuring the pseudo-field, it's a regular attribute
+        let filter = Filter::from_str("_docid IN [0, 2]").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `_docid` is not filterable."));
+    }
+
+    #[test]
+    fn step_range() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("year") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> =
+            (0..=25).map(|i| serde_json::json!({ "id": i, "year": 2000 + i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the step divides the range evenly
+        let filter = Filter::from_str("year 2000 TO 2020 STEP 5").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 5, 10, 15, 20]));
+
+        // the step doesn't divide the range evenly
+        let filter = Filter::from_str("year 2001 TO 2020 STEP 7").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 8, 15]));
+
+        // the distance between the bounds doesn't fit in an `i64`
+        let filter = Filter::from_str("year -9223372036854775808 TO 2010 STEP 2").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2, 4, 6, 8, 10]));
+
+        let filter = Filter::from_str("year 2000 TO 2020 STEP 2.5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "`2.5` is not valid in a `STEP` range. The bounds must be integers and the step a positive integer."
+        ));
+
+        let filter = Filter::from_str("year 2000 TO 2020 STEP 0").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("`0` is not valid in a `STEP` range."));
+    }
+
+    #[test]
+    fn in_list_order() {
+        #[derive(Default)]
+        struct StreamingMetrics {
+            values: Vec<(String, RoaringBitmap)>,
+        }
+
+        impl FacetMetrics for StreamingMetrics {
+            fn in_value_matched(
+                &mut self,
+                _field_id: FieldId,
+                value: &str,
+                docids: &RoaringBitmap,
+            ) {
+                self.values.push((value.to_string(), docids.clone()));
+            }
+        }
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("tag"), S("age") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "tag": 1, "age": 1 },
+                { "id": 1, "tag": 2, "age": 2 },
+                { "id": 2, "tag": 3, "age": 3 },
+                { "id": 3, "tag": "kefir", "age": 5 },
+                { "id": 4, "tag": "bob", "age": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let streamed = |filter: &Filter| {
+            let mut metrics = StreamingMetrics::default();
+            let result = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(0..5));
+            metrics.values
+        };
+        let values = |streamed: Vec<(String, RoaringBitmap)>| {
+            streamed.into_iter().map(|(value, _)| value).collect::<Vec<_>>()
+        };
+
+        let mut filter = Filter::from_str("tag IN [3, kefir, 1, bob, 2]").unwrap().unwrap();
+
+        let given = streamed(filter.in_list_order(InListOrder::Given));
+        assert_eq!(given[0], (S("3"), RoaringBitmap::from_iter([2])));
+        assert_eq!(given[1], (S("kefir"), RoaringBitmap::from_iter([3])));
+        assert_eq!(values(given), ["3", "kefir", "1", "bob", "2"]);
+
+        let ascending = streamed(filter.in_list_order(InListOrder::Ascending));
+        assert_eq!(values(ascending), ["1", "2", "3", "bob", "kefir"]);
+
+        let descending = streamed(filter.in_list_order(InListOrder::Descending));
+        assert_eq!(values(descending), ["kefir", "bob", "3", "2", "1"]);
+
+        // by default, the run `1, 2, 3` of the numeric field is read as a range and not streamed
+        let mut filter = Filter::from_str("age IN [5, 3, 1, 2]").unwrap().unwrap();
+        assert_eq!(values(streamed(&filter)), ["5"]);
+        assert_eq!(
+            values(streamed(filter.in_list_order(InListOrder::Given))),
+            ["5", "3", "1", "2"]
+        );
+    }
+
+    #[test]
+    fn evaluate_sorted_vec() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        // the prices are in the opposite order of the documents ids
+        let documents: Vec<_> =
+            (0..100).map(|i| serde_json::json!({ "id": i, "price": 100 - i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        for filter in ["price 10 TO 60", "price > 90 OR price < 5", "price != 50"] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+            let sorted = filter.evaluate_sorted_vec(&rtxn, &index).unwrap();
+            assert_eq!(sorted, bitmap.iter().collect::<Vec<_>>());
+            assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn evaluate_serialized() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> =
+            (0..1000).map(|i| serde_json::json!({ "id": i, "price": i % 100 })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        for filter in ["price 10 TO 60", "price = 7", "price > 1000", "NOT price = 7"] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let bytes = filter.evaluate_serialized(&rtxn, &index).unwrap();
+            let deserialized = Filter::deserialize_evaluated(&bytes).unwrap();
+            assert_eq!(deserialized, filter.evaluate(&rtxn, &index).unwrap());
+        }
+
+        assert!(Filter::deserialize_evaluated(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn evaluate_first_k() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        // the prices are in the opposite order of the documents ids
+        let documents: Vec<_> =
+            (0..100).map(|i| serde_json::json!({ "id": i, "price": 100 - i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        for filter in ["price 10 TO 60", "price > 90 OR price < 5", "price != 50", "price = 7"] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let sorted = filter.evaluate_sorted_vec(&rtxn, &index).unwrap();
+            for k in [0, 1, 5, 200] {
+                let first = filter.evaluate_first_k(&rtxn, &index, k).unwrap();
+                assert_eq!(first, sorted[..k.min(sorted.len())]);
+            }
+        }
+
+        // the smallest ids are the ones of the highest prices
+        let filter = Filter::from_str("price 10 TO 60").unwrap().unwrap();
+        assert_eq!(filter.evaluate_first_k(&rtxn, &index, 3).unwrap(), vec![40, 41, 42]);
+    }
+
+    #[test]
+    fn bind_placeholders() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 2019 },
+                { "id": 1, "genre": "horror", "year": 2021 },
+                { "id": 2, "genre": "comedy", "year": 2022 },
+                { "id": 3, "genre": "horror", "year": 2023 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("genre = ? AND year > ?").unwrap().unwrap();
+        let bound = filter.bind(&[serde_json::json!("horror"), serde_json::json!(2020)]).unwrap();
+        let result = bound.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 3]));
+
+        // the same prepared filter can be bound again to other values
+        let bound = filter.bind(&[serde_json::json!("comedy"), serde_json::json!(2000)]).unwrap();
+        let result = bound.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([2]));
+
+        let error = filter.bind(&[serde_json::json!("horror")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The filter expects 2 parameters but 1 were provided."));
+
+        let error =
+            filter.bind(&[serde_json::json!("horror"), serde_json::json!(null)]).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Invalid type for filter subexpression: expected: String, Number, Boolean, found: null."
+        ));
+
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The `?` placeholder has not been bound to a value."));
+    }
+
+    #[test]
+    fn bind_named_expressions() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 5 },
+                { "id": 1, "price": 10 },
+                { "id": 2, "price": 19 },
+                { "id": 3, "price": 20 },
+                { "id": 4, "price": 30 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price < base * 2").unwrap().unwrap();
+        let bound = filter.bind_named(&HashMap::from([(S("base"), 10.0)])).unwrap();
+        let result = bound.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1, 2]));
+
+        let filter = Filter::from_str("price >= low + 1 AND price <= high / 2").unwrap().unwrap();
+        let params = HashMap::from([(S("low"), 9.0), (S("high"), 40.0)]);
+        let result = filter.bind_named(&params).unwrap().evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2, 3]));
+
+        // expressions made of numbers only are computed right away
+        let filter = Filter::from_str("price > 2 * 3 + 1").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2, 3, 4]));
+
+        let error = Filter::from_str("price < 1 / 0").unwrap_err();
+        assert!(error.to_string().starts_with("Division by zero in the expression."));
+
+        let filter = Filter::from_str("price < base / 0").unwrap().unwrap();
+        let error = filter.bind_named(&HashMap::from([(S("base"), 10.0)])).unwrap_err();
+        assert!(error.to_string().starts_with("Division by zero in the expression."));
+
+        let filter = Filter::from_str("price < base * 2").unwrap().unwrap();
+        let error = filter.bind_named(&HashMap::new()).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Parameter `base` is not a number and has not been provided."));
+
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The expression uses parameters that have not been bound."));
+    }
+
+    #[test]
+    fn lazy_filter_chain() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 2019 },
+                { "id": 1, "genre": "horror", "year": 2021 },
+                { "id": 2, "genre": "comedy", "year": 2022 },
+                { "id": 3, "genre": "horror", "year": 2023 },
+                { "id": 4, "genre": "drama", "year": 2018 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        fn filter(s: &str) -> Filter {
+            Filter::from_str(s).unwrap().unwrap()
+        }
+
+        let chain =
+            filter("genre = horror").lazy().and(filter("year > 2020")).or(filter("year < 2019"));
+        let expected = filter("(genre = horror AND year > 2020) OR year < 2019");
+        assert_eq!(chain.clone().into_filter(), expected);
+        let result = chain.collect(&rtxn, &index).unwrap();
+        assert_eq!(result, expected.evaluate(&rtxn, &index).unwrap());
+        assert_eq!(result, RoaringBitmap::from_iter([1, 3, 4]));
+
+        let chain = filter("genre = horror OR genre = comedy")
+            .lazy()
+            .and(filter("year >= 2021"))
+            .and(filter("year <= 2022"));
+        let expected =
+            filter("(genre = horror OR genre = comedy) AND year >= 2021 AND year <= 2022");
+        assert_eq!(chain.clone().into_filter(), expected);
+        let result = chain.collect(&rtxn, &index).unwrap();
+        assert_eq!(result, expected.evaluate(&rtxn, &index).unwrap());
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2]));
+
+        // nothing is read before the chain is collected
+        let chain = filter("genre = horror").lazy().and(filter("unknown = 1"));
+        let error = chain.collect(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `unknown` is not filterable."));
+    }
+
+    #[test]
+    fn like_operator() {
+        #[derive(Default)]
+        struct ReadsMetrics {
+            reads: u64,
+        }
+
+        impl FacetMetrics for ReadsMetrics {
+            fn database_read(&mut self, _field_id: FieldId) {
+                self.reads += 1;
+            }
+        }
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "Apple" },
+                { "id": 1, "name": "apricot" },
+                { "id": 2, "name": "banana" },
+                { "id": 3, "name": "A_C" },
+                { "id": 4, "name": "ABC" },
+                { "id": 5, "name": "100%" },
+                { "id": 6, "name": "1000" },
+                { "id": 7, "name": "cabana" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let mut metrics = ReadsMetrics::default();
+            let result = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            (result.into_iter().collect::<Vec<_>>(), metrics.reads)
+        };
+
+        // only the values starting with `a` are read, until `banana`
+        assert_eq!(evaluate("name LIKE 'a%'"), (vec![0, 1, 3, 4], 5));
+        assert_eq!(evaluate("name LIKE 'A_C'").0, vec![3, 4]);
+        assert_eq!(evaluate("name LIKE '_pple'").0, vec![0]);
+        assert_eq!(evaluate("name NOT LIKE 'a%'").0, vec![2, 5, 6, 7]);
+
+        // without a fixed prefix every value is compared to the pattern
+        assert_eq!(evaluate("name LIKE '%an%'"), (vec![2, 7], 8));
+        assert_eq!(evaluate("name LIKE '%a'").0, vec![2, 7]);
+
+        // the wildcards can be escaped
+        assert_eq!(evaluate(r"name LIKE 'A\_C'").0, vec![3]);
+        assert_eq!(evaluate(r"name LIKE '100\%'"), (vec![5], 2));
+        assert_eq!(evaluate("name LIKE '100%'").0, vec![5, 6]);
+    }
+
+    #[test]
+    fn not_ignores_deleted_documents() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror" },
+                { "id": 1, "genre": "comedy" },
+                { "id": 2, "genre": "drama" },
+                { "id": 3, "genre": "comedy" },
+            ]))
+            .unwrap();
+
+        index.delete_documents(vec![S("1"), S("2")]);
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the deleted documents are removed from the documents ids, the universe of the negation
+        let filter = Filter::from_str("NOT genre = horror").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([3]));
+
+        let filter = Filter::from_str("genre != comedy").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("genre NOT IN [horror]").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([3]));
+    }
+
+    #[test]
+    fn quoted_numbers_are_strings() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("zip") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "zip": "01234" },
+                { "id": 1, "zip": "1234" },
+                { "id": 2, "zip": 1234 },
+                { "id": 3, "zip": "75001" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("zip = \"01234\"").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+
+        // unquoted, the leading zero is kept to match the strings but it also matches the number
+        let filter = Filter::from_str("zip = 01234").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+
+        // a quoted number only matches the strings
+        let filter = Filter::from_str("zip = '1234'").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+        let filter = Filter::from_str("zip = 1234").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 2]));
+
+        let filter = Filter::from_str("zip != \"01234\"").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 2, 3]));
+
+        let filter = Filter::from_str("zip IN ['1234', \"75001\"]").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 3]));
+    }
+
+    #[test]
+    fn strict_types() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("year"), S("genre") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 10.5, "year": 2019, "genre": "horror" },
+                { "id": 1, "price": 10, "year": 2020, "genre": "comedy" },
+                { "id": 2, "price": 20.25, "year": 2021, "genre": "drama" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the coercions are accepted by default
+        let filter = Filter::from_str("price = 10 AND year = 2020.0").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+
+        let evaluate = |filter: &str| {
+            let mut filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.strict_types(true).evaluate(&rtxn, &index).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            evaluate("price = 10.0 AND year = 2020").unwrap(),
+            RoaringBitmap::from_iter([1])
+        );
+        assert_eq!(
+            evaluate("price 10.0 TO 15.0 OR genre = 1").unwrap(),
+            RoaringBitmap::from_iter([0, 1])
+        );
+        assert_eq!(evaluate("year IN [2019, 2021]").unwrap(), RoaringBitmap::from_iter([0, 2]));
+        // the quoted and annotated values are not checked
+        assert_eq!(
+            evaluate("price = f64:10 OR year = '2020.0'").unwrap(),
+            RoaringBitmap::from_iter([1])
+        );
+
+        let error = evaluate("price = 10").unwrap_err();
+        assert!(error.starts_with(
+            "Attribute `price` contains floats, but `10` is an integer. Write it as a float, e.g. `10.0`, or disable the strict types."
+        ));
+        let error = evaluate("year > 2019 AND NOT price < 20").unwrap_err();
+        assert!(error.starts_with("Attribute `price` contains floats, but `20` is an integer."));
+        let error = evaluate("year = 2020.0").unwrap_err();
+        assert!(error.starts_with(
+            "Attribute `year` only contains integers, but `2020.0` is a float. Write it as an integer or disable the strict types."
+        ));
+        let error = evaluate("year IN [2019, 2021.5]").unwrap_err();
+        assert!(
+            error.starts_with("Attribute `year` only contains integers, but `2021.5` is a float.")
+        );
+    }
+
+    #[test]
+    fn number_types() {
+        let floats = TempIndex::new();
+        let integers = TempIndex::new();
+        for index in [&floats, &integers] {
+            index
+                .update_settings(|settings| {
+                    settings.set_filterable_fields(hashset! { S("price") });
+                })
+                .unwrap();
+        }
+        floats
+            .add_documents(documents!([
+                { "id": 0, "price": 9.5 },
+                { "id": 1, "price": 10.0 },
+                { "id": 2, "price": 15.25 },
+            ]))
+            .unwrap();
+        integers
+            .add_documents(documents!([
+                { "id": 0, "price": 9 },
+                { "id": 1, "price": 10 },
+                { "id": 2, "price": 20 },
+            ]))
+            .unwrap();
+
+        // the same expression mixes an integer and a float
+        let expression = "price >= 10 AND price < 20.0";
+        let evaluate = |index: &TempIndex, number_type: Option<NumberType>| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.strict_types(true);
+            if let Some(number_type) = number_type {
+                filter.number_type("price", number_type);
+            }
+            let rtxn = index.read_txn().unwrap();
+            filter.evaluate(&rtxn, index).map_err(|e| e.to_string())
+        };
+
+        let error = evaluate(&floats, None).unwrap_err();
+        assert!(error.starts_with("Attribute `price` contains floats, but `10` is an integer."));
+        assert_eq!(
+            evaluate(&floats, Some(NumberType::Float)).unwrap(),
+            RoaringBitmap::from_iter([1, 2])
+        );
+
+        let error = evaluate(&integers, None).unwrap_err();
+        assert!(
+            error.starts_with("Attribute `price` only contains integers, but `20.0` is a float.")
+        );
+        assert_eq!(
+            evaluate(&integers, Some(NumberType::Integer)).unwrap(),
+            RoaringBitmap::from_iter([1])
+        );
+
+        // an integer can't be written with a fractional part
+        let rtxn = integers.read_txn().unwrap();
+        let mut filter = Filter::from_str("price > 10.5").unwrap().unwrap();
+        filter.number_type("price", NumberType::Integer);
+        let error = filter.evaluate(&rtxn, &integers).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `price` is set to contain integers, but `10.5` is not an integer."
+        ));
+        let overlaid = filter.evaluate_with_overlay(&rtxn, &integers, &rtxn, &integers);
+        assert!(overlaid
+            .unwrap_err()
+            .to_string()
+            .starts_with("Attribute `price` is set to contain integers"));
+        let columns = FacetColumns::new();
+        let error = filter.evaluate_with_columns(&rtxn, &integers, &columns).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `price` is set to contain integers"));
+    }
+
+    #[test]
+    fn leaf_operators() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("genre"), S("_geo") });
+            })
+            .unwrap();
+
+        index.add_documents(documents!([{ "id": 0, "price": 10, "genre": "horror" }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let price = fields_ids_map.id("price").unwrap();
+        let genre = fields_ids_map.id("genre").unwrap();
+
+        let filter = Filter::from_str(
+            "price > 10 AND (NOT genre IN [horror, drama] OR (price 1 TO 5 AND TRUE)) \
+             OR _geoRadius(45, 3, 100) OR size EXISTS",
+        )
+        .unwrap()
+        .unwrap();
+
+        let leaves: Vec<_> = filter.leaf_operators(&fields_ids_map).collect();
+        assert_eq!(leaves.len(), 5);
+
+        let summary: Vec<_> =
+            leaves.iter().map(|leaf| (leaf.field(), leaf.field_id(), leaf.kind())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("price", Some(price), LeafKind::GreaterThan),
+                ("genre", Some(genre), LeafKind::In),
+                ("price", Some(price), LeafKind::Range),
+                ("_geo", fields_ids_map.id("_geo"), LeafKind::GeoRadius),
+                ("size", fields_ids_map.id("size"), LeafKind::Exists),
+            ]
+        );
+
+        assert_eq!(leaves[0].condition().to_string(), "{price} > {10}");
+        assert_eq!(leaves[1].condition().to_string(), "{genre} IN[{horror}, {drama}, ]");
+    }
+
+    #[test]
+    fn merge_ranges() {
+        #[derive(Default)]
+        struct ReadsMetrics {
+            reads: u64,
+        }
+
+        impl FacetMetrics for ReadsMetrics {
+            fn database_read(&mut self, _field_id: FieldId) {
+                self.reads += 1;
+            }
+        }
+
+        let index = TempIndex::new_with_map_size(4096 * 1000);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("year") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..500)
+            .map(|id| {
+                serde_json::json!({ "id": id, "year": 1800 + id % 250 })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+        index
+            .add_documents(crate::documents::documents_batch_reader_from_objects(documents))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let evaluate = |filter: &Filter| {
+            let mut metrics = ReadsMetrics::default();
+            let docids = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            (docids, metrics.reads)
+        };
+
+        // overlapping, contained and adjacent ranges
+        for (filter, merged) in [
+            ("year > 1900 OR year > 1950", "{year} ({1900}, *)"),
+            ("year 1850 TO 2000 OR year 1900 TO 1950", "{year} [{1850}, {2000}]"),
+            ("year 1850 TO 1900 OR year [1900, 1990)", "{year} [{1850}, {1990})"),
+        ] {
+            let original = Filter::from_str(filter).unwrap().unwrap();
+            let mut optimized = original.clone();
+            optimized.merge_ranges();
+            assert_eq!(optimized.condition.to_string(), merged);
+
+            let (original_docids, original_reads) = evaluate(&original);
+            let (optimized_docids, optimized_reads) = evaluate(&optimized);
+            assert_eq!(optimized_docids, original_docids);
+            assert!(
+                optimized_reads < original_reads,
+                "{filter}: {optimized_reads} < {original_reads}"
+            );
+        }
+
+        // the disjoint ranges are kept as is
+        let original = Filter::from_str("year < 1810 OR year > 2040").unwrap().unwrap();
+        let mut optimized = original.clone();
+        optimized.merge_ranges();
+        assert_eq!(optimized, original);
+        assert_eq!(evaluate(&optimized), evaluate(&original));
+    }
+
+    #[test]
+    fn evaluate_many() {
+        let movies = TempIndex::new();
+        movies
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+        movies
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 1999 },
+                { "id": 1, "genre": "horror", "year": 2010 },
+                { "id": 2, "genre": "comedy", "year": 2015 },
+            ]))
+            .unwrap();
+
+        let books = TempIndex::new();
+        books
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("rating") });
+            })
+            .unwrap();
+        books
+            .add_documents(documents!([
+                { "id": 0, "genre": "comedy", "rating": 5, "year": 2012 },
+                { "id": 1, "genre": "horror", "rating": 3 },
+            ]))
+            .unwrap();
+
+        let movies_rtxn = movies.read_txn().unwrap();
+        let books_rtxn = books.read_txn().unwrap();
+        let indexes = [(&movies_rtxn, &*movies), (&books_rtxn, &*books)];
+
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate_many(&indexes).unwrap()
+        };
+
+        assert_eq!(
+            evaluate("genre = horror"),
+            vec![RoaringBitmap::from_iter([0, 1]), RoaringBitmap::from_iter([1])]
+        );
+        // `year` is only filterable in the movies, and `rating` in the books
+        assert_eq!(
+            evaluate("genre = horror AND year > 2000"),
+            vec![RoaringBitmap::from_iter([1]), RoaringBitmap::new()]
+        );
+        assert_eq!(
+            evaluate("year > 2000 OR rating >= 4"),
+            vec![RoaringBitmap::from_iter([1, 2]), RoaringBitmap::from_iter([0])]
+        );
+        assert_eq!(
+            evaluate("NOT year > 2000"),
+            vec![RoaringBitmap::from_iter([0]), RoaringBitmap::from_iter([0, 1])]
+        );
+        assert_eq!(
+            evaluate("_geoRadius(45, 3, 1000) OR genre = comedy"),
+            vec![RoaringBitmap::from_iter([2]), RoaringBitmap::from_iter([0])]
+        );
+
+        // evaluated on a single index, the filter still fails on the unfilterable fields
+        let filter = Filter::from_str("year > 2000").unwrap().unwrap();
+        assert!(filter.evaluate(&books_rtxn, &books).is_err());
+    }
+
+    #[test]
+    fn escaped_control_characters() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("address") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "address": "1 Main Street\nSpringfield" },
+                { "id": 1, "address": "1 Main Street Springfield" },
+                { "id": 2, "address": "1 Main Street\tSpringfield" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(
+            evaluate(r#"address = "1 Main Street\nSpringfield""#),
+            RoaringBitmap::from_iter([0])
+        );
+        assert_eq!(
+            evaluate(r"address = '1 Main Street\tSpringfield'"),
+            RoaringBitmap::from_iter([2])
+        );
+        // a literal newline between the quotes is kept as is
+        assert_eq!(
+            evaluate("address = \"1 Main Street\nSpringfield\""),
+            RoaringBitmap::from_iter([0])
+        );
+        assert_eq!(
+            evaluate(r#"address != "1 Main Street\nSpringfield""#),
+            RoaringBitmap::from_iter([1, 2])
+        );
+    }
+
+    #[test]
+    fn cache_string_lookups() {
+        #[derive(Default)]
+        struct ReadsMetrics {
+            reads: HashMap<FieldId, u64>,
+        }
+
+        impl FacetMetrics for ReadsMetrics {
+            fn database_read(&mut self, field_id: FieldId) {
+                *self.reads.entry(field_id).or_default() += 1;
+            }
+        }
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 1999 },
+                { "id": 1, "genre": "Horror", "year": 2010 },
+                { "id": 2, "genre": "comedy", "year": 2015 },
+                { "id": 3, "genre": "drama", "year": 2020 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let genre = index.fields_ids_map(&rtxn).unwrap().id("genre").unwrap();
+
+        let evaluate = |filter: &str, cache: bool| {
+            let mut filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.cache_string_lookups(cache);
+            let mut metrics = ReadsMetrics::default();
+            let docids = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            (docids, metrics.reads.get(&genre).copied().unwrap_or_default())
+        };
+
+        let filter = "genre = horror OR (genre = HORROR AND year > 2000) OR genre != horror";
+        assert_eq!(evaluate(filter, false), (RoaringBitmap::from_iter([0, 1, 2, 3]), 3));
+        assert_eq!(evaluate(filter, true), (RoaringBitmap::from_iter([0, 1, 2, 3]), 1));
+
+        let filter = "genre IN [horror, comedy] AND NOT genre HAS_ALL [comedy, horror]";
+        assert_eq!(evaluate(filter, false), (RoaringBitmap::from_iter([0, 1, 2]), 4));
+        assert_eq!(evaluate(filter, true), (RoaringBitmap::from_iter([0, 1, 2]), 2));
+
+        // the looked up values are forgotten between two evaluations
+        let mut filter = Filter::from_str("genre = horror").unwrap().unwrap();
+        filter.cache_string_lookups(true);
+        for _ in 0..2 {
+            let mut metrics = ReadsMetrics::default();
+            filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            assert_eq!(metrics.reads[&genre], 1);
+        }
+    }
+
+    #[test]
+    fn evaluate_with_fields() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 1999 },
+                { "id": 1, "genre": "horror", "year": 2010 },
+                { "id": 2, "genre": "comedy", "year": 2015 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let filterable_fields = index.filterable_fields(&rtxn).unwrap();
+
+        let filter = Filter::from_str("genre = horror AND year > 2000").unwrap().unwrap();
+        let docids = filter
+            .evaluate_with_fields(&rtxn, &index, &fields_ids_map, &filterable_fields)
+            .unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([1]));
+        assert_eq!(docids, filter.evaluate(&rtxn, &index).unwrap());
+
+        // the given fields are used instead of the ones of the index
+        let only_genre = hashset! { S("genre") };
+        let error =
+            filter.evaluate_with_fields(&rtxn, &index, &fields_ids_map, &only_genre).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `year` is not filterable."));
+
+        let mut without_year = FieldsIdsMap::new();
+        without_year.insert("id").unwrap();
+        without_year.insert("genre").unwrap();
+        let filter = Filter::from_str("genre = horror OR year > 2000").unwrap().unwrap();
+        let docids =
+            filter.evaluate_with_fields(&rtxn, &index, &without_year, &filterable_fields).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([0, 1]));
+    }
+
+    #[test]
+    fn evaluate_with_tolerances() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("weight") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 10.0, "weight": 1.5 },
+                { "id": 1, "price": 10.004, "weight": 1.504 },
+                { "id": 2, "price": 9.997, "weight": 1.497 },
+                { "id": 3, "price": 10.008, "weight": 1.5 },
+                { "id": 4, "price": 20.001, "weight": 2.0 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let tolerances = HashMap::from([(S("price"), 0.005)]);
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate_with_tolerances(&rtxn, &index, &tolerances).unwrap()
+        };
+
+        // the prices are compared up to half a cent
+        assert_eq!(evaluate("price = 10"), RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(evaluate("price != 10"), RoaringBitmap::from_iter([3, 4]));
+        assert_eq!(evaluate("price IN [10, 20]"), RoaringBitmap::from_iter([0, 1, 2, 4]));
+        assert_eq!(evaluate("NOT price = 10.008 AND price < 15"), RoaringBitmap::from_iter([0, 2]));
+
+        // but the weights exactly
+        assert_eq!(evaluate("weight = 1.5"), RoaringBitmap::from_iter([0, 3]));
+        assert_eq!(evaluate("weight IN [1.5, 2]"), RoaringBitmap::from_iter([0, 3, 4]));
+
+        // as are the numbers written as strings
+        assert_eq!(evaluate("price = '10.004'"), RoaringBitmap::new());
+
+        let filter = Filter::from_str("price = 10").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+    }
+
+    #[test]
+    fn evaluate_explained() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("genre = horror").unwrap().unwrap();
+        let (docids, reason) = filter.evaluate_explained(&rtxn, &index).unwrap();
+        assert!(docids.is_empty());
+        assert_eq!(reason, Some(EmptyReason::NoDocuments));
+        drop(rtxn);
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 2010 },
+                { "id": 1, "genre": "horror", "year": 2015 },
+                { "id": 2, "genre": "comedy", "year": 1995 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let explain = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (docids, reason) = filter.evaluate_explained(&rtxn, &index).unwrap();
+            let reason = reason.map(|reason| match reason {
+                EmptyReason::NoDocuments => S("no documents"),
+                EmptyReason::EmptyLeaf(condition) => format!("empty leaf {condition}"),
+                EmptyReason::EmptyIntersection(condition) => {
+                    format!("empty intersection {condition}")
+                }
+                EmptyReason::FullNegation(condition) => format!("full negation {condition}"),
+            });
+            (docids, reason)
+        };
+
+        assert_eq!(explain("genre = horror"), (RoaringBitmap::from_iter([0, 1]), None));
+        assert_eq!(
+            explain("genre = thriller"),
+            (RoaringBitmap::new(), Some(S("empty leaf {genre} = {thriller}")))
+        );
+        assert_eq!(
+            explain("genre = horror AND year < 2000"),
+            (
+                RoaringBitmap::new(),
+                Some(S("empty intersection AND[{genre} = {horror}, {year} < {2000}, ]"))
+            )
+        );
+        assert_eq!(
+            explain("genre = horror AND (year > 3000 OR genre = thriller)"),
+            (RoaringBitmap::new(), Some(S("empty leaf {year} > {3000}")))
+        );
+        assert_eq!(
+            explain("NOT year > 1990"),
+            (RoaringBitmap::new(), Some(S("full negation {year} > {1990}")))
+        );
+    }
+
+    #[test]
+    fn evaluate_emptying_operand() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("year"), S("rating") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 2010, "rating": 4 },
+                { "id": 1, "genre": "horror", "year": 2015, "rating": 2 },
+                { "id": 2, "genre": "comedy", "year": 1995, "rating": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let emptying = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (docids, operand) = filter.evaluate_emptying_operand(&rtxn, &index).unwrap();
+            (docids, operand.map(|(position, condition)| (position, condition.to_string())))
+        };
+
+        // each clause matches some documents, but none are left after the year
+        assert_eq!(
+            emptying("genre = horror AND year < 2000 AND rating > 1"),
+            (RoaringBitmap::new(), Some((1, S("{year} < {2000}"))))
+        );
+        assert_eq!(
+            emptying("genre = horror AND (year < 2000 OR rating < 3) AND rating > 3"),
+            (RoaringBitmap::new(), Some((2, S("{rating} > {3}"))))
+        );
+        assert_eq!(
+            emptying("genre = horror AND year > 2000 AND rating > 1"),
+            (RoaringBitmap::from_iter([0, 1]), None)
+        );
+        assert_eq!(emptying("genre = thriller"), (RoaringBitmap::new(), None));
+    }
+
+    #[test]
+    fn value_functions() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name"), S("title") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "Foo", "title": "Hello world" },
+                { "id": 1, "name": "FOO BAR", "title": "Hi" },
+                { "id": 2, "name": "bar", "title": "Short" },
+                { "id": 3, "name": 42, "title": "Lengthy" },
+                { "id": 4, "title": ["ab", "abcdefg"] },
+                { "id": 5, "name": "  Foo Bar " },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(evaluate("LOWER(name) = \"foo\""), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate("UPPER(name) = FOO"), RoaringBitmap::from_iter([0]));
+        assert!(evaluate("UPPER(name) = foo").is_empty());
+        assert_eq!(evaluate("TRIM(name) = 'foo bar'"), RoaringBitmap::from_iter([1, 5]));
+        assert_eq!(evaluate("LOWER(name) != foo"), RoaringBitmap::from_iter([1, 2, 3, 4, 5]));
+        assert_eq!(evaluate("LOWER(name) > c"), RoaringBitmap::from_iter([0, 1, 5]));
+
+        // the compared value is normalized like the indexed values for `LOWER` and `TRIM`
+        assert_eq!(evaluate("TRIM(name) = 'Foo Bar'"), evaluate("name = 'Foo Bar'"));
+        assert_eq!(evaluate("TRIM(name) = ' Foo Bar'"), RoaringBitmap::from_iter([1, 5]));
+        assert_eq!(evaluate("LOWER(name) = FOO"), RoaringBitmap::from_iter([0]));
+
+        assert_eq!(evaluate("LENGTH(title) > 5"), RoaringBitmap::from_iter([0, 3, 4]));
+        assert_eq!(evaluate("LENGTH(title) <= 2"), RoaringBitmap::from_iter([1, 4]));
+        assert_eq!(
+            evaluate("LENGTH(title) = 5 AND LOWER(name) = bar"),
+            RoaringBitmap::from_iter([2])
+        );
+
+        let filter = Filter::from_str("LENGTH(title) > five").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("invalid float literal"), "{}", error);
+    }
+
+    #[test]
+    fn between_count() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("tags") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "tags": ["rust"] },
+                { "id": 1, "tags": ["rust", "search", 2023] },
+                { "id": 2, "tags": ["a", "b", "c", "d", "e", "f"] },
+                { "id": 3, "title": "no tags" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(evaluate("tags BETWEEN_COUNT 2 TO 5"), RoaringBitmap::from_iter([1]));
+        assert_eq!(evaluate("tags BETWEEN_COUNT 1 TO 6"), RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(evaluate("tags BETWEEN_COUNT 0 TO 1"), RoaringBitmap::from_iter([0, 3]));
+        assert_eq!(evaluate("NOT tags BETWEEN_COUNT 2 TO 5"), RoaringBitmap::from_iter([0, 2, 3]));
+        assert!(evaluate("tags BETWEEN_COUNT 5 TO 2").is_empty());
+    }
+
+    #[test]
+    fn evaluate_with_values_count() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("brand"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "brand": "Acme", "price": 5 },
+                { "id": 1, "brand": "acme", "price": 15 },
+                { "id": 2, "brand": "Acorn", "price": 8 },
+                { "id": 3, "brand": "Apex", "price": 20 },
+                { "id": 4, "brand": ["Bolt", "Acme"], "price": 3 },
+                { "id": 5, "brand": "Bolt", "price": 30 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (docids, values) = filter.evaluate_with_values_count(&rtxn, &index).unwrap();
+            (docids.len(), values)
+        };
+
+        // a single value, matching the documents of both cases
+        assert_eq!(evaluate("brand = ACME"), (3, 1));
+        // the values starting with `ac`
+        assert_eq!(evaluate("brand LIKE 'Ac%'"), (4, 2));
+        assert_eq!(evaluate("brand LIKE 'a%' AND price < 10"), (3, 2));
+        // `apex` has no document below 10
+        assert_eq!(evaluate("brand IN [acme, apex, bolt] AND price < 10"), (2, 2));
+        assert_eq!(evaluate("brand = missing"), (0, 0));
+        assert_eq!(evaluate("price > 10"), (3, 0));
+    }
+
+    #[test]
+    fn integer_and_fractional() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 10.0 },
+                { "id": 1, "price": 10.5 },
+                { "id": 2, "price": 3 },
+                { "id": 3, "price": -0.25 },
+                { "id": 4, "price": [7, 7.99] },
+                { "id": 5, "price": "12" },
+                { "id": 6 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(evaluate("price IS_INTEGER"), RoaringBitmap::from_iter([0, 2, 4]));
+        assert_eq!(evaluate("price IS_FRACTIONAL"), RoaringBitmap::from_iter([1, 3, 4]));
+        assert_eq!(evaluate("NOT price IS_FRACTIONAL"), RoaringBitmap::from_iter([0, 2, 5, 6]));
+        assert_eq!(evaluate("price IS_FRACTIONAL AND price > 0"), RoaringBitmap::from_iter([1, 4]));
+    }
+
+    #[test]
+    fn window() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("docid"));
+                settings.set_filterable_fields(hashset! { S("id"), S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..300u32)
+            .map(|id| serde_json::json!({ "docid": id, "id": id * 2, "price": id as f64 + 0.5 }))
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map_err(|e| e.to_string())
+        };
+
+        // the ids 100 to 148 of the window are the documents 50 to 74
+        assert_eq!(evaluate("id FROM 100 LIMIT 50").unwrap(), RoaringBitmap::from_iter(50..75));
+        assert_eq!(evaluate("id FROM 99 LIMIT 2").unwrap(), RoaringBitmap::from_iter([50]));
+        assert_eq!(evaluate("id FROM 590 LIMIT 100").unwrap(), RoaringBitmap::from_iter(295..300));
+        assert!(evaluate("id FROM 100 LIMIT 0").unwrap().is_empty());
+
+        let error = evaluate("id FROM 100 LIMIT -1").unwrap_err();
+        assert!(error.starts_with("`-1` is not valid in a `FROM` window."), "{}", error);
+        let error = evaluate("id FROM 1.5 LIMIT 10").unwrap_err();
+        assert!(error.starts_with("`1.5` is not valid in a `FROM` window."), "{}", error);
+        let error = evaluate("price FROM 100 LIMIT 50").unwrap_err();
+        assert!(error.starts_with("Attribute `price` contains floats"), "{}", error);
+    }
+
+    #[test]
+    fn and_restricts_ranges_to_selected_documents() {
+        #[derive(Default)]
+        struct ReadsMetrics {
+            reads: HashMap<FieldId, u64>,
+        }
+
+        impl FacetMetrics for ReadsMetrics {
+            fn database_read(&mut self, field_id: FieldId) {
+                *self.reads.entry(field_id).or_default() += 1;
+            }
+        }
+
+        let index = TempIndex::new_with_map_size(4096 * 1000);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("x"), S("y"), S("z") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..1000)
+            .map(|id| {
+                serde_json::json!({ "id": id, "x": id, "y": (id * 37) % 1000, "z": id })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+        index
+            .add_documents(crate::documents::documents_batch_reader_from_objects(documents))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let z = index.fields_ids_map(&rtxn).unwrap().id("z").unwrap();
+
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let mut metrics = ReadsMetrics::default();
+            let docids = filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap();
+            (docids, metrics.reads)
+        };
+
+        for filter in [
+            "x 100 TO 400 AND y 500 TO 900",
+            "y > 10 AND x < 20 AND y <= 990",
+            "x >= 990 AND NOT y < 500 AND y (*, 800]",
+            "x 10 TO 20 AND z 15 TO 25 AND y > 0",
+            "x < 20 AND z 500 TO 998",
+        ] {
+            let naive = filter
+                .split(" AND ")
+                .map(|condition| evaluate(condition).0)
+                .reduce(|left, right| left & right)
+                .unwrap();
+            assert_eq!(evaluate(filter).0, naive, "{filter}");
+        }
+
+        // the groups of `z` values around the bounds contain none of the selected documents,
+        // they are not descended into
+        let (docids, reads) = evaluate("x < 20 AND z 500 TO 998");
+        assert!(docids.is_empty());
+        assert!(reads[&z] < evaluate("z 500 TO 998").1[&z]);
+    }
+
+    #[test]
+    fn equal_matches_single_value_range() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        // enough distinct values for the facet database to build several levels
+        let docs: Vec<_> = (0..1000)
+            .map(|i| serde_json::json!({ "id": i, "price": (i % 250) as f64 / 2.0 }))
+            .collect();
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        for value in ["0", "12", "12.5", "57", "124.5", "125", "-1", "1000"] {
+            let equal = Filter::from_str(&format!("price = {value}")).unwrap().unwrap();
+            let range = Filter::from_str(&format!("price {value} TO {value}")).unwrap().unwrap();
+            let expected = range.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(equal.evaluate(&rtxn, &index).unwrap(), expected, "price = {}", value);
+        }
+
+        let filter = Filter::from_str("price = 12.5").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([25, 275, 525, 775]));
+    }
+
+    #[test]
+    fn null_safe_equality() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "blue" },
+                { "id": 1, "color": null },
+                { "id": 2 },
+                { "id": 3, "color": "" },
+                { "id": 4, "color": "NULL" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the null keyword only matches the documents without the field, a `null` value exists
+        let filter = Filter::from_str("color <=> NULL").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([2]));
+
+        // a normal value never matches the documents without the field
+        let filter = Filter::from_str("color <=> blue").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        // a quoted NULL is a normal value
+        let filter = Filter::from_str("color <=> 'NULL'").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([4]));
+
+        let filter = Filter::from_str("NOT color <=> NULL").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 1, 3, 4]));
+    }
+
+    #[test]
+    fn case_folded_string_range() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("name"), S("age") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "apple", "age": 1 },
+                { "id": 1, "name": "Mango", "age": 2 },
+                { "id": 2, "name": "Banana", "age": 3 },
+                { "id": 3, "name": "nectarine", "age": 4 },
+                { "id": 4, "name": "Zucchini", "age": 5 },
+                { "id": 5, "age": 6 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let upper = Filter::from_str("name \"A\" TO \"M\"").unwrap().unwrap();
+        let lower = Filter::from_str("name a TO m").unwrap().unwrap();
+        let upper = upper.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(upper, RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(upper, lower.evaluate(&rtxn, &index).unwrap());
+
+        let filter = Filter::from_str("name b TO N").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([1, 2, 3]));
+
+        let filter = Filter::from_str("name Banana TO Banana").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([2]));
+
+        // the upper bound includes the values it prefixes, but not the ones it only sorts before
+        let filter = Filter::from_str("name a TO ban").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2]));
+        let filter = Filter::from_str("name ban TO ban").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([2]));
+        let filter = Filter::from_str("name a TO bananas").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2]));
+        let filter = Filter::from_str("name a TO b").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0, 2]));
+        let filter = Filter::from_str("name a TO apples").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("name z TO a").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.is_empty());
+
+        // numeric bounds are still rejected on string fields, and string bounds on numeric fields
+        let filter = Filter::from_str("name 1 TO 5").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains("the `TO` operator can only be applied to numbers"));
+
+        let filter = Filter::from_str("age a TO m").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+    }
+
+    #[test]
+    fn optimize_and_operands() {
+        #[derive(Default)]
+        struct CountingMetrics {
+            reads: u64,
+        }
+
+        impl FacetMetrics for CountingMetrics {
+            fn database_read(&mut self, _field_id: FieldId) {
+                self.reads += 1;
+            }
+        }
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("color") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..200)
+            .map(|i| {
+                let color = if i % 50 == 0 { "red" } else { "blue" };
+                serde_json::json!({ "id": i, "price": i, "color": color })
+            })
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price > 10 AND color = red").unwrap().unwrap();
+        assert_eq!(filter.estimate(&rtxn, &index).unwrap(), 4);
+
+        let mut optimized = filter.clone();
+        optimized.optimize(&rtxn, &index).unwrap();
+        let condition = FilterCondition::from(optimized.clone());
+        insta::assert_display_snapshot!(condition, @"AND[{color} = {red}, {price} > {10}, ]");
+
+        for filter in [
+            "price > 10 AND color = red",
+            "price 10 TO 150 AND (color = blue OR price < 5) AND NOT color = red",
+            "color != red AND price >= 100 AND price IN [100, 150, 151]",
+            "price > 10 AND color = green",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let mut optimized = filter.clone();
+            optimized.optimize(&rtxn, &index).unwrap();
+            assert_eq!(
+                optimized.evaluate(&rtxn, &index).unwrap(),
+                filter.evaluate(&rtxn, &index).unwrap()
+            );
+        }
+
+        // when the most selective operand matches nothing, the others are not evaluated anymore
+        let filter = Filter::from_str("price > 10 AND color = green").unwrap().unwrap();
+        let mut optimized = filter.clone();
+        optimized.optimize(&rtxn, &index).unwrap();
+
+        let mut original_metrics = CountingMetrics::default();
+        filter.evaluate_with_metrics(&rtxn, &index, &mut original_metrics).unwrap();
+        let mut optimized_metrics = CountingMetrics::default();
+        optimized.evaluate_with_metrics(&rtxn, &index, &mut optimized_metrics).unwrap();
+        assert!(optimized_metrics.reads < original_metrics.reads);
+    }
+
+    #[test]
+    fn interval() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> =
+            (0..30).map(|i| serde_json::json!({ "id": i, "price": i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        for (interval, expected) in [
+            ("price [10, 20]", 10..=20),
+            ("price [10, 20)", 10..=19),
+            ("price (10, 20]", 11..=20),
+            ("price (10, 20)", 11..=19),
+            ("price [*, 5)", 0..=4),
+            ("price (25, ]", 26..=29),
+            ("price [, *]", 0..=29),
+        ] {
+            let filter = Filter::from_str(interval).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, expected.collect::<RoaringBitmap>(), "{}", interval);
+        }
+
+        // the intervals are the same as the comparison operators
+        let interval = Filter::from_str("price [10, 20)").unwrap().unwrap();
+        let comparisons = Filter::from_str("price >= 10 AND price < 20").unwrap().unwrap();
+        assert_eq!(
+            interval.evaluate(&rtxn, &index).unwrap(),
+            comparisons.evaluate(&rtxn, &index).unwrap()
+        );
+
+        let filter = Filter::from_str("price (20, 10)").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).unwrap().is_empty());
+
+        let filter = Filter::from_str("price [10, twenty]").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("invalid float literal"), "{}", error);
+    }
+
+    #[test]
+    fn evaluate_within() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> =
+            (0..20).map(|i| serde_json::json!({ "id": i, "price": i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the documents 10 to 19 would match but only the even ones are allowed
+        let allowed = RoaringBitmap::from_iter((0..20).step_by(2).chain([42]));
+
+        let filter = Filter::from_str("price >= 10").unwrap().unwrap();
+        let docids = filter.evaluate_within(&rtxn, &index, &allowed).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([10, 12, 14, 16, 18]));
+
+        let filter = Filter::from_str("NOT price >= 10").unwrap().unwrap();
+        let docids = filter.evaluate_within(&rtxn, &index, &allowed).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([0, 2, 4, 6, 8]));
+
+        let filter = Filter::from_str("NOT price = 3 OR price = 4").unwrap().unwrap();
+        let docids = filter.evaluate_within(&rtxn, &index, &allowed).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter((0..20).step_by(2)));
+
+        let filter = Filter::from_str("price = 3").unwrap().unwrap();
+        assert!(filter.evaluate_within(&rtxn, &index, &allowed).unwrap().is_empty());
+        let filter = Filter::from_str("price >= 0").unwrap().unwrap();
+        assert!(filter.evaluate_within(&rtxn, &index, &RoaringBitmap::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn evaluate_partition() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> =
+            (0..20).map(|i| serde_json::json!({ "id": i, "price": i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price >= 10").unwrap().unwrap();
+        let candidates = RoaringBitmap::from_iter([1, 5, 10, 12, 19, 42]);
+        let (matched, excluded) = filter.evaluate_partition(&rtxn, &index, &candidates).unwrap();
+        assert_eq!(matched, RoaringBitmap::from_iter([10, 12, 19]));
+        assert_eq!(excluded, RoaringBitmap::from_iter([1, 5, 42]));
+        assert!(matched.is_disjoint(&excluded));
+        assert_eq!(matched | excluded, candidates);
+
+        let (matched, excluded) =
+            filter.evaluate_partition(&rtxn, &index, &RoaringBitmap::new()).unwrap();
+        assert!(matched.is_empty() && excluded.is_empty());
+    }
+
+    #[test]
+    fn filter_number() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("id"), S("one"), S("two") });
+            })
+            .unwrap();
+
+        let mut docs = vec![];
+        for i in 0..100 {
+            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
+        }
+
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for i in 0..100 {
+            let filter_str = format!("id = {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter([i]));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id > {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter((i + 1)..100));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id < {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(0..i));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id <= {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(0..=i));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id >= {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(i..100));
+        }
+        for i in 0..100 {
+            for j in i..100 {
+                let filter_str = format!("id {i} TO {j}");
+                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+                let result = filter.evaluate(&rtxn, &index).unwrap();
+                assert_eq!(result, RoaringBitmap::from_iter(i..=j));
+            }
+        }
+        let filter = Filter::from_str("one >= 0 OR one <= 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::default());
+
+        let filter = Filter::from_str("one = 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::default());
+
+        for i in 0..10 {
+            for j in i..10 {
+                let filter_str = format!("two {i} TO {j}");
+                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+                let result = filter.evaluate(&rtxn, &index).unwrap();
+                assert_eq!(
+                    result,
+                    RoaringBitmap::from_iter((0..100).filter(|x| (i..=j).contains(&(x % 10))))
+                );
+            }
+        }
+        let filter = Filter::from_str("two != 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn evaluate_prefetched() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 5 },
+                { "id": 1, "color": "blue", "price": 15 },
+                { "id": 2 },
+                { "id": 3, "color": ["red", "blue"], "price": [5, 15] },
+                { "id": 4, "color": null, "price": null },
+                { "id": 5, "color": "green", "price": 25 },
+            ]))
+            .unwrap();
 
-                    let xyz_base_point = lat_lng_to_xyz(&base_point);
+        let rtxn = index.read_txn().unwrap();
+        for expression in [
+            "color = red",
+            "color = red OR price > 10",
+            "color = red AND price > 10",
+            "color = purple AND price > 10",
+            "NOT (color = red OR color = blue) AND price EXISTS",
+            "(color IN [red, green] OR price 10 TO 20) AND NOT color IS NULL",
+            "- price > 10 OR (color = green AND price < 30)",
+            "_docid IN [1, 2] OR (_docid > 3 AND color = green)",
+            "color = red OR color = blue OR color = green OR price = 5 OR price = 25",
+        ] {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.document_id_field("_docid");
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            let prefetched = filter.evaluate_prefetched(&rtxn, &index).unwrap();
+            assert_eq!(prefetched, expected, "{expression}");
+        }
 
-                    let result = rtree
-                        .nearest_neighbor_iter(&xyz_base_point)
-                        .take_while(|point| {
-                            distance_between_two_points(&base_point, &point.data.1)
-                                <= radius + f64::EPSILON
-                        })
-                        .map(|point| point.data.0)
-                        .collect();
+        // the errors of the leaves are returned like by `evaluate`
+        let filter = Filter::from_str("color = red OR doggo = 4").unwrap().unwrap();
+        assert!(filter.evaluate_prefetched(&rtxn, &index).is_err());
 
-                    Ok(result)
-                } else {
-                    Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: "_geo",
-                        filterable_fields: filterable_fields.clone(),
-                    }))?
-                }
-            }
-            FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
-                if filterable_fields.contains("_geo") {
-                    let top_right: [f64; 2] = [
-                        top_right_point[0].parse_finite_float()?,
-                        top_right_point[1].parse_finite_float()?,
-                    ];
-                    let bottom_left: [f64; 2] = [
-                        bottom_left_point[0].parse_finite_float()?,
-                        bottom_left_point[1].parse_finite_float()?,
-                    ];
-                    if !(-90.0..=90.0).contains(&top_right[0]) {
-                        return Err(
-                            top_right_point[0].as_external_error(BadGeoError::Lat(top_right[0]))
-                        )?;
-                    }
-                    if !(-180.0..=180.0).contains(&top_right[1]) {
-                        return Err(
-                            top_right_point[1].as_external_error(BadGeoError::Lng(top_right[1]))
-                        )?;
-                    }
-                    if !(-90.0..=90.0).contains(&bottom_left[0]) {
-                        return Err(bottom_left_point[0]
-                            .as_external_error(BadGeoError::Lat(bottom_left[0])))?;
-                    }
-                    if !(-180.0..=180.0).contains(&bottom_left[1]) {
-                        return Err(bottom_left_point[1]
-                            .as_external_error(BadGeoError::Lng(bottom_left[1])))?;
-                    }
-                    if top_right[0] < bottom_left[0] {
-                        return Err(bottom_left_point[1].as_external_error(
-                            BadGeoError::BoundingBoxTopIsBelowBottom(top_right[0], bottom_left[0]),
-                        ))?;
-                    }
+        // the threads borrow their transactions from the pool
+        let pool = ReadTxnPool::new(&index);
+        let filter =
+            Filter::from_str("color = red OR price > 10 OR color = green").unwrap().unwrap();
+        let expected = filter.evaluate(&rtxn, &index).unwrap();
+        for _ in 0..3 {
+            let pooled = filter.evaluate_prefetched_pooled(&rtxn, &index, &pool).unwrap();
+            assert_eq!(pooled, expected);
+        }
+        assert!(!pool.is_empty());
+    }
 
-                    // Instead of writing a custom `GeoBoundingBox` filter we're simply going to re-use the range
-                    // filter to create the following filter;
-                    // `_geo.lat {top_right[0]} TO {bottom_left[0]} AND _geo.lng {top_right[1]} TO {bottom_left[1]}`
-                    // As we can see, we need to use a bunch of tokens that don't exist in the original filter,
-                    // thus we're going to create tokens that point to a random span but contain our text.
+    #[test]
+    fn evaluate_pooled() {
+        let index = TempIndex::new();
 
-                    let geo_lat_token = Token::new(
-                        top_right_point[0].original_span(),
-                        Some("_geo.lat".to_string()),
-                    );
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("price") });
+            })
+            .unwrap();
 
-                    let condition_lat = FilterCondition::Condition {
-                        fid: geo_lat_token,
-                        op: Condition::Between {
-                            from: bottom_left_point[0].clone(),
-                            to: top_right_point[0].clone(),
-                        },
-                    };
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 5 },
+                { "id": 1, "color": "blue", "price": 15 },
+                { "id": 2, "color": ["red", "green"], "price": 25 },
+                { "id": 3 },
+            ]))
+            .unwrap();
 
-                    let selected_lat = Filter { condition: condition_lat }.inner_evaluate(
-                        rtxn,
-                        index,
-                        filterable_fields,
-                    )?;
+        let pool = ReadTxnPool::new(&index);
+        let fresh = || -> crate::Result<_> { Ok(index.read_txn()?) };
+        let rtxn = index.read_txn().unwrap();
+        for expression in ["color = red", "price > 10 AND NOT color = blue", "color = purple"] {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            let direct = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(filter.evaluate_pooled(&index, &pool).unwrap(), direct, "{expression}");
+            assert_eq!(filter.evaluate_pooled(&index, &fresh).unwrap(), direct, "{expression}");
+        }
+        // the transaction is given back and reused, even when the evaluation fails
+        assert_eq!(pool.len(), 1);
+        let filter = Filter::from_str("doggo = 4").unwrap().unwrap();
+        assert!(filter.evaluate_pooled(&index, &pool).is_err());
+        assert_eq!(pool.len(), 1);
 
-                    let geo_lng_token = Token::new(
-                        top_right_point[1].original_span(),
-                        Some("_geo.lng".to_string()),
-                    );
-                    let selected_lng = if top_right[1] < bottom_left[1] {
-                        // In this case the bounding box is wrapping around the earth (going from 180 to -180).
-                        // We need to update the lng part of the filter from;
-                        // `_geo.lng {top_right[1]} TO {bottom_left[1]}` to
-                        // `_geo.lng {bottom_left[1]} TO 180 AND _geo.lng -180 TO {top_right[1]}`
+        // the pooled transactions don't see the later updates until the pool is cleared
+        drop(rtxn);
+        index.add_documents(documents!([{ "id": 4, "color": "red" }])).unwrap();
+        let filter = Filter::from_str("color = red").unwrap().unwrap();
+        assert_eq!(filter.evaluate_pooled(&index, &pool).unwrap().len(), 2);
+        pool.clear();
+        assert_eq!(filter.evaluate_pooled(&index, &pool).unwrap().len(), 3);
+    }
 
-                        let min_lng_token = Token::new(
-                            top_right_point[1].original_span(),
-                            Some("-180.0".to_string()),
-                        );
-                        let max_lng_token = Token::new(
-                            top_right_point[1].original_span(),
-                            Some("180.0".to_string()),
-                        );
+    #[test]
+    fn date_field() {
+        let index = TempIndex::new();
 
-                        let condition_left = FilterCondition::Condition {
-                            fid: geo_lng_token.clone(),
-                            op: Condition::Between {
-                                from: bottom_left_point[1].clone(),
-                                to: max_lng_token,
-                            },
-                        };
-                        let left = Filter { condition: condition_left }.inner_evaluate(
-                            rtxn,
-                            index,
-                            filterable_fields,
-                        )?;
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("created") });
+            })
+            .unwrap();
 
-                        let condition_right = FilterCondition::Condition {
-                            fid: geo_lng_token,
-                            op: Condition::Between {
-                                from: min_lng_token,
-                                to: top_right_point[1].clone(),
-                            },
-                        };
-                        let right = Filter { condition: condition_right }.inner_evaluate(
-                            rtxn,
-                            index,
-                            filterable_fields,
-                        )?;
+        // 2021-03-01T00:00:00Z
+        let midnight = 1614556800;
+        index
+            .add_documents(documents!([
+                { "id": 0, "created": midnight - 3600 },
+                { "id": 1, "created": midnight + 3600 },
+                { "id": 2, "created": midnight + 23 * 3600 },
+                { "id": 3, "created": midnight + 25 * 3600 },
+            ]))
+            .unwrap();
 
-                        left | right
-                    } else {
-                        let condition_lng = FilterCondition::Condition {
-                            fid: geo_lng_token,
-                            op: Condition::Between {
-                                from: bottom_left_point[1].clone(),
-                                to: top_right_point[1].clone(),
-                            },
-                        };
-                        Filter { condition: condition_lng }.inner_evaluate(
-                            rtxn,
-                            index,
-                            filterable_fields,
-                        )?
-                    };
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str, offset: UtcOffset| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.date_field("created", offset).evaluate(&rtxn, &index).unwrap()
+        };
+        let utc = UtcOffset::UTC;
+        let east = UtcOffset::from_hms(2, 0, 0).unwrap();
+        let west = UtcOffset::from_hms(-5, 0, 0).unwrap();
 
-                    Ok(selected_lat & selected_lng)
-                } else {
-                    Err(top_right_point[0].as_external_error(
-                        FilterError::AttributeNotFilterable {
-                            attribute: "_geo",
-                            filterable_fields: filterable_fields.clone(),
-                        },
-                    ))?
-                }
-            }
-        }
+        assert_eq!(evaluate("created = \"2021-03-01\"", utc), RoaringBitmap::from_iter([1, 2]));
+        // the local day starts on the previous UTC day
+        assert_eq!(evaluate("created = \"2021-03-01\"", east), RoaringBitmap::from_iter([0, 1]));
+        // the local day ends on the next UTC day
+        assert_eq!(evaluate("created = 2021-03-01", west), RoaringBitmap::from_iter([2, 3]));
+
+        assert_eq!(evaluate("created != \"2021-03-01\"", east), RoaringBitmap::from_iter([2, 3]));
+        assert_eq!(evaluate("created > \"2021-03-01\"", east), RoaringBitmap::from_iter([2, 3]));
+        assert_eq!(evaluate("created <= \"2021-02-28\"", west), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(
+            evaluate("created \"2021-02-28\" TO \"2021-03-01\"", west),
+            RoaringBitmap::from_iter([0, 1, 2, 3])
+        );
+        assert_eq!(evaluate("created IN [2021-02-28]", west), RoaringBitmap::from_iter([0, 1]));
+
+        // the timestamps are still compared as they are written
+        let expression = format!("created < {}", midnight);
+        assert_eq!(evaluate(&expression, east), RoaringBitmap::from_iter([0]));
     }
-}
 
-impl<'a> From<FilterCondition<'a>> for Filter<'a> {
-    fn from(fc: FilterCondition<'a>) -> Self {
-        Self { condition: fc }
+    #[test]
+    fn evaluate_traced() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("price") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 5 },
+                { "id": 1, "color": "blue", "price": 15 },
+                { "id": 2, "color": "green", "price": 25 },
+                { "id": 3, "color": "red", "price": 30 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter =
+            Filter::from_str("(color = red AND price < 10) OR NOT price <= 20").unwrap().unwrap();
+        let (docids, leaves) = filter.evaluate_traced(&rtxn, &index).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([0, 2, 3]));
+
+        let leaves: Vec<_> =
+            leaves.into_iter().map(|(leaf, docids)| (leaf.field(), leaf.kind(), docids)).collect();
+        assert_eq!(
+            leaves,
+            vec![
+                ("color", LeafKind::Equal, RoaringBitmap::from_iter([0, 3])),
+                ("price", LeafKind::LowerThan, RoaringBitmap::from_iter([0])),
+                ("price", LeafKind::LowerThanOrEqual, RoaringBitmap::from_iter([0, 1])),
+            ]
+        );
+        // the document 0 went through the `AND`, as both of its leaves contain it
+        assert!(leaves[0].2.contains(0) && leaves[1].2.contains(0));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fmt::Write;
-    use std::iter::FromIterator;
+    #[test]
+    fn in_ignores_case() {
+        let index = TempIndex::new();
 
-    use big_s::S;
-    use either::Either;
-    use maplit::hashset;
-    use roaring::RoaringBitmap;
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre") });
+            })
+            .unwrap();
 
-    use crate::index::tests::TempIndex;
-    use crate::Filter;
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "Horror" },
+                { "id": 1, "genre": "COMEDY" },
+                { "id": 2, "genre": ["drama", "Comedy"] },
+                { "id": 3, "genre": "Science-Fiction" },
+                { "id": 4, "genre": "Épouvante" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str, order: InListOrder, cache: bool| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.in_list_order(order).cache_string_lookups(cache);
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        // the stored and the given strings are both normalized before being compared
+        for (order, cache) in [(InListOrder::Any, false), (InListOrder::Given, true)] {
+            let expected = RoaringBitmap::from_iter([0, 1, 2]);
+            assert_eq!(evaluate(r#"genre IN ["Horror", "comedy"]"#, order, cache), expected);
+            assert_eq!(evaluate("genre IN [hORROR, CoMeDy]", order, cache), expected);
+            assert_eq!(
+                evaluate("genre IN [SCIENCE-FICTION, épouvante]", order, cache),
+                RoaringBitmap::from_iter([3, 4])
+            );
+            assert_eq!(
+                evaluate("genre NOT IN [DRAMA, horror]", order, cache),
+                RoaringBitmap::from_iter([1, 3, 4])
+            );
+        }
+    }
 
     #[test]
-    fn empty_db() {
+    fn not_equal_ignores_case() {
         let index = TempIndex::new();
-        //Set the filterable fields to be the channel.
+
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(hashset! { S("PrIcE") });
+                settings.set_filterable_fields(hashset! { S("brand") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "brand": "Nike" },
+                { "id": 1, "brand": "NIKE" },
+                { "id": 2, "brand": "nike" },
+                { "id": 3, "brand": "Adidas" },
+                { "id": 4, "brand": ["Puma", "NiKe"] },
+                { "id": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str, cache: bool| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.cache_string_lookups(cache);
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        // every case of the value is excluded, and the documents without a brand are kept
+        for cache in [false, true] {
+            let expected = RoaringBitmap::from_iter([3, 5]);
+            assert_eq!(evaluate(r#"brand != "nike""#, cache), expected);
+            assert_eq!(evaluate("brand != NIKE", cache), expected);
+            assert_eq!(
+                evaluate("brand != nIkE AND brand != adidas", cache),
+                RoaringBitmap::from_iter([5])
+            );
+            assert_eq!(
+                evaluate("brand != ADIDAS", cache),
+                RoaringBitmap::from_iter([0, 1, 2, 4, 5])
+            );
+            assert_eq!(evaluate("brand != puma", cache), RoaringBitmap::from_iter([0, 1, 2, 3, 5]));
+        }
+
+        // among the documents with a brand, the one without a brand isn't matched anymore
+        let evaluate = |expression: &str| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.not_equal_present_only(true);
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+        assert_eq!(evaluate(r#"brand != "nike""#), RoaringBitmap::from_iter([3]));
+        assert_eq!(evaluate("brand != NIKE"), RoaringBitmap::from_iter([3]));
+        assert_eq!(evaluate("brand != puma"), RoaringBitmap::from_iter([0, 1, 2, 3]));
+        assert_eq!(evaluate("NOT brand != nike"), RoaringBitmap::from_iter([0, 1, 2, 4, 5]));
+        assert_eq!(evaluate("brand != nike OR brand = nike"), RoaringBitmap::from_iter(0..5));
+    }
+
+    #[test]
+    fn max_database_reads() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("color") });
             })
             .unwrap();
 
+        let documents: Vec<_> = (0..1000)
+            .map(|i| serde_json::json!({ "id": i, "price": i, "color": if i % 2 == 0 { "red" } else { "blue" } }))
+            .map(|document| document.as_object().unwrap().clone())
+            .collect();
+        index
+            .add_documents(crate::documents::documents_batch_reader_from_objects(documents))
+            .unwrap();
+
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str, max: Option<u64>| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.max_database_reads(max).evaluate(&rtxn, &index)
+        };
 
-        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        // the wide range reads many groups of values across the levels
+        let error = evaluate("price 3 TO 997 AND color = red", Some(10)).unwrap_err();
+        assert!(matches!(error, crate::Error::UserError(crate::UserError::FilterBudgetExceeded)));
+        assert_eq!(
+            error.to_string(),
+            "The filter read more facet values than allowed by its budget."
+        );
 
-        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        let docids = evaluate("price 3 TO 997 AND color = red", Some(10_000)).unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter((4..=996).step_by(2)));
+        let docids = evaluate("price 3 TO 997 AND color = red", None).unwrap();
+        assert_eq!(docids.len(), 497);
+
+        // a single value is a single read
+        assert_eq!(evaluate("color = blue", Some(1)).unwrap().len(), 500);
+        assert!(evaluate("color = blue OR color = red", Some(1)).is_err());
+
+        // the evaluations reading the leaves one by one share the budget of the filter
+        let mut filter = Filter::from_str("price 3 TO 997 AND color = red").unwrap().unwrap();
+        filter.max_database_reads(Some(10));
+        let columns = FacetColumns::new();
+        let errors = [
+            filter.evaluate_with_columns(&rtxn, &index, &columns).unwrap_err(),
+            filter.evaluate_with_overlay(&rtxn, &index, &rtxn, &index).unwrap_err(),
+            #[cfg(feature = "parallel")]
+            filter.evaluate_prefetched(&rtxn, &index).unwrap_err(),
+        ];
+        for error in errors {
+            let exceeded = crate::Error::UserError(crate::UserError::FilterBudgetExceeded);
+            assert_eq!(error.to_string(), exceeded.to_string());
+        }
+        let mut filter = Filter::from_str("color = blue OR color = red").unwrap().unwrap();
+        filter.max_database_reads(Some(1));
+        assert!(filter.evaluate_with_columns(&rtxn, &index, &columns).is_err());
     }
 
     #[test]
-    fn from_array() {
-        // Simple array with Left
-        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    fn cached_branches_first() {
+        let index = TempIndex::new();
 
-        // Simple array with Right
-        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
-            .unwrap()
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("price"), S("size") });
+            })
             .unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
 
-        // Array with Left and escaped quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 5, "size": 1 },
+                { "id": 1, "color": "blue", "price": 15, "size": 2 },
+                { "id": 2, "color": "green", "price": 25, "size": 3 },
+            ]))
+            .unwrap();
 
-        // Array with Right and escaped quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        struct MatchedFields(Vec<FieldId>);
 
-        // Array with Left and escaped simple quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        impl FacetMetrics for MatchedFields {
+            fn documents_matched(&mut self, field_id: FieldId, _count: u64) {
+                self.0.push(field_id);
+            }
+        }
 
-        // Array with Right and escaped simple quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let id = |name: &str| fields_ids_map.id(name).unwrap();
 
-        // Simple with parenthesis
-        let condition =
-            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
-        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let expression = "color = red OR price > 20 OR (size = 2 OR color = green) OR size = 1";
+        let mut filter = Filter::from_str(expression).unwrap().unwrap();
+        let expected = filter.evaluate(&rtxn, &index).unwrap();
 
-        // Test that the facet condition is correctly generated.
-        let condition = Filter::from_array(vec![
-            Either::Right("channel = gotaga"),
-            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
-        ])
-        .unwrap()
-        .unwrap();
-        let expected =
-            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
-                .unwrap()
-                .unwrap();
-        assert_eq!(condition, expected);
+        let mut consulted = Vec::new();
+        filter.cached_branches_first(|condition| {
+            consulted.push(condition.to_string());
+            matches!(condition, FilterCondition::Condition { fid, .. } if fid.value() != "color")
+        });
+        // the operands of the nested `OR` are consulted before the nested `OR` itself
+        assert_eq!(
+            consulted,
+            vec![
+                "{color} = {red}",
+                "{price} > {20}",
+                "{size} = {2}",
+                "{color} = {green}",
+                "OR[{size} = {2}, {color} = {green}, ]",
+                "{size} = {1}",
+            ]
+        );
+
+        let mut metrics = MatchedFields(Vec::new());
+        assert_eq!(filter.evaluate_with_metrics(&rtxn, &index, &mut metrics).unwrap(), expected);
+        assert_eq!(expected, RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(metrics.0, vec![id("price"), id("size"), id("color"), id("size"), id("color")]);
     }
 
     #[test]
-    fn not_filterable() {
+    fn boolean_fields() {
         let index = TempIndex::new();
 
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("active"), S("stock"), S("state") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "active": 1, "stock": 0, "state": "true" },
+                { "id": 1, "active": 0, "stock": 1, "state": "false" },
+                { "id": 2, "active": 1, "stock": 2 },
+                { "id": 3 },
+            ]))
+            .unwrap();
+
         let rtxn = index.read_txn().unwrap();
-        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. This index does not have configured filterable attributes."
-        ));
+        let evaluate = |expression: &str| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.boolean_fields(["active"]).evaluate(&rtxn, &index)
+        };
 
-        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert_eq!(evaluate("active = true").unwrap(), RoaringBitmap::from_iter([0, 2]));
+        assert_eq!(evaluate("active = false").unwrap(), RoaringBitmap::from_iter([1]));
+        assert_eq!(evaluate("active != true").unwrap(), RoaringBitmap::from_iter([1, 3]));
+        assert_eq!(evaluate("active IN [false, 2]").unwrap(), RoaringBitmap::from_iter([1]));
+        assert_eq!(
+            evaluate("NOT active = false AND active EXISTS").unwrap(),
+            RoaringBitmap::from_iter([0, 2])
+        );
+        // the quoted values and the string fields are compared as they are written
+        assert_eq!(evaluate("active = \"true\"").unwrap(), RoaringBitmap::new());
+        assert_eq!(evaluate("state = true").unwrap(), RoaringBitmap::from_iter([0]));
+
+        let error = evaluate("stock = true").unwrap_err();
         assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. This index does not have configured filterable attributes."
+            "Attribute `stock` contains numbers and is not boolean-like, it can't be compared with `true`."
         ));
-
-        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let error = evaluate("active = true OR stock IN [1, false]").unwrap_err();
         assert!(error.to_string().starts_with(
-            "Attribute `dog` is not filterable. This index does not have configured filterable attributes."
+            "Attribute `stock` contains numbers and is not boolean-like, it can't be compared with `false`."
         ));
-        drop(rtxn);
+
+        // without boolean-like fields, `true` is a string matching no number
+        let filter = Filter::from_str("stock = true").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::new());
+
+        // the evaluations reading the leaves one by one replace the booleans too
+        let mut columns = FacetColumns::new();
+        columns.load(&rtxn, &index, "active").unwrap();
+        for expression in ["active = true", "NOT active = false AND active EXISTS"] {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.boolean_fields(["active"]);
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(filter.evaluate_with_columns(&rtxn, &index, &columns).unwrap(), expected);
+            let overlaid = filter.evaluate_with_overlay(&rtxn, &index, &rtxn, &index);
+            assert_eq!(overlaid.unwrap(), expected);
+            #[cfg(feature = "parallel")]
+            assert_eq!(filter.evaluate_prefetched(&rtxn, &index).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn units() {
+        let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("title")]);
-                settings.set_filterable_fields(hashset! { S("title") });
+                settings.set_filterable_fields(hashset! { S("size"), S("duration") });
             })
             .unwrap();
 
+        index
+            .add_documents(documents!([
+                { "id": 0, "size": 1000, "duration": 60 },
+                { "id": 1, "size": 6000, "duration": 3600 },
+                { "id": 2, "size": 2097152, "duration": 9000 },
+                { "id": 3, "size": 5120, "duration": 7200 },
+            ]))
+            .unwrap();
+
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str| {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter
+                .units("size", [("kb", 1024), ("mb", 1024 * 1024)])
+                .units("duration", [("s", 1), ("m", 60), ("h", 3600)])
+                .evaluate(&rtxn, &index)
+        };
 
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
-        ));
+        assert_eq!(evaluate("size > 5kb").unwrap(), RoaringBitmap::from_iter([1, 2]));
+        assert_eq!(evaluate("size >= 5kb").unwrap(), RoaringBitmap::from_iter([1, 2, 3]));
+        assert_eq!(evaluate("size = 2mb").unwrap(), RoaringBitmap::from_iter([2]));
+        assert_eq!(evaluate("size 1kb TO 1.5mb").unwrap(), RoaringBitmap::from_iter([1, 3]));
+        assert_eq!(evaluate("duration < 1h").unwrap(), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate("duration <= 60m").unwrap(), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate("duration = 2h").unwrap(), RoaringBitmap::from_iter([3]));
+        assert_eq!(evaluate("duration != 1m").unwrap(), RoaringBitmap::from_iter([1, 2, 3]));
+        assert_eq!(evaluate("duration > 2.5h").unwrap(), RoaringBitmap::new());
+        // the plain numbers are already in the base unit
+        assert_eq!(evaluate("size < 5120").unwrap(), RoaringBitmap::from_iter([0]));
 
-        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let error = evaluate("size > 5gb").unwrap_err();
         assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
+            "Unit `gb` is not a unit of attribute `size`. Available units are: `kb mb`."
         ));
-
-        let filter = Filter::from_str("name = 12").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let error = evaluate("duration > 1h AND size < 5h").unwrap_err();
         assert!(error.to_string().starts_with(
-            "Attribute `name` is not filterable. Available filterable attributes are: `title`."
+            "Unit `h` is not a unit of attribute `size`. Available units are: `kb mb`."
         ));
+
+        // without units, the values are compared as they are written
+        let filter = Filter::from_str("size > 5kb").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+
+        // the evaluations reading the leaves one by one convert the units too
+        let mut columns = FacetColumns::new();
+        columns.load(&rtxn, &index, "size").unwrap();
+        for expression in ["size > 5kb", "size 1kb TO 1.5mb OR duration = 2h"] {
+            let mut filter = Filter::from_str(expression).unwrap().unwrap();
+            filter
+                .units("size", [("kb", 1024), ("mb", 1024 * 1024)])
+                .units("duration", [("h", 3600)]);
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(filter.evaluate_with_columns(&rtxn, &index, &columns).unwrap(), expected);
+            let overlaid = filter.evaluate_with_overlay(&rtxn, &index, &rtxn, &index);
+            assert_eq!(overlaid.unwrap(), expected);
+            #[cfg(feature = "parallel")]
+            assert_eq!(filter.evaluate_prefetched(&rtxn, &index).unwrap(), expected);
+        }
     }
 
     #[test]
-    fn escaped_quote_in_filter_value_2380() {
+    fn numeric_bounds() {
         let index = TempIndex::new();
 
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("title") });
+            })
+            .unwrap();
+
         index
             .add_documents(documents!([
-                {
-                    "id": "test_1",
-                    "monitor_diagonal": "27' to 30'"
-                },
-                {
-                    "id": "test_2",
-                    "monitor_diagonal": "27\" to 30\""
-                },
-                {
-                    "id": "test_3",
-                    "monitor_diagonal": "27\" to 30'"
-                },
+                { "id": 0, "price": 5, "title": "kefir" },
+                { "id": 1, "price": 10, "title": "milk" },
+                { "id": 2, "price": 15.5 },
+                { "id": 3, "price": 20 },
+                { "id": 4 },
             ]))
             .unwrap();
 
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let price = fields_ids_map.id("price").unwrap();
+        let evaluate = |left, right| {
+            let filter = Filter::numeric_bounds(&rtxn, &index, price, left, right).unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        let half_open = evaluate(Included(10.), Excluded(20.));
+        assert_eq!(half_open, RoaringBitmap::from_iter([1, 2]));
+        let half_open = evaluate(Excluded(10.), Included(20.));
+        assert_eq!(half_open, RoaringBitmap::from_iter([2, 3]));
+        let open = evaluate(Excluded(5.), Excluded(20.));
+        assert_eq!(open, RoaringBitmap::from_iter([1, 2]));
+        let unbounded = evaluate(Included(15.), Bound::Unbounded);
+        assert_eq!(unbounded, RoaringBitmap::from_iter([2, 3]));
+        let all = evaluate(Bound::Unbounded, Bound::Unbounded);
+        assert_eq!(all, RoaringBitmap::from_iter([0, 1, 2, 3]));
+        let infinite = evaluate(Included(f64::NEG_INFINITY), Excluded(10.));
+        assert_eq!(infinite, RoaringBitmap::from_iter([0]));
+        assert!(evaluate(Included(f64::NAN), Bound::Unbounded).is_empty());
+
+        // the bounds are the same as the interval notation
+        let filter = Filter::from_str("price (5, 20]").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), evaluate(Excluded(5.), Included(20.)));
+
+        let title = fields_ids_map.id("title").unwrap();
+        let error =
+            Filter::numeric_bounds(&rtxn, &index, title, Included(1.), Excluded(2.)).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `title` only contains string values, but the `interval` operator can only be applied to numbers."
+        ));
+        let unknown = Filter::numeric_bounds(&rtxn, &index, 1000, Included(1.), Excluded(2.));
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn evaluate_with_overlay() {
+        let documents = || {
+            vec![
+                serde_json::json!({ "id": 0, "genre": "horror", "price": 10 }),
+                serde_json::json!({ "id": 1, "genre": "comedy", "price": 20 }),
+            ]
+        };
+
+        let index = TempIndex::new();
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(hashset!(S("monitor_diagonal")));
+                settings.set_filterable_fields(hashset! { S("genre"), S("price") });
+            })
+            .unwrap();
+        index.add_documents(documents!(documents())).unwrap();
+
+        // the overlay is a copy of the index which already received a new document
+        let overlay = TempIndex::new();
+        overlay
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre"), S("price") });
             })
             .unwrap();
+        let mut staged = documents();
+        staged.push(serde_json::json!({ "id": 2, "genre": "Horror", "price": 5 }));
+        overlay.add_documents(documents!(staged)).unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        let overlay_rtxn = overlay.read_txn().unwrap();
+        let evaluate = |expression: &str| {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            let with_overlay =
+                filter.evaluate_with_overlay(&rtxn, &index, &overlay_rtxn, &overlay).unwrap();
+            (docids, with_overlay)
+        };
 
-        let mut search = crate::Search::new(&rtxn, &index);
-        // this filter is copy pasted from #2380 with the exact same espace sequence
-        search.filter(Filter::from_str("monitor_diagonal = '27\" to 30\\''").unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![2]);
+        let (docids, with_overlay) = evaluate("genre = horror");
+        assert_eq!(docids, RoaringBitmap::from_iter([0]));
+        assert_eq!(with_overlay, RoaringBitmap::from_iter([0, 2]));
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27' to 30'" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![0]);
+        let (docids, with_overlay) = evaluate("genre = horror AND price < 8");
+        assert!(docids.is_empty());
+        assert_eq!(with_overlay, RoaringBitmap::from_iter([2]));
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30\"" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![1]);
+        let (docids, with_overlay) = evaluate("NOT genre = comedy");
+        assert_eq!(docids, RoaringBitmap::from_iter([0]));
+        assert_eq!(with_overlay, RoaringBitmap::from_iter([0, 2]));
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30'" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![2]);
+        let (docids, with_overlay) = evaluate("genre IN [comedy, drama] OR price > 100");
+        assert_eq!(docids, RoaringBitmap::from_iter([1]));
+        assert_eq!(with_overlay, docids);
     }
 
     #[test]
-    fn zero_radius() {
+    fn evaluate_with_columns() {
         let index = TempIndex::new();
-
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(hashset! { S("_geo") });
+                settings.set_filterable_fields(hashset! { S("genre"), S("price"), S("year") });
             })
             .unwrap();
 
-        index
-            .add_documents(documents!([
-              {
-                "id": 1,
-                "name": "Nàpiz' Milano",
-                "address": "Viale Vittorio Veneto, 30, 20124, Milan, Italy",
-                "type": "pizza",
-                "rating": 9,
-                "_geo": {
-                  "lat": 45.4777599,
-                  "lng": 9.1967508
-                }
-              },
-              {
-                "id": 2,
-                "name": "Artico Gelateria Tradizionale",
-                "address": "Via Dogana, 1, 20123 Milan, Italy",
-                "type": "ice cream",
-                "rating": 10,
-                "_geo": {
-                  "lat": 45.4632046,
-                  "lng": 9.1719421
+        let documents: Vec<_> = (0..500u32)
+            .map(|id| {
+                let price = (id * 37 % 101) as f64 / 4.0;
+                let genre = ["horror", "comedy", "drama"][id as usize % 3];
+                match id % 5 {
+                    0 => serde_json::json!({ "id": id, "genre": genre, "price": [price, id] }),
+                    1 => serde_json::json!({ "id": id, "genre": genre }),
+                    _ => serde_json::json!({ "id": id, "genre": genre, "price": price }),
                 }
-              },
-            ]))
-            .unwrap();
+            })
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        let mut columns = FacetColumns::new();
+        columns.load(&rtxn, &index, "price").unwrap().load(&rtxn, &index, "unknown").unwrap();
 
-        let mut search = crate::Search::new(&rtxn, &index);
+        let evaluate = |expression: &str| {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).map_err(|e| e.to_string());
+            let with_columns =
+                filter.evaluate_with_columns(&rtxn, &index, &columns).map_err(|e| e.to_string());
+            assert_eq!(docids, with_columns, "{expression}");
+            docids
+        };
 
-        search.filter(Filter::from_str("_geoRadius(45.4777599, 9.1967508, 0)").unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![0]);
+        for (from, to) in [(0.0, 25.0), (3.25, 12.5), (-4.0, 0.25), (24.5, 1000.0), (8.0, 2.0)] {
+            evaluate(&format!("price > {from}"));
+            evaluate(&format!("price >= {from} AND price < {to}"));
+            evaluate(&format!("price <= {to} OR genre = drama"));
+            evaluate(&format!("price {from} TO {to}"));
+            evaluate(&format!("NOT price ({from}, {to}]"));
+            evaluate(&format!("price [*, {to}) AND NOT genre IN [horror]"));
+        }
+
+        assert!(!evaluate("price 5 TO 10").unwrap().is_empty());
+        assert!(evaluate("price > 400 AND price EXISTS").unwrap().len() > 10);
+        assert!(evaluate("genre > 5").is_err());
     }
 
     #[test]
-    fn geo_radius_error() {
+    fn attribute_unicode_forms() {
         let index = TempIndex::new();
 
+        // the attribute is stored in the NFC form, with a single `é`
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
-                settings.set_filterable_fields(hashset! { S("_geo"), S("price") });
+                settings.set_filterable_fields(hashset! { S("caf\u{e9}") });
             })
             .unwrap();
 
+        index
+            .add_documents(documents!([
+                { "id": 0, "caf\u{e9}": "open" },
+                { "id": 1, "caf\u{e9}": "closed" },
+            ]))
+            .unwrap();
+
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
+        // the same attribute in the NFD form, an `e` followed by a combining acute accent
+        assert_eq!(evaluate("'cafe\u{301}' = open"), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate("caf\u{e9} = open"), RoaringBitmap::from_iter([0]));
+        assert_eq!(
+            evaluate("'cafe\u{301}' IN [closed] OR caf\u{e9} = open"),
+            RoaringBitmap::from_iter([0, 1])
         );
+    }
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
+    #[test]
+    fn exists_wildcard() {
+        let index = TempIndex::new();
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().contains(
-                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
-            ),
-            "{}",
-            error.to_string(),
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("author"), S("authors") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "author": { "name": "Victor Hugo" } },
+                { "id": 1, "author": { "email": "jules@verne.fr", "books": 54 } },
+                { "id": 2, "author": "Anonymous" },
+                { "id": 3, "authors": { "name": "Lewis Carroll" } },
+                { "id": 4 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str| {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index)
+        };
+
+        assert_eq!(evaluate("author.* EXISTS").unwrap(), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate("author.* NOT EXISTS").unwrap(), RoaringBitmap::from_iter([2, 3, 4]));
+        assert_eq!(
+            evaluate("author.* EXISTS AND NOT author.name EXISTS").unwrap(),
+            RoaringBitmap::from_iter([1])
         );
+        assert_eq!(evaluate("authors.* EXISTS").unwrap(), RoaringBitmap::from_iter([3]));
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        let error = evaluate("publisher.* EXISTS").unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute wildcard `publisher.*` does not match any attribute of this index."
         ));
+
+        // the evaluations reading the leaves one by one expand the wildcards too
+        let overlay = TempIndex::new();
+        overlay
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("author"), S("authors") });
+            })
+            .unwrap();
+        let overlay_rtxn = overlay.read_txn().unwrap();
+        let columns = FacetColumns::new();
+        for expression in ["author.* EXISTS", "author.* EXISTS AND NOT author.name EXISTS"] {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(filter.evaluate_with_columns(&rtxn, &index, &columns).unwrap(), expected);
+            let overlaid = filter.evaluate_with_overlay(&rtxn, &index, &overlay_rtxn, &overlay);
+            assert_eq!(overlaid.unwrap(), expected);
+            #[cfg(feature = "parallel")]
+            assert_eq!(filter.evaluate_prefetched(&rtxn, &index).unwrap(), expected);
+        }
     }
 
     #[test]
-    fn geo_bounding_box_error() {
+    fn evaluate_ordered() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
-                settings.set_filterable_fields(hashset! { S("_geo"), S("price") });
+                settings.set_filterable_fields(hashset! { S("price") });
             })
             .unwrap();
 
-        let rtxn = index.read_txn().unwrap();
-
-        // geoboundingbox top left coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-90.0000001, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
-        );
+        let documents: Vec<_> =
+            (0..100).map(|i| serde_json::json!({ "id": i, "price": (i * 37) % 100 })).collect();
+        index.add_documents(documents!(documents)).unwrap();
 
-        // geoboundingbox top left coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([90.0000001, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
-        );
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expression: &str| {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            filter.evaluate_ordered(&rtxn, &index).unwrap()
+        };
 
-        // geoboundingbox bottom right coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-90.0000001, 150])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
+        // two runs of the same filter, or with its operands swapped, give the same list
+        let first = evaluate("price > 90 OR price < 10");
+        assert_eq!(first, evaluate("price > 90 OR price < 10"));
+        assert_eq!(first, evaluate("price < 10 OR price > 90"));
+        assert!(first.windows(2).all(|window| window[0] < window[1]));
 
-        // geoboundingbox bottom right coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [90.0000001, 150])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
+        let filter = Filter::from_str("price > 90 OR price < 10").unwrap().unwrap();
+        let docids = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(first.len() as u64, docids.len());
+        assert_eq!(first, docids.iter().collect::<Vec<_>>());
+    }
 
-        // geoboundingbox top left coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-10, 180.000001], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
+    #[test]
+    fn interned_string_facets() {
+        let index = TempIndex::new();
 
-        // geoboundingbox top left coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-10, -180.000001], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("tag"), S("color") });
+            })
+            .unwrap();
 
-        // geoboundingbox bottom right coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-10, -180.000001])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
+        let document = |i: u32| {
+            let tags = match i % 3 {
+                0 => serde_json::json!(format!("Tag-{}", i % 37)),
+                1 => serde_json::json!([format!("tag-{}", i % 11), format!("tag-{}", i % 23)]),
+                _ => serde_json::json!(null),
+            };
+            let color = if i % 2 == 0 { "red" } else { "blue" };
+            serde_json::json!({ "id": i, "tag": tags, "color": color })
+        };
+        let documents: Vec<_> = (0..200).map(document).collect();
+        index.add_documents(documents!(documents)).unwrap();
 
-        // geoboundingbox bottom right coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-10, 180.000001])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
-    }
+        let filters = [
+            "tag = tag-3",
+            "tag = TAG-30",
+            "tag != tag-5",
+            "tag IN [tag-1, TAG-2, missing]",
+            "tag = missing",
+            "tag NOT IN [tag-0, tag-1] AND color = red",
+            "tag = tag-4 OR color = blue",
+        ];
+        let evaluate_all = || {
+            let rtxn = index.read_txn().unwrap();
+            filters
+                .iter()
+                .map(|filter| {
+                    let filter = Filter::from_str(filter).unwrap().unwrap();
+                    filter.evaluate(&rtxn, &index).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        let plain = evaluate_all();
 
-    #[test]
-    fn filter_depth() {
-        // generates a big (2 MiB) filter with too much of ORs.
-        let tipic_filter = "account_ids=14361 OR ";
-        let mut filter_string = String::with_capacity(tipic_filter.len() * 14360);
-        for i in 1..=14361 {
-            let _ = write!(&mut filter_string, "account_ids={}", i);
-            if i != 14361 {
-                let _ = write!(&mut filter_string, " OR ");
-            }
-        }
+        index
+            .update_settings(|settings| {
+                settings.set_interned_string_facets(hashset! { S("tag") });
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let tag = index.fields_ids_map(&rtxn).unwrap().id("tag").unwrap();
+        let color = index.fields_ids_map(&rtxn).unwrap().id("color").unwrap();
+        assert_eq!(index.interned_string_facets_ids(&rtxn).unwrap(), hashset! { tag });
+        let value_id = index.facet_id_string_value_ids.get(&rtxn, &(tag, "tag-3")).unwrap();
+        assert!(value_id.is_some());
+        assert!(index.facet_id_string_value_ids.get(&rtxn, &(color, "red")).unwrap().is_none());
+        drop(rtxn);
+        assert_eq!(evaluate_all(), plain);
 
-        // Note: the filter used to be rejected for being too deep, but that is
-        // no longer the case
-        let filter = Filter::from_str(&filter_string).unwrap();
-        assert!(filter.is_some());
-    }
+        // the value ids of the modified values are updated with the documents
+        let documents: Vec<_> = (200..260).map(document).collect();
+        index.add_documents(documents!(documents)).unwrap();
+        index.add_documents(documents!([{ "id": 260, "tag": "fresh", "color": "red" }])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.facet_id_string_value_ids.get(&rtxn, &(tag, "tag-3")).unwrap(), value_id);
+        let fresh = index.facet_id_string_value_ids.get(&rtxn, &(tag, "fresh")).unwrap().unwrap();
+        let docids = index.facet_id_value_id_docids.get(&rtxn, &(tag, fresh)).unwrap();
+        assert_eq!(docids, Some(RoaringBitmap::from_iter([260])));
+        drop(rtxn);
+        index.delete_documents(vec![S("260")]);
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.facet_id_string_value_ids.get(&rtxn, &(tag, "fresh")).unwrap().is_none());
+        assert!(index.facet_id_value_id_docids.get(&rtxn, &(tag, fresh)).unwrap().is_none());
+        drop(rtxn);
+        let interned = evaluate_all();
 
-    #[test]
-    fn empty_filter() {
-        let option = Filter::from_str("     ").unwrap();
-        assert_eq!(option, None);
+        index
+            .update_settings(|settings| {
+                settings.reset_interned_string_facets();
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.facet_id_string_value_ids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_value_id_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        assert_eq!(evaluate_all(), interned);
+        assert_ne!(interned, plain);
     }
 
     #[test]
-    fn non_finite_float() {
+    fn number_locale() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("price")]); // to keep the fields order
                 settings.set_filterable_fields(hashset! { S("price") });
             })
             .unwrap();
-        index
-            .add_documents(documents!([
-                {
-                    "id": "test_1",
-                    "price": "inf"
-                },
-                {
-                    "id": "test_2",
-                    "price": "2000"
-                },
-                {
-                    "id": "test_3",
-                    "price": "infinity"
-                },
-            ]))
-            .unwrap();
+
+        index.add_documents(documents!([{ "id": 0, "price": 1.5 }])).unwrap();
 
         let rtxn = index.read_txn().unwrap();
-        let filter = Filter::from_str("price = inf").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(0));
-        let filter = Filter::from_str("price < inf").unwrap().unwrap();
-        assert!(matches!(
-            filter.evaluate(&rtxn, &index),
-            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
-        ));
+        let evaluate = |filter: &str, locale: NumberLocale| {
+            let filter = Filter::from_str_with_locale(filter, locale).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index)
+        };
 
-        let filter = Filter::from_str("price = NaN").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.is_empty());
-        let filter = Filter::from_str("price < NaN").unwrap().unwrap();
-        assert!(matches!(
-            filter.evaluate(&rtxn, &index),
-            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
-        ));
+        let error = evaluate("price > '1,2'", NumberLocale::new(',')).unwrap_err();
+        assert!(error.to_string().starts_with("invalid float literal: "), "{}", error);
+        assert!(error.to_string().contains("e.g. `1,5` is written `1.5`"), "{}", error);
+        // the position of the number is kept
+        assert!(error.to_string().ends_with(" price > '1,2'"), "{}", error);
 
-        let filter = Filter::from_str("price = infinity").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(2));
-        let filter = Filter::from_str("price < infinity").unwrap().unwrap();
-        assert!(matches!(
-            filter.evaluate(&rtxn, &index),
-            Err(crate::Error::UserError(crate::error::UserError::InvalidFilter(_)))
-        ));
+        let error = evaluate("price > twelve", NumberLocale::new('\'')).unwrap_err();
+        assert!(error.to_string().contains("e.g. `1'5` is written `1.5`"), "{}", error);
+
+        // the default locale keeps the message of the parser
+        let error = evaluate("price > '1,2'", NumberLocale::default()).unwrap_err();
+        assert!(error.to_string().starts_with("invalid float literal\n"), "{}", error);
+
+        // the numbers are parsed the same way, and the other errors are unchanged
+        assert_eq!(evaluate("price > 1.2", NumberLocale::new(',')).unwrap().len(), 1);
+        let error = evaluate("price > NaN", NumberLocale::new(',')).unwrap_err();
+        assert!(error.to_string().starts_with("Non finite floats are not supported"), "{}", error);
+
+        // the evaluations reading the leaves one by one word the errors for the locale too
+        let filter =
+            Filter::from_str_with_locale("price > '1,2' OR price < 1", NumberLocale::new(','))
+                .unwrap()
+                .unwrap();
+        let mut columns = FacetColumns::new();
+        columns.load(&rtxn, &index, "price").unwrap();
+        let errors = [
+            filter.evaluate_with_columns(&rtxn, &index, &FacetColumns::new()).unwrap_err(),
+            filter.evaluate_with_columns(&rtxn, &index, &columns).unwrap_err(),
+            filter.evaluate_with_overlay(&rtxn, &index, &rtxn, &index).unwrap_err(),
+            #[cfg(feature = "parallel")]
+            filter.evaluate_prefetched(&rtxn, &index).unwrap_err(),
+        ];
+        for error in errors {
+            assert!(error.to_string().contains("e.g. `1,5` is written `1.5`"), "{}", error);
+        }
     }
 
     #[test]
-    fn filter_number() {
+    fn in_ranges() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_primary_key("id".to_owned());
-                settings.set_filterable_fields(hashset! { S("id"), S("one"), S("two") });
+                settings.set_filterable_fields(hashset! { S("year") });
             })
             .unwrap();
 
-        let mut docs = vec![];
-        for i in 0..100 {
-            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
-        }
-
-        index.add_documents(documents!(docs)).unwrap();
+        let documents: Vec<_> =
+            (0..40).map(|i| serde_json::json!({ "id": i, "year": 1980 + i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
 
         let rtxn = index.read_txn().unwrap();
-        for i in 0..100 {
-            let filter_str = format!("id = {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter([i]));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id > {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter((i + 1)..100));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id < {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(0..i));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id <= {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(0..=i));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id >= {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(i..100));
-        }
-        for i in 0..100 {
-            for j in i..100 {
-                let filter_str = format!("id {i} TO {j}");
-                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-                let result = filter.evaluate(&rtxn, &index).unwrap();
-                assert_eq!(result, RoaringBitmap::from_iter(i..=j));
-            }
-        }
-        let filter = Filter::from_str("one >= 0 OR one <= 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::default());
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+        let years = |years: &[std::ops::RangeInclusive<u32>]| -> RoaringBitmap {
+            years.iter().cloned().flatten().map(|year| year - 1980).collect()
+        };
 
-        let filter = Filter::from_str("one = 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::default());
+        // disjoint ranges
+        assert_eq!(
+            evaluate("year IN [1990 TO 1995, 2000 TO 2005]"),
+            years(&[1990..=1995, 2000..=2005])
+        );
+        assert_eq!(
+            evaluate("year IN [1990 TO 1995, 2000 TO 2005]"),
+            evaluate("year 1990 TO 1995 OR year 2000 TO 2005")
+        );
+        assert_eq!(
+            evaluate("year IN [2015 TO 2030, 1970 TO 1981]"),
+            years(&[1980..=1981, 2015..=2019])
+        );
 
-        for i in 0..10 {
-            for j in i..10 {
-                let filter_str = format!("two {i} TO {j}");
-                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-                let result = filter.evaluate(&rtxn, &index).unwrap();
-                assert_eq!(
-                    result,
-                    RoaringBitmap::from_iter((0..100).filter(|x| (i..=j).contains(&(x % 10))))
-                );
-            }
-        }
-        let filter = Filter::from_str("two != 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
+        // overlapping ranges
+        assert_eq!(
+            evaluate("year IN [1990 TO 1995, 1993 TO 2000, 2000 TO 2002]"),
+            years(&[1990..=2002])
+        );
+        assert_eq!(
+            evaluate("year IN [1990 TO 1995, 2010 TO 2012, 1994 TO 1998, 1992]"),
+            years(&[1990..=1998, 2010..=2012])
+        );
+
+        // the values are matched next to the ranges
+        assert_eq!(evaluate("year IN [1990 TO 1991, 2005]"), years(&[1990..=1991, 2005..=2005]));
+        assert_eq!(evaluate("year NOT IN [1980 TO 1990, 1995 TO 2030]"), years(&[1991..=1994]));
     }
 }
@@ -0,0 +1,215 @@
+//! An in-memory store of facet values to test the logic of the filters without creating an
+//! index, see [`MockFacetStore`].
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::RangeBounds;
+
+use filter_parser::{Condition, FilterCondition, Token};
+use roaring::RoaringBitmap;
+use serde_json::Value;
+
+use super::{Filter, FilterError};
+use crate::{DocumentId, FieldId, FieldsIdsMap, Result};
+
+/// The facet values of a few documents, kept in memory.
+///
+/// It only exposes the read surface needed by the filters: the documents ids, the fields ids
+/// map, the filterable fields and the documents of the facet values. The comparisons, `EXISTS`,
+/// `IN`, `NOT`, `AND` and `OR` are evaluated like by [`Filter::evaluate`], the other conditions
+/// are not supported and make the evaluation panic.
+#[derive(Debug, Default)]
+pub struct MockFacetStore {
+    documents_ids: RoaringBitmap,
+    fields_ids_map: FieldsIdsMap,
+    filterable_fields: HashSet<String>,
+    numbers: BTreeMap<FieldId, Vec<(f64, DocumentId)>>,
+    strings: BTreeMap<(FieldId, String), RoaringBitmap>,
+}
+
+impl MockFacetStore {
+    pub fn new<'f>(filterable_fields: impl IntoIterator<Item = &'f str>) -> Self {
+        MockFacetStore {
+            filterable_fields: filterable_fields.into_iter().map(String::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the top-level fields of `document` as facet values, the arrays being flattened
+    /// like by the indexing.
+    pub fn add_document(&mut self, docid: DocumentId, document: Value) -> &mut Self {
+        self.documents_ids.insert(docid);
+        let Value::Object(document) = document else { panic!("documents must be objects") };
+        for (field, value) in document {
+            let field_id = self.fields_ids_map.insert(&field).unwrap();
+            let values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                match value {
+                    Value::Number(number) => {
+                        let number = number.as_f64().unwrap();
+                        self.numbers.entry(field_id).or_default().push((number, docid));
+                    }
+                    Value::String(string) => {
+                        let key = (field_id, crate::normalize_facet(&string));
+                        self.strings.entry(key).or_default().insert(docid);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        self
+    }
+
+    pub fn documents_ids(&self) -> &RoaringBitmap {
+        &self.documents_ids
+    }
+
+    pub fn fields_ids_map(&self) -> &FieldsIdsMap {
+        &self.fields_ids_map
+    }
+
+    pub fn filterable_fields(&self) -> &HashSet<String> {
+        &self.filterable_fields
+    }
+
+    /// Returns the documents containing a number of the field within `range`.
+    pub fn facet_id_f64_docids(
+        &self,
+        field_id: FieldId,
+        range: impl RangeBounds<f64>,
+    ) -> RoaringBitmap {
+        let numbers = self.numbers.get(&field_id).map_or(&[][..], Vec::as_slice);
+        numbers
+            .iter()
+            .filter(|(number, _)| range.contains(number))
+            .map(|(_, docid)| docid)
+            .collect()
+    }
+
+    /// Returns the documents containing the normalized string `value` in the field.
+    pub fn facet_id_string_docids(&self, field_id: FieldId, value: &str) -> RoaringBitmap {
+        self.strings.get(&(field_id, value.to_string())).cloned().unwrap_or_default()
+    }
+
+    pub fn evaluate(&self, filter: &Filter) -> Result<RoaringBitmap> {
+        self.evaluate_condition(&filter.condition)
+    }
+
+    fn evaluate_condition(&self, condition: &FilterCondition) -> Result<RoaringBitmap> {
+        match condition {
+            FilterCondition::Not(condition) => {
+                Ok(&self.documents_ids - self.evaluate_condition(condition)?)
+            }
+            FilterCondition::Or(conditions) => {
+                let mut docids = RoaringBitmap::new();
+                for condition in conditions {
+                    docids |= self.evaluate_condition(condition)?;
+                }
+                Ok(docids)
+            }
+            FilterCondition::And(conditions) => {
+                let mut conditions = conditions.iter();
+                let mut docids = match conditions.next() {
+                    Some(condition) => self.evaluate_condition(condition)?,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+                for condition in conditions {
+                    docids &= self.evaluate_condition(condition)?;
+                }
+                Ok(docids)
+            }
+            FilterCondition::Const(true) => Ok(self.documents_ids.clone()),
+            FilterCondition::Const(false) => Ok(RoaringBitmap::new()),
+            FilterCondition::In { fid, els } => {
+                let mut docids = RoaringBitmap::new();
+                for el in els {
+                    docids |= self.evaluate_operator(fid, &Condition::Equal(el.clone()))?;
+                }
+                Ok(docids)
+            }
+            FilterCondition::Condition { fid, op } => self.evaluate_operator(fid, op),
+            condition => panic!("the mock facet store doesn't support `{condition:?}`"),
+        }
+    }
+
+    fn evaluate_operator(&self, fid: &Token, op: &Condition) -> Result<RoaringBitmap> {
+        if !crate::is_faceted(fid.value(), &self.filterable_fields) {
+            return Err(fid.as_external_error(FilterError::AttributeNotFilterable {
+                attribute: fid.value(),
+                filterable_fields: self.filterable_fields.clone(),
+            }))?;
+        }
+        let field_id = match self.fields_ids_map.id(fid.value()) {
+            Some(field_id) => field_id,
+            None => return Ok(RoaringBitmap::new()),
+        };
+
+        let number = |token: &Token| token.parse_finite_float();
+        let range =
+            |from: Bound<f64>, to: Bound<f64>| self.facet_id_f64_docids(field_id, (from, to));
+        Ok(match op {
+            Condition::Equal(value) => {
+                let mut docids =
+                    self.facet_id_string_docids(field_id, &crate::normalize_facet(value.value()));
+                if let Ok(number) = number(value) {
+                    docids |= range(Included(number), Included(number));
+                }
+                docids
+            }
+            Condition::NotEqual(value) => {
+                &self.documents_ids
+                    - self.evaluate_operator(fid, &Condition::Equal(value.clone()))?
+            }
+            Condition::GreaterThan(value) => range(Excluded(number(value)?), Unbounded),
+            Condition::GreaterThanOrEqual(value) => range(Included(number(value)?), Unbounded),
+            Condition::LowerThan(value) => range(Unbounded, Excluded(number(value)?)),
+            Condition::LowerThanOrEqual(value) => range(Unbounded, Included(number(value)?)),
+            Condition::Between { from, to } => {
+                range(Included(number(from)?), Included(number(to)?))
+            }
+            Condition::Exists => {
+                let mut docids = range(Unbounded, Unbounded);
+                for ((id, _), string_docids) in &self.strings {
+                    if *id == field_id {
+                        docids |= string_docids;
+                    }
+                }
+                docids
+            }
+            op => panic!("the mock facet store doesn't support `{op:?}`"),
+        })
+    }
+}
+
+#[test]
+fn range_and_equality() {
+    use serde_json::json;
+
+    let mut store = MockFacetStore::new(["price", "color"]);
+    store
+        .add_document(0, json!({ "price": 5, "color": "Red" }))
+        .add_document(1, json!({ "price": 15, "color": "blue" }))
+        .add_document(2, json!({ "price": [10, 25], "color": ["red", "green"] }))
+        .add_document(3, json!({ "name": "kefir" }));
+    assert_eq!(store.documents_ids().len(), 4);
+    assert_eq!(store.fields_ids_map().len(), 3);
+    assert!(!store.filterable_fields().contains("name"));
+
+    let evaluate = |expression: &str| {
+        let filter = Filter::from_str(expression).unwrap().unwrap();
+        store.evaluate(&filter).unwrap()
+    };
+
+    assert_eq!(evaluate("price 10 TO 20"), RoaringBitmap::from_iter([1, 2]));
+    assert_eq!(evaluate("price > 20"), RoaringBitmap::from_iter([2]));
+    assert_eq!(evaluate("color = red"), RoaringBitmap::from_iter([0, 2]));
+    assert_eq!(evaluate("color != red"), RoaringBitmap::from_iter([1, 3]));
+    assert_eq!(evaluate("color = red AND price < 10"), RoaringBitmap::from_iter([0]));
+    assert_eq!(evaluate("NOT price EXISTS OR color IN [blue]"), RoaringBitmap::from_iter([1, 3]));
+
+    let filter = Filter::from_str("name = kefir").unwrap().unwrap();
+    assert!(store.evaluate(&filter).is_err());
+}
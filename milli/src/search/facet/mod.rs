@@ -1,20 +1,35 @@
+pub use facet_range_search::facet_range_iter;
 pub use facet_sort_ascending::ascending_facet_sort;
 pub use facet_sort_descending::descending_facet_sort;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
 use heed::types::{Bytes, DecodeIgnore};
 use heed::{BytesDecode, RoTxn};
 use roaring::RoaringBitmap;
 
+pub use self::facet_column::FacetColumns;
 pub use self::facet_distribution::{FacetDistribution, OrderBy, DEFAULT_VALUES_PER_FACET};
-pub use self::filter::{BadGeoError, Filter};
-use crate::heed_codec::facet::{FacetGroupKeyCodec, FacetGroupValueCodec, OrderedF64Codec};
+pub use self::facet_metrics::FacetMetrics;
+pub use self::filter::{
+    BadGeoError, EmptyReason, Filter, HasAllDuplicates, InListOrder, LazyFilter, LeafKind,
+    LeafOperator, NumberLocale, NumberType,
+};
+pub use self::read_txn_pool::{ReadTxnPool, ReadTxnSource};
+use crate::heed_codec::facet::{
+    FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, OrderedF64Codec,
+};
 use crate::heed_codec::BytesRefCodec;
 use crate::{Index, Result};
+mod facet_column;
 mod facet_distribution;
 mod facet_distribution_iter;
+mod facet_metrics;
 mod facet_range_search;
 mod facet_sort_ascending;
 mod facet_sort_descending;
 mod filter;
+mod read_txn_pool;
 
 fn facet_extreme_value<'t>(
     mut extreme_it: impl Iterator<Item = heed::Result<(RoaringBitmap, &'t [u8])>> + 't,
@@ -50,6 +65,317 @@ pub fn facet_max_value<'t>(
     facet_extreme_value(it)
 }
 
+/// Counts the candidates for each integer value of this numeric field.
+///
+/// Only the level 0 of the database is read, which makes it simpler and faster than the
+/// general facet distribution for fields with few distinct values, e.g. ratings from 1 to 5.
+/// The values that are not integers and the values without candidates are ignored.
+pub fn facet_integer_distribution(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    candidates: &RoaringBitmap,
+) -> Result<BTreeMap<i64, u64>> {
+    let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+
+    let mut distribution = BTreeMap::new();
+    for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+        let (key, value) = result?;
+        if key.left_bound.fract() != 0.0 {
+            continue;
+        }
+        let count = value.bitmap.intersection_len(candidates);
+        if count != 0 {
+            distribution.insert(key.left_bound as i64, count);
+        }
+    }
+
+    Ok(distribution)
+}
+
+/// Returns the distinct numeric values of this field in ascending order, e.g. the years to
+/// list in a dropdown. With some `candidates`, only the values of the candidates are returned.
+///
+/// Only the level 0 of the database is read, until `limit` values are found, so the lowest
+/// values are returned for a field with more distinct values than that.
+pub fn facet_number_distinct_values(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    candidates: Option<&RoaringBitmap>,
+    limit: usize,
+) -> Result<Vec<f64>> {
+    let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+
+    let mut values = Vec::new();
+    for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+        if values.len() == limit {
+            break;
+        }
+        let (key, value) = result?;
+        if candidates.map_or(true, |candidates| !value.bitmap.is_disjoint(candidates)) {
+            values.push(key.left_bound);
+        }
+    }
+
+    Ok(values)
+}
+
+/// Returns the sum of the numeric values of this field over the candidates, e.g. the revenue
+/// of the matching documents.
+///
+/// The level 0 of the database is read once, each value being weighted by the number of
+/// candidates it contains. The distinct values of a document with several values are all
+/// summed.
+pub fn facet_number_sum(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    candidates: &RoaringBitmap,
+) -> Result<f64> {
+    facet_number_sum_and_count(index, rtxn, field_id, candidates).map(|(sum, _)| sum)
+}
+
+/// Returns the average of the numeric values of this field over the candidates, or `None`
+/// if none of them has a value.
+///
+/// Like [`facet_number_sum`], the distinct values of a document with several values are all
+/// counted.
+pub fn facet_number_average(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    candidates: &RoaringBitmap,
+) -> Result<Option<f64>> {
+    let (sum, count) = facet_number_sum_and_count(index, rtxn, field_id, candidates)?;
+    Ok((count != 0).then(|| sum / count as f64))
+}
+
+/// Returns the sum of the numeric values of this field over the candidates, and the number
+/// of values summed.
+fn facet_number_sum_and_count(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    candidates: &RoaringBitmap,
+) -> Result<(f64, u64)> {
+    let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+
+    let (mut sum, mut count) = (0.0, 0);
+    if candidates.is_empty() {
+        return Ok((sum, count));
+    }
+    for result in index.facet_id_f64_docids.range(rtxn, &(left..=right))? {
+        let (key, value) = result?;
+        let matching = value.bitmap.intersection_len(candidates);
+        sum += key.left_bound * matching as f64;
+        count += matching;
+    }
+
+    Ok((sum, count))
+}
+
+/// Returns the documents whose numeric value for this field lies between the `low`
+/// and `high` percentiles of the field values, e.g. `25.0` and `75.0`.
+///
+/// The percentiles use the nearest-rank method over the level 0 of the database, where
+/// each value weighs as many documents as it contains. When a percentile falls on a value
+/// shared by several documents, all of them are returned. Percentiles are clamped to
+/// `0..=100` and no document is returned if `low` is greater than `high`.
+pub fn facet_percentile_range(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    low: f64,
+    high: f64,
+) -> Result<RoaringBitmap> {
+    let (low, high) = (low.clamp(0.0, 100.0), high.clamp(0.0, 100.0));
+    // also handles the NaNs
+    if !(low <= high) {
+        return Ok(RoaringBitmap::new());
+    }
+
+    let left = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level: 0, left_bound: f64::MAX };
+    let level_zero = left..=right;
+
+    // first pass: count the documents of every value
+    let mut counts = Vec::new();
+    for result in index.facet_id_f64_docids.range(rtxn, &level_zero)? {
+        let (_, value) = result?;
+        counts.push(value.bitmap.len());
+    }
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return Ok(RoaringBitmap::new());
+    }
+
+    // the position of the value at which the cumulative count reaches the percentile's rank
+    let position_of = |percentile: f64| {
+        let rank = ((percentile / 100.0 * total as f64).ceil() as u64).max(1);
+        let mut cumulative = 0;
+        counts
+            .iter()
+            .position(|count| {
+                cumulative += count;
+                cumulative >= rank
+            })
+            .unwrap_or(counts.len() - 1)
+    };
+    let (first, last) = (position_of(low), position_of(high));
+
+    // second pass: gather the documents of the values in between
+    let mut docids = RoaringBitmap::new();
+    for result in
+        index.facet_id_f64_docids.range(rtxn, &level_zero)?.skip(first).take(last - first + 1)
+    {
+        let (_, value) = result?;
+        docids |= value.bitmap;
+    }
+
+    Ok(docids)
+}
+
+/// Returns the `(min, max, count)` band of every group of this level of the numeric facet
+/// database, in ascending order, e.g. to display coarse price ranges.
+///
+/// The level 0 holds one band per value. Higher levels hold the groups already aggregated by
+/// the indexing, so the bands are neither recomputed nor of equal width. The count is the
+/// number of documents of the group, and a document with several values in the same group is
+/// counted once. No band is returned for a level that does not exist.
+pub fn facet_level_ranges(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    level: u8,
+) -> Result<Vec<(f64, f64, u64)>> {
+    let db = index.facet_id_f64_docids;
+    let left = FacetGroupKey { field_id, level, left_bound: f64::MIN };
+    let right = FacetGroupKey { field_id, level, left_bound: f64::MAX };
+
+    let mut ranges = Vec::new();
+    for result in db.range(rtxn, &(left..=right))? {
+        let (key, value) = result?;
+
+        // the maximum is the last value of level 0 below the group, found by following
+        // the last child of each group down the levels
+        let mut max = key.left_bound;
+        let mut size = value.size;
+        for level in (0..key.level).rev() {
+            let left = FacetGroupKey { field_id, level, left_bound: max };
+            let right = FacetGroupKey { field_id, level, left_bound: f64::MAX };
+            let last_child = db.range(rtxn, &(left..=right))?.take(size as usize).last();
+            if let Some(last_child) = last_child {
+                let (key, value) = last_child?;
+                max = key.left_bound;
+                size = value.size;
+            }
+        }
+
+        ranges.push((key.left_bound, max, value.bitmap.len()));
+    }
+
+    Ok(ranges)
+}
+
+/// Returns the documents with a number value for this field within the two bounds, without
+/// descending more than `max_levels_down` levels below the highest level of the database.
+///
+/// This bounds the number of bitmaps read for wide ranges, on memory-constrained deployments,
+/// at the cost of precision: the returned documents are a superset of the exact result, and
+/// the boolean is `true` when they may contain documents outside the bounds.
+pub fn facet_number_range_approximate(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    left: Bound<f64>,
+    right: Bound<f64>,
+    max_levels_down: u8,
+) -> Result<(RoaringBitmap, bool)> {
+    let mut docids = RoaringBitmap::new();
+    let approximate =
+        facet_range_search::find_approximate_docids_of_facet_within_bounds::<OrderedF64Codec>(
+            rtxn,
+            index.facet_id_f64_docids,
+            field_id,
+            &left,
+            &right,
+            max_levels_down,
+            &mut docids,
+            &mut facet_metrics::NoopFacetMetrics,
+        )?;
+    Ok((docids, approximate))
+}
+
+/// Returns a **superset** of the documents with a number value for this field within the two
+/// bounds, read from the highest level of the database only.
+///
+/// Each group of the highest level overlapping the bounds is taken whole, so the result may
+/// contain documents whose values are all outside the bounds, but never misses a matching one.
+/// It is a cheap pre-check before the precise evaluation, e.g. to skip it when the superset is
+/// empty or disjoint from the candidates, and must not be returned as the result of a filter.
+pub fn facet_number_range_superset(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    left: Bound<f64>,
+    right: Bound<f64>,
+) -> Result<RoaringBitmap> {
+    facet_number_range_approximate(index, rtxn, field_id, left, right, 0).map(|(docids, _)| docids)
+}
+
+/// Returns the documents with a number value for this field within the two bounds, with the
+/// lowest and the highest of the values they matched, e.g. to tighten a slider around the
+/// matching documents. The extremes are the stored values, not the bounds, and are `None` when
+/// no value is within the bounds.
+///
+/// The extremes are the first and the last values of the level 0 within the bounds, each found
+/// with a single lookup, and the documents are read like by a range filter.
+pub fn facet_number_range_with_extremes(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: u16,
+    left: Bound<f64>,
+    right: Bound<f64>,
+) -> Result<(RoaringBitmap, Option<(f64, f64)>)> {
+    let db = index.facet_id_f64_docids;
+    let key = |left_bound| FacetGroupKey { field_id, level: 0, left_bound };
+    let start = match left {
+        Bound::Included(left) => Bound::Included(key(left)),
+        Bound::Excluded(left) => Bound::Excluded(key(left)),
+        Bound::Unbounded => Bound::Included(key(f64::MIN)),
+    };
+    let end = match right {
+        Bound::Included(right) => Bound::Included(key(right)),
+        Bound::Excluded(right) => Bound::Excluded(key(right)),
+        Bound::Unbounded => Bound::Included(key(f64::MAX)),
+    };
+
+    let min = match db.range(rtxn, &(start, end))?.next() {
+        Some(result) => result?.0.left_bound,
+        None => return Ok((RoaringBitmap::new(), None)),
+    };
+    let max = match db.rev_range(rtxn, &(start, end))?.next() {
+        Some(result) => result?.0.left_bound,
+        None => min,
+    };
+
+    let mut docids = RoaringBitmap::new();
+    facet_range_search::find_docids_of_facet_within_bounds::<OrderedF64Codec>(
+        rtxn,
+        db,
+        field_id,
+        &left,
+        &right,
+        &mut docids,
+        &mut facet_metrics::NoopFacetMetrics,
+    )?;
+    Ok((docids, Some((min, max))))
+}
+
 /// Get the first facet value in the facet database
 pub(crate) fn get_first_facet_value<'t, BoundCodec>(
     txn: &'t RoTxn,
@@ -119,11 +445,22 @@ pub(crate) fn get_highest_level<'t>(
 
 #[cfg(test)]
 pub(crate) mod tests {
+    use std::collections::BTreeMap;
+    use std::ops::{Bound, RangeBounds};
+
+    use big_s::S;
+    use maplit::hashset;
     use rand::{Rng, SeedableRng};
     use roaring::RoaringBitmap;
 
+    use super::{
+        facet_integer_distribution, facet_level_ranges, facet_number_average,
+        facet_number_distinct_values, facet_number_range_superset,
+        facet_number_range_with_extremes, facet_number_sum, facet_percentile_range,
+    };
     use crate::heed_codec::facet::OrderedF64Codec;
     use crate::heed_codec::StrRefCodec;
+    use crate::index::tests::TempIndex;
     use crate::update::facet::test_helpers::FacetIndex;
 
     pub fn get_simple_index() -> FacetIndex<OrderedF64Codec> {
@@ -221,4 +558,339 @@ pub(crate) mod tests {
         txn.commit().unwrap();
         index
     }
+
+    #[test]
+    fn percentile_range() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("rating") });
+            })
+            .unwrap();
+
+        // the prices are uniformly distributed from 1 to 100, the ratings are mostly ties
+        let documents: Vec<_> = (0..100)
+            .map(|i| {
+                let rating = match i {
+                    0 => 1,
+                    1..=80 => 2,
+                    _ => 3,
+                };
+                serde_json::json!({ "id": i, "price": i + 1, "rating": rating })
+            })
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let price = fields_ids_map.id("price").unwrap();
+        let rating = fields_ids_map.id("rating").unwrap();
+
+        let result = facet_percentile_range(&index, &rtxn, price, 25.0, 75.0).unwrap();
+        assert_eq!(result, (24..=74).collect());
+
+        let result = facet_percentile_range(&index, &rtxn, price, 0.0, 100.0).unwrap();
+        assert_eq!(result, (0..100).collect());
+
+        let result = facet_percentile_range(&index, &rtxn, price, 90.0, 90.0).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter([89]));
+
+        // both percentiles fall on the same tied value, all its documents are returned
+        let result = facet_percentile_range(&index, &rtxn, rating, 25.0, 75.0).unwrap();
+        assert_eq!(result, (1..=80).collect());
+
+        let result = facet_percentile_range(&index, &rtxn, rating, 75.0, 90.0).unwrap();
+        assert_eq!(result, (1..100).collect());
+
+        let result = facet_percentile_range(&index, &rtxn, price, 75.0, 25.0).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn integer_distribution() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("rating") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "rating": 5 },
+                { "id": 1, "rating": 4 },
+                { "id": 2, "rating": 5 },
+                { "id": 3, "rating": 1 },
+                { "id": 4, "rating": 4.5 },
+                { "id": 5, "rating": [3, 5] },
+                { "id": 6, "rating": "five" },
+                { "id": 7 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let rating = index.fields_ids_map(&rtxn).unwrap().id("rating").unwrap();
+
+        let all = index.documents_ids(&rtxn).unwrap();
+        let distribution = facet_integer_distribution(&index, &rtxn, rating, &all).unwrap();
+        assert_eq!(distribution, BTreeMap::from([(1, 1), (3, 1), (4, 1), (5, 3)]));
+
+        let candidates = RoaringBitmap::from_iter([1, 2, 4, 5, 6]);
+        let distribution = facet_integer_distribution(&index, &rtxn, rating, &candidates).unwrap();
+        assert_eq!(distribution, BTreeMap::from([(3, 1), (4, 1), (5, 2)]));
+
+        let distribution =
+            facet_integer_distribution(&index, &rtxn, rating, &RoaringBitmap::new()).unwrap();
+        assert!(distribution.is_empty());
+    }
+
+    #[test]
+    fn number_distinct_values() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "year": 2021 },
+                { "id": 1, "year": 1999 },
+                { "id": 2, "year": 2021 },
+                { "id": 3, "year": [2005, 2010] },
+                { "id": 4, "year": 1999.5 },
+                { "id": 5, "year": "2030" },
+                { "id": 6 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let year = index.fields_ids_map(&rtxn).unwrap().id("year").unwrap();
+
+        let values = facet_number_distinct_values(&index, &rtxn, year, None, 100).unwrap();
+        assert_eq!(values, [1999., 1999.5, 2005., 2010., 2021.]);
+
+        let candidates = RoaringBitmap::from_iter([2, 3, 5, 6]);
+        let values =
+            facet_number_distinct_values(&index, &rtxn, year, Some(&candidates), 100).unwrap();
+        assert_eq!(values, [2005., 2010., 2021.]);
+
+        // the lowest values are kept when there are more than the limit
+        let values = facet_number_distinct_values(&index, &rtxn, year, None, 2).unwrap();
+        assert_eq!(values, [1999., 1999.5]);
+        let values =
+            facet_number_distinct_values(&index, &rtxn, year, Some(&candidates), 1).unwrap();
+        assert_eq!(values, [2005.]);
+
+        let empty = RoaringBitmap::new();
+        let values = facet_number_distinct_values(&index, &rtxn, year, Some(&empty), 100).unwrap();
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn number_range_with_extremes() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let prices = |i: u32| -> Vec<f64> {
+            match i % 4 {
+                0 => vec![(i * 7 % 90) as f64 + 0.5],
+                1 => vec![(i * 13 % 90) as f64, (i * 3 % 90) as f64],
+                2 => vec![-(i as f64)],
+                _ => vec![],
+            }
+        };
+        let documents: Vec<_> =
+            (0..120).map(|i| serde_json::json!({ "id": i, "price": prices(i) })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let price = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+
+        for (left, right) in [
+            (Bound::Included(10.0), Bound::Excluded(40.0)),
+            (Bound::Excluded(-50.0), Bound::Included(0.5)),
+            (Bound::Unbounded, Bound::Included(-100.0)),
+            (Bound::Included(87.0), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Unbounded),
+        ] {
+            let matched: Vec<(u32, f64)> = (0..120)
+                .flat_map(|i| prices(i).into_iter().map(move |price| (i, price)))
+                .filter(|(_, price)| (left, right).contains(price))
+                .collect();
+            let expected_docids: RoaringBitmap = matched.iter().map(|(i, _)| *i).collect();
+            let expected_min = matched.iter().map(|(_, price)| *price).reduce(f64::min);
+            let expected_max = matched.iter().map(|(_, price)| *price).reduce(f64::max);
+
+            let (docids, extremes) =
+                facet_number_range_with_extremes(&index, &rtxn, price, left, right).unwrap();
+            assert_eq!(docids, expected_docids);
+            assert_eq!(extremes.map(|(min, _)| min), expected_min);
+            assert_eq!(extremes.map(|(_, max)| max), expected_max);
+        }
+
+        // the extremes are the stored values, not the bounds
+        let (_, extremes) = facet_number_range_with_extremes(
+            &index,
+            &rtxn,
+            price,
+            Bound::Included(-1000.0),
+            Bound::Included(1000.0),
+        )
+        .unwrap();
+        assert_eq!(extremes, Some((-118.0, 88.5)));
+
+        let (docids, extremes) = facet_number_range_with_extremes(
+            &index,
+            &rtxn,
+            price,
+            Bound::Excluded(88.5),
+            Bound::Unbounded,
+        )
+        .unwrap();
+        assert!(docids.is_empty());
+        assert_eq!(extremes, None);
+    }
+
+    #[test]
+    fn number_range_superset() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..300)
+            .map(|i: u32| serde_json::json!({ "id": i, "price": [(i * 7 % 250) as f64, i as f64 / 3.0] }))
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let price = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+
+        let mut approximate = false;
+        for (left, right) in [
+            (Bound::Included(10.0), Bound::Excluded(40.0)),
+            (Bound::Excluded(99.5), Bound::Included(100.5)),
+            (Bound::Unbounded, Bound::Included(3.0)),
+            (Bound::Included(240.0), Bound::Unbounded),
+            (Bound::Included(1000.0), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Unbounded),
+        ] {
+            let (exact, _) =
+                facet_number_range_with_extremes(&index, &rtxn, price, left, right).unwrap();
+            let superset = facet_number_range_superset(&index, &rtxn, price, left, right).unwrap();
+            assert!(superset.is_superset(&exact));
+            approximate |= superset != exact;
+        }
+        // the groups of the highest level are too wide for the narrow ranges to be exact
+        assert!(approximate);
+    }
+
+    #[test]
+    fn number_sum_and_average() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("revenue") });
+            })
+            .unwrap();
+
+        // some documents have several revenues, a string revenue or no revenue at all
+        let revenues = |i: u32| -> Vec<f64> {
+            match i % 5 {
+                0 => vec![],
+                1 => vec![i as f64 * 1.5],
+                2 => vec![i as f64, 0.25],
+                3 => vec![-(i as f64)],
+                _ => vec![(i % 7) as f64],
+            }
+        };
+        let documents: Vec<_> = (0..200)
+            .map(|i| match i % 5 {
+                0 if i % 2 == 0 => serde_json::json!({ "id": i, "revenue": "unknown" }),
+                0 => serde_json::json!({ "id": i }),
+                _ => serde_json::json!({ "id": i, "revenue": revenues(i) }),
+            })
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let revenue = index.fields_ids_map(&rtxn).unwrap().id("revenue").unwrap();
+
+        for candidates in [
+            RoaringBitmap::from_iter(0..200),
+            RoaringBitmap::from_iter((0..200).filter(|i| i % 3 == 0)),
+            RoaringBitmap::from_iter([2, 7, 12]),
+        ] {
+            let values: Vec<f64> = candidates.iter().flat_map(revenues).collect();
+            let expected: f64 = values.iter().sum();
+
+            let sum = facet_number_sum(&index, &rtxn, revenue, &candidates).unwrap();
+            assert!((sum - expected).abs() < 1e-6, "{sum} != {expected}");
+
+            let average = facet_number_average(&index, &rtxn, revenue, &candidates).unwrap();
+            let expected = expected / values.len() as f64;
+            assert!((average.unwrap() - expected).abs() < 1e-6);
+        }
+
+        let candidates = RoaringBitmap::from_iter([0, 5, 10]);
+        assert_eq!(facet_number_sum(&index, &rtxn, revenue, &candidates).unwrap(), 0.0);
+        assert_eq!(facet_number_average(&index, &rtxn, revenue, &candidates).unwrap(), None);
+    }
+
+    #[test]
+    fn level_ranges() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        // 100 distinct prices from 0 to 990, and one document with two prices in the same band
+        let mut documents: Vec<_> =
+            (0..100).map(|i| serde_json::json!({ "id": i, "price": i * 10 })).collect();
+        documents.push(serde_json::json!({ "id": 100, "price": [0, 10] }));
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let price = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+
+        let level_zero = facet_level_ranges(&index, &rtxn, price, 0).unwrap();
+        assert_eq!(level_zero.len(), 100);
+        assert_eq!(level_zero[0], (0.0, 0.0, 2));
+        assert_eq!(level_zero[1], (10.0, 10.0, 2));
+        assert_eq!(level_zero[99], (990.0, 990.0, 1));
+
+        // the groups of level 1 gather 4 consecutive values
+        let level_one = facet_level_ranges(&index, &rtxn, price, 1).unwrap();
+        assert_eq!(level_one.len(), 25);
+        assert_eq!(level_one[0], (0.0, 30.0, 5));
+        assert_eq!(level_one[1], (40.0, 70.0, 4));
+        assert_eq!(level_one[24], (960.0, 990.0, 4));
+        assert_eq!(level_one.iter().map(|(_, _, count)| count).sum::<u64>(), 101);
+        for window in level_one.windows(2) {
+            assert!(window[0].1 < window[1].0);
+        }
+
+        // the bands of a higher level follow the groups below them
+        let level_two = facet_level_ranges(&index, &rtxn, price, 2).unwrap();
+        assert_eq!(level_two[0], (0.0, 150.0, 17));
+
+        assert!(facet_level_ranges(&index, &rtxn, price, 10).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,80 @@
+//! The read transactions lent to the evaluations of the filters, e.g. by a server reusing them
+//! between its requests, see [`ReadTxnSource`].
+
+use std::sync::Mutex;
+
+use heed::RoTxn;
+
+use crate::{Index, Result};
+
+/// Lends the read transactions used by [`Filter::evaluate_pooled`](crate::Filter::evaluate_pooled)
+/// and, with the `parallel` feature, by each thread of
+/// [`Filter::evaluate_prefetched_pooled`](crate::Filter::evaluate_prefetched_pooled).
+///
+/// A closure returning a fresh transaction is a source, and [`ReadTxnPool`] keeps the given back
+/// transactions to lend them again.
+pub trait ReadTxnSource<'e>: Sync {
+    /// Returns a read transaction on the index the filter is evaluated on.
+    fn take(&self) -> Result<RoTxn<'e>>;
+
+    /// Takes back a transaction returned by [`ReadTxnSource::take`] once the evaluation is done
+    /// with it. It is dropped by default.
+    fn give_back(&self, rtxn: RoTxn<'e>) {
+        drop(rtxn);
+    }
+}
+
+impl<'e, F> ReadTxnSource<'e> for F
+where
+    F: Fn() -> Result<RoTxn<'e>> + Sync,
+{
+    fn take(&self) -> Result<RoTxn<'e>> {
+        self()
+    }
+}
+
+/// A pool of read transactions on an index, opening a new one when all of them are lent.
+///
+/// A read transaction sees the index as it was when it was opened: the pooled transactions
+/// don't see the documents indexed after them, and keep the pages of the older versions of
+/// the index from being reused. The pool must be [cleared](ReadTxnPool::clear) after each
+/// update of the index the evaluations must see.
+pub struct ReadTxnPool<'e> {
+    index: &'e Index,
+    rtxns: Mutex<Vec<RoTxn<'e>>>,
+}
+
+impl<'e> ReadTxnPool<'e> {
+    pub fn new(index: &'e Index) -> Self {
+        ReadTxnPool { index, rtxns: Mutex::new(Vec::new()) }
+    }
+
+    /// The number of transactions waiting to be lent.
+    pub fn len(&self) -> usize {
+        self.rtxns.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the transactions waiting to be lent, the next ones are opened on the current
+    /// version of the index.
+    pub fn clear(&self) {
+        self.rtxns.lock().unwrap().clear();
+    }
+}
+
+impl<'e> ReadTxnSource<'e> for ReadTxnPool<'e> {
+    fn take(&self) -> Result<RoTxn<'e>> {
+        let rtxn = self.rtxns.lock().unwrap().pop();
+        match rtxn {
+            Some(rtxn) => Ok(rtxn),
+            None => Ok(self.index.read_txn()?),
+        }
+    }
+
+    fn give_back(&self, rtxn: RoTxn<'e>) {
+        self.rtxns.lock().unwrap().push(rtxn);
+    }
+}
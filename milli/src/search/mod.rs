@@ -10,7 +10,11 @@ use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
 use tracing::error;
 
-pub use self::facet::{FacetDistribution, Filter, OrderBy, DEFAULT_VALUES_PER_FACET};
+pub use self::facet::{
+    EmptyReason, FacetColumns, FacetDistribution, FacetMetrics, Filter, HasAllDuplicates,
+    InListOrder, LazyFilter, LeafKind, LeafOperator, NumberLocale, NumberType, OrderBy,
+    ReadTxnPool, ReadTxnSource, DEFAULT_VALUES_PER_FACET,
+};
 pub use self::new::matches::{FormatOptions, MatchBounds, MatcherBuilder, MatchingWords};
 use self::new::{execute_vector_search, PartialSearchResult};
 use crate::error::UserError;
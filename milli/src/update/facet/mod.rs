@@ -76,7 +76,8 @@ pub const FACET_MAX_GROUP_SIZE: u8 = 8;
 pub const FACET_GROUP_SIZE: u8 = 4;
 pub const FACET_MIN_LEVEL_SIZE: u8 = 5;
 
-use std::collections::BTreeSet;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::BufReader;
 use std::iter::FromIterator;
@@ -84,18 +85,20 @@ use std::iter::FromIterator;
 use charabia::normalizer::{Normalize, NormalizerOption};
 use grenad::{CompressionType, SortAlgorithm};
 use heed::types::{Bytes, DecodeIgnore, SerdeJson};
-use heed::BytesEncode;
+use heed::{BytesDecode, BytesEncode};
 use time::OffsetDateTime;
 use tracing::debug;
 
 use self::incremental::FacetsUpdateIncremental;
 use super::FacetsUpdateBulk;
 use crate::facet::FacetType;
-use crate::heed_codec::facet::{FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec};
-use crate::heed_codec::BytesRefCodec;
-use crate::update::index_documents::create_sorter;
+use crate::heed_codec::facet::{
+    FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, FieldIdValueIdCodec,
+};
+use crate::heed_codec::{BytesRefCodec, StrRefCodec};
+use crate::update::index_documents::{create_sorter, valid_lmdb_key};
 use crate::update::merge_btreeset_string;
-use crate::{BEU16StrCodec, Index, Result, MAX_FACET_VALUE_LENGTH};
+use crate::{BEU16StrCodec, FieldId, Index, Result, MAX_FACET_VALUE_LENGTH};
 
 pub mod bulk;
 pub mod incremental;
@@ -144,16 +147,22 @@ impl<'i> FacetsUpdate<'i> {
         }
         debug!("Computing and writing the facet values levels docids into LMDB on disk...");
         self.index.set_updated_at(wtxn, &OffsetDateTime::now_utc())?;
+        self.index.bump_facet_generation(wtxn)?;
+
+        let (interned_values, delta_data) = match self.facet_type {
+            FacetType::String => interned_string_facets_delta(wtxn, self.index, self.delta_data)?,
+            FacetType::Number => (Vec::new(), self.delta_data),
+        };
 
         // See self::comparison_bench::benchmark_facet_indexing
-        if self.delta_data.len() >= (self.database.len(wtxn)? / 50) {
+        if delta_data.len() >= (self.database.len(wtxn)? / 50) {
             let field_ids =
                 self.index.faceted_fields_ids(wtxn)?.iter().copied().collect::<Vec<_>>();
             let bulk_update = FacetsUpdateBulk::new(
                 self.index,
                 field_ids,
                 self.facet_type,
-                self.delta_data,
+                delta_data,
                 self.group_size,
                 self.min_level_size,
             );
@@ -162,7 +171,7 @@ impl<'i> FacetsUpdate<'i> {
             let incremental_update = FacetsUpdateIncremental::new(
                 self.index,
                 self.facet_type,
-                self.delta_data,
+                delta_data,
                 self.group_size,
                 self.min_level_size,
                 self.max_group_size,
@@ -256,8 +265,132 @@ impl<'i> FacetsUpdate<'i> {
             self.index.facet_id_string_fst.put(wtxn, &field_id, &fst)?;
         }
 
-        Ok(())
+        update_interned_string_facets(wtxn, self.index, interned_values)
+    }
+}
+
+/// Returns the string facets of the interned fields that are modified by the `delta_data`,
+/// along with the `delta_data` itself.
+fn interned_string_facets_delta(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    delta_data: grenad::Reader<BufReader<File>>,
+) -> Result<(Vec<(FieldId, String)>, grenad::Reader<BufReader<File>>)> {
+    let interned_fields = index.interned_string_facets_ids(rtxn)?;
+    if interned_fields.is_empty() {
+        return Ok((Vec::new(), delta_data));
+    }
+
+    let mut values = Vec::new();
+    let mut cursor = delta_data.into_cursor()?;
+    while let Some((key, _)) = cursor.move_on_next()? {
+        if !valid_lmdb_key(key) {
+            continue;
+        }
+        let key =
+            FacetGroupKeyCodec::<StrRefCodec>::bytes_decode(key).map_err(heed::Error::Encoding)?;
+        if interned_fields.contains(&key.field_id) {
+            values.push((key.field_id, key.left_bound.to_string()));
+        }
+    }
+
+    Ok((values, cursor.into_reader()))
+}
+
+/// Writes the documents of the given string facets of the interned fields under their
+/// value id, after the `facet_id_string_docids` database has been updated.
+///
+/// The values that are not given an id yet get the one following the highest id of their
+/// field, and the values that no longer have any document lose their id.
+fn update_interned_string_facets(
+    wtxn: &mut heed::RwTxn,
+    index: &Index,
+    values: Vec<(FieldId, String)>,
+) -> Result<()> {
+    let mut next_value_ids = HashMap::new();
+    for (field_id, value) in values {
+        let key = FacetGroupKey { field_id, level: 0, left_bound: value.as_str() };
+        let docids = index.facet_id_string_docids.get(wtxn, &key)?.map(|value| value.bitmap);
+        let value_id = index.facet_id_string_value_ids.get(wtxn, &(field_id, value.as_str()))?;
+
+        match (docids, value_id) {
+            (Some(docids), value_id) if !docids.is_empty() => {
+                let value_id = match value_id {
+                    Some(value_id) => value_id,
+                    None => {
+                        let next_value_id = match next_value_ids.entry(field_id) {
+                            Entry::Occupied(entry) => entry.into_mut(),
+                            Entry::Vacant(entry) => {
+                                entry.insert(next_interned_value_id(wtxn, index, field_id)?)
+                            }
+                        };
+                        let value_id = *next_value_id;
+                        *next_value_id += 1;
+                        index.facet_id_string_value_ids.put(
+                            wtxn,
+                            &(field_id, value.as_str()),
+                            &value_id,
+                        )?;
+                        value_id
+                    }
+                };
+                index.facet_id_value_id_docids.put(wtxn, &(field_id, value_id), &docids)?;
+            }
+            (_, Some(value_id)) => {
+                index.facet_id_string_value_ids.delete(wtxn, &(field_id, value.as_str()))?;
+                index.facet_id_value_id_docids.delete(wtxn, &(field_id, value_id))?;
+            }
+            (_, None) => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the id following the highest value id of the interned field.
+fn next_interned_value_id(rtxn: &heed::RoTxn, index: &Index, field_id: FieldId) -> Result<u32> {
+    let prefix = field_id.to_be_bytes();
+    let mut iter = index
+        .facet_id_value_id_docids
+        .remap_types::<Bytes, DecodeIgnore>()
+        .rev_prefix_iter(rtxn, &prefix)?;
+    match iter.next().transpose()? {
+        Some((key, ())) => {
+            let (_, value_id) =
+                FieldIdValueIdCodec::bytes_decode(key).map_err(heed::Error::Decoding)?;
+            Ok(value_id + 1)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Gives a value id to the string facets of the interned fields and writes their documents
+/// under this id, from scratch.
+///
+/// This must run after every change of the interned fields, the updates of the string facets
+/// only keep the ids of the values they modify up to date.
+pub(crate) fn write_interned_string_facets(wtxn: &mut heed::RwTxn, index: &Index) -> Result<()> {
+    index.facet_id_string_value_ids.clear(wtxn)?;
+    index.facet_id_value_id_docids.clear(wtxn)?;
+
+    for field_id in index.interned_string_facets_ids(wtxn)? {
+        // As we can't read and write in the same transaction at the same time
+        // we first collect the level 0 of the field.
+        let left = FacetGroupKey { field_id, level: 0, left_bound: "" };
+        let end_of_level = FacetGroupKey { field_id, level: 1, left_bound: "" };
+        let mut values = Vec::new();
+        for result in index.facet_id_string_docids.range(wtxn, &(left..end_of_level))? {
+            let (key, value) = result?;
+            values.push((key.left_bound.to_string(), value.bitmap));
+        }
+
+        for (value_id, (value, docids)) in (0..).zip(values) {
+            index.facet_id_string_value_ids.put(wtxn, &(field_id, value.as_str()), &value_id)?;
+            index.facet_id_value_id_docids.put(wtxn, &(field_id, value_id), &docids)?;
+        }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
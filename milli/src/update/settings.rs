@@ -15,6 +15,7 @@ use crate::criterion::Criterion;
 use crate::error::UserError;
 use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
 use crate::proximity::ProximityPrecision;
+use crate::update::facet::write_interned_string_facets;
 use crate::update::index_documents::IndexDocumentsMethod;
 use crate::update::{IndexDocuments, UpdateIndexingStep};
 use crate::vector::settings::{check_set, check_unset, EmbedderSource, EmbeddingSettings};
@@ -130,6 +131,7 @@ pub struct Settings<'a, 't, 'i> {
     displayed_fields: Setting<Vec<String>>,
     filterable_fields: Setting<HashSet<String>>,
     sortable_fields: Setting<HashSet<String>>,
+    interned_string_facets: Setting<HashSet<String>>,
     criteria: Setting<Vec<Criterion>>,
     stop_words: Setting<BTreeSet<String>>,
     non_separator_tokens: Setting<BTreeSet<String>>,
@@ -164,6 +166,7 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             displayed_fields: Setting::NotSet,
             filterable_fields: Setting::NotSet,
             sortable_fields: Setting::NotSet,
+            interned_string_facets: Setting::NotSet,
             criteria: Setting::NotSet,
             stop_words: Setting::NotSet,
             non_separator_tokens: Setting::NotSet,
@@ -218,6 +221,16 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.sortable_fields = Setting::Reset;
     }
 
+    /// Sets the fields whose string facets are interned, see
+    /// [`Index::interned_string_facets`].
+    pub fn set_interned_string_facets(&mut self, names: HashSet<String>) {
+        self.interned_string_facets = Setting::Set(names);
+    }
+
+    pub fn reset_interned_string_facets(&mut self) {
+        self.interned_string_facets = Setting::Reset;
+    }
+
     pub fn reset_criteria(&mut self) {
         self.criteria = Setting::Reset;
     }
@@ -765,6 +778,20 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(())
     }
 
+    fn update_interned_string_facets(&mut self) -> Result<bool> {
+        let old_fields = self.index.interned_string_facets(self.wtxn)?;
+        match self.interned_string_facets {
+            Setting::Set(ref fields) => {
+                self.index.put_interned_string_facets(self.wtxn, fields)?;
+            }
+            Setting::Reset => {
+                self.index.delete_interned_string_facets(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(self.index.interned_string_facets(self.wtxn)? != old_fields)
+    }
+
     fn update_criteria(&mut self) -> Result<()> {
         match &self.criteria {
             Setting::Set(criteria) => {
@@ -1069,6 +1096,7 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         // 2. Only change the name -> embedder mapping on a name change
         // 3. Keep the old vectors but reattempt indexing on a prompt change: only actually changed prompt will need embedding + storage
         let embedding_configs_updated = self.update_embedding_configs()?;
+        let interned_string_facets_updated = self.update_interned_string_facets()?;
 
         if stop_words_updated
             || non_separator_tokens_updated
@@ -1084,6 +1112,12 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             self.reindex(&progress_callback, &should_abort, old_fields_ids_map)?;
         }
 
+        // The value ids are only written by the facets update, which doesn't run
+        // when the interned fields change without reindexing.
+        if interned_string_facets_updated || faceted_updated {
+            write_interned_string_facets(self.wtxn, self.index)?;
+        }
+
         Ok(())
     }
 }
@@ -2001,6 +2035,7 @@ mod tests {
                     displayed_fields,
                     filterable_fields,
                     sortable_fields,
+                    interned_string_facets,
                     criteria,
                     stop_words,
                     non_separator_tokens,
@@ -2024,6 +2059,7 @@ mod tests {
                 assert!(matches!(displayed_fields, Setting::NotSet));
                 assert!(matches!(filterable_fields, Setting::NotSet));
                 assert!(matches!(sortable_fields, Setting::NotSet));
+                assert!(matches!(interned_string_facets, Setting::NotSet));
                 assert!(matches!(criteria, Setting::NotSet));
                 assert!(matches!(stop_words, Setting::NotSet));
                 assert!(matches!(non_separator_tokens, Setting::NotSet));